@@ -2,7 +2,8 @@
 //!
 //! This module provides functionality for running Bend books and commands.
 //! It includes a `run` function that executes a command on a given Bend book
-//! and returns the result along with diagnostics.
+//! and returns the result along with diagnostics, or a `RunError` telling if
+//! the book failed to compile or to run.
 //!
 //! # Dependencies
 //!
@@ -10,10 +11,57 @@
 //! - `bend::fun`: For working with Bend books and terms
 //! - `bend`: For compilation and runtime options
 
-use bend::diagnostics::{Diagnostics, DiagnosticsConfig};
+use std::fmt::Display;
+use std::sync::Mutex;
+
+use bend::diagnostics::{Diagnostics, DiagnosticsConfig, Severity};
 use bend::fun::{Book, Term};
 use bend::{CompileOpts, RunOpts};
 
+use crate::errors::strip_colors;
+
+/// Bend writes the compiled HVM book to a fixed file before running it,
+/// so only one book can be run at a time.
+static RUN_LOCK: Mutex<()> = Mutex::new(());
+
+/// The stack of the thread that runs a book, unless a `RunConfig` sets another
+///
+/// Bend's compilation passes and readback are recursive, so big terms (like
+/// long lists) need more stack than the caller's thread usually has. 64 MiB
+/// is enough for the biggest terms the HVM takes and returns, lists of a few
+/// thousand elements, even in debug builds, so runs don't reserve a much
+/// bigger stack that they never use. `RunConfig.stack_size` can change it.
+pub const DEFAULT_STACK_SIZE: usize = 64 << 20;
+
+/// The ways running a book can fail, with the diagnostics reported by Bend
+#[derive(Debug)]
+pub enum RunError {
+    /// The book has errors and could not be compiled
+    Compile(Diagnostics),
+    /// The book was compiled, but the HVM failed to run it or its output could
+    /// not be read back
+    Runtime(Diagnostics),
+}
+
+impl RunError {
+    pub fn diagnostics(&self) -> &Diagnostics {
+        match self {
+            RunError::Compile(diags) | RunError::Runtime(diags) => diags,
+        }
+    }
+}
+
+impl Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = self
+            .diagnostics()
+            .display_with_severity(Severity::Error)
+            .to_string();
+
+        f.write_str(strip_colors(&text).trim_end())
+    }
+}
+
 /**
  Runs a command on a book and returns the result.
 
@@ -29,25 +77,124 @@ use bend::{CompileOpts, RunOpts};
  * `String` - The output of the command.
  * `Diagnostics` - Any diagnostics generated during the execution.
 
- # Panics
+ # Errors
 
- This function will panic if the HVM execution fails.
+ Returns a `RunError` if the book can't be compiled or the HVM execution fails.
 */
 pub fn run(
     book: &Book,
     cmd: &str,
-) -> Result<Option<(Term, String, Diagnostics)>, Diagnostics> {
-    let run_opts = RunOpts::default();
-    let compile_opts = CompileOpts::default().set_all();
-    let diagnostics_cfg = DiagnosticsConfig::default();
-    let args = None;
-
-    bend::run_book(
-        book.to_owned(),
-        run_opts,
-        compile_opts,
-        diagnostics_cfg,
-        args,
+) -> Result<Option<(Term, String, Diagnostics)>, RunError> {
+    run_with_opts(
+        book,
         cmd,
+        RunOpts::default(),
+        CompileOpts::default().set_all(),
+        DEFAULT_STACK_SIZE,
     )
 }
+
+/**
+ Runs a command on a book with the given options and returns the result.
+
+ # Arguments
+
+ * `book` - The book to run in the HVM.
+ * `cmd` - The runtime to run the book on: Rust, C or CUDA.
+ * `run_opts` - How the HVM is called and its result is read back.
+ * `compile_opts` - The compilation passes applied to the book.
+ * `stack_size` - The size of the stack of the thread that runs the book.
+
+ # Returns
+
+ The same as `run`.
+
+ # Errors
+
+ The same as `run`.
+*/
+pub fn run_with_opts(
+    book: &Book,
+    cmd: &str,
+    run_opts: RunOpts,
+    compile_opts: CompileOpts,
+    stack_size: usize,
+) -> Result<Option<(Term, String, Diagnostics)>, RunError> {
+    // The book always has definitions that aren't used by `main`, like the
+    // builtins, so warning about them is only noise when pruning is disabled.
+    let diagnostics_cfg = DiagnosticsConfig {
+        unused_definition: Severity::Allow,
+        ..DiagnosticsConfig::default()
+    };
+    let args = None;
+
+    let _guard = RUN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    std::thread::scope(|scope| {
+        let handle = std::thread::Builder::new()
+            .stack_size(stack_size)
+            .spawn_scoped(scope, || {
+                bend::run_book(
+                    book.to_owned(),
+                    run_opts,
+                    compile_opts.clone(),
+                    diagnostics_cfg,
+                    args,
+                    cmd,
+                )
+                .map_err(|diags| {
+                    // `run_book` reports compilation and HVM errors the same
+                    // way, so the book is compiled again to tell them apart.
+                    let compiled = bend::compile_book(
+                        &mut book.to_owned(),
+                        compile_opts.clone(),
+                        diagnostics_cfg,
+                        None,
+                    );
+
+                    match compiled {
+                        Ok(_) => RunError::Runtime(diags),
+                        Err(_) => RunError::Compile(diags),
+                    }
+                })
+            })
+            .map_err(|e| RunError::Runtime(Diagnostics::from(e.to_string())))?;
+
+        handle.join().map_err(|_| {
+            RunError::Runtime(Diagnostics::from(
+                "Bend panicked while running the book".to_string(),
+            ))
+        })?
+    })
+}
+
+#[cfg(test)]
+mod benda_ffi_tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn run_code(code: &str) -> Result<(), RunError> {
+        let book = bend::fun::load_book::do_parse_book(
+            code,
+            Path::new("bend.tmp"),
+            Book::builtins(),
+        )
+        .unwrap();
+
+        run(&book, "run").map(|_| ())
+    }
+
+    #[test]
+    fn run_errors() {
+        let compile = run_code("def main():\n  return undefined_fun(1)");
+        assert!(matches!(compile, Err(RunError::Compile(_))));
+
+        let msg = compile.unwrap_err().to_string();
+        assert!(msg.contains("undefined_fun"));
+        assert!(!msg.contains('\x1b'));
+
+        let runtime = run_code("def main():\n  return 1 / 0");
+        assert!(matches!(runtime, Err(RunError::Runtime(_))));
+    }
+}
@@ -0,0 +1,81 @@
+//! Benda Exceptions
+//!
+//! Every error caused by the user's Bend code, Python code or arguments is raised
+//! as a subclass of `benda.BendError`, carrying the diagnostics reported by Bend,
+//! so they can be caught all at once or told apart by their kind.
+//!
+//! # Exceptions
+//!
+//! - `BendError`: Base class of all the errors raised by Benda
+//! - `BendSyntaxError`: The Bend code could not be parsed
+//! - `BendCompileError`: The Bend book could not be compiled
+//! - `BendRuntimeError`: The HVM failed to run the book
+//! - `ArgumentConversionError`: A value could not be converted between Python and Bend
+//! - `UnsupportedPythonConstruct`: `bjit` found Python code it can't translate to Bend
+
+use pyo3::exceptions::PyException;
+use pyo3::{create_exception, PyErr};
+
+use crate::benda_ffi::RunError;
+
+create_exception!(
+    benda,
+    BendError,
+    PyException,
+    "Base class of all the errors raised by Benda."
+);
+create_exception!(
+    benda,
+    BendSyntaxError,
+    BendError,
+    "Raised when Bend code can't be parsed."
+);
+create_exception!(
+    benda,
+    BendCompileError,
+    BendError,
+    "Raised when a Bend book can't be compiled."
+);
+create_exception!(
+    benda,
+    BendRuntimeError,
+    BendError,
+    "Raised when the HVM fails to run a book or its result can't be read back."
+);
+create_exception!(
+    benda,
+    ArgumentConversionError,
+    BendError,
+    "Raised when a value can't be converted between Python and Bend."
+);
+create_exception!(
+    benda,
+    UnsupportedPythonConstruct,
+    BendError,
+    "Raised when bjit finds Python code it can't translate to Bend."
+);
+
+impl From<RunError> for PyErr {
+    fn from(err: RunError) -> Self {
+        match err {
+            RunError::Compile(_) => BendCompileError::new_err(err.to_string()),
+            RunError::Runtime(_) => BendRuntimeError::new_err(err.to_string()),
+        }
+    }
+}
+
+/// Removes the terminal color codes Bend uses when printing its diagnostics
+pub fn strip_colors(text: &str) -> String {
+    let mut chars = text.chars();
+    let mut plain = String::with_capacity(text.len());
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|c| *c == 'm');
+        } else {
+            plain.push(c);
+        }
+    }
+
+    plain
+}
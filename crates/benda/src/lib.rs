@@ -11,6 +11,7 @@
 //! - `load_book`: Load a Bend book from a string of Bend code
 //! - `load_book_from_file`: Load a Bend book from a file
 //! - `PyBjit`: Annotation class for compiling Python functions to Bend code at runtime
+//! - `BendError`: Base class of the exceptions raised by Benda, defined in `errors`
 //!
 //! # Examples
 //!
@@ -37,18 +38,30 @@
 //! This module integrates Python and Bend, allowing for seamless interaction between the two languages.
 //! It's particularly useful for projects that need to leverage Bend's capabilities while working within a Python environment.
 
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 
-use num_traits::ToPrimitive;
+use errors::{
+    strip_colors, ArgumentConversionError, BendCompileError, BendError,
+    BendRuntimeError, BendSyntaxError, UnsupportedPythonConstruct,
+};
+use parser::call_graph::{CallGraph, Sources};
+use parser::num_type::NumType;
 use parser::Parser;
-use pyo3::exceptions::PyException;
+use pyo3::exceptions::PyTypeError;
 use pyo3::prelude::*;
 use pyo3::types::{PyDict, PyFunction, PyString, PyTuple};
-use rustpython_parser::{parse, Mode};
+use types::bjit_result::{ResultTypes, TupleShape};
 use types::book::{BendRuntime, Book};
+use types::config::RunConfig;
+use types::f24::F24;
 use types::fan::Fan;
+use types::i24::I24;
+use types::run_result::RunResult;
 use types::u24::U24;
 pub mod benda_ffi;
+pub mod errors;
 mod parser;
 pub mod types;
 
@@ -69,6 +82,10 @@ fn switch() -> PyResult<String> {
 ///
 /// Returns a `Book` instance.
 ///
+/// # Errors
+///
+/// Raises a `BendSyntaxError` with Bend's error message if the code can't be parsed.
+///
 /// # Examples
 ///
 /// ```python
@@ -79,15 +96,16 @@ fn switch() -> PyResult<String> {
 pub fn load_book(py: Python, code: Py<PyString>) -> PyResult<Py<Book>> {
     let builtins = bend::fun::Book::builtins();
     let path = Path::new("./tmp/bend_book.tmp");
-    let bend_book = bend::fun::load_book::do_parse_book(
+    let mut bend_book = bend::fun::load_book::do_parse_book(
         code.to_string().as_str(),
         path,
         builtins,
-    );
+    )
+    .map_err(|e| BendSyntaxError::new_err(strip_colors(&e)))?;
 
-    let book = Book::new(&mut bend_book.unwrap());
+    let book = Book::new(&mut bend_book)?;
 
-    Ok(Py::new(py, book).unwrap())
+    Py::new(py, book)
 }
 
 /// Load a Book from a file
@@ -102,6 +120,11 @@ pub fn load_book(py: Python, code: Py<PyString>) -> PyResult<Py<Book>> {
 ///
 /// Returns a `Book` instance.
 ///
+/// # Errors
+///
+/// Raises an `OSError` if the file can't be read and a `BendSyntaxError` with Bend's
+/// error message if its code can't be parsed.
+///
 /// # Examples
 ///
 /// ```python
@@ -115,11 +138,17 @@ pub fn load_book_from_file(
 ) -> PyResult<Py<Book>> {
     let binding = path.to_string();
     let new_path = Path::new(&binding);
-    let bend_book = bend::load_file_to_book(new_path);
+    let code = std::fs::read_to_string(new_path)?;
+    let mut bend_book = bend::fun::load_book::do_parse_book(
+        &code,
+        new_path,
+        bend::fun::Book::builtins(),
+    )
+    .map_err(|e| BendSyntaxError::new_err(strip_colors(&e)))?;
 
-    let book = Book::new(&mut bend_book.unwrap());
+    let book = Book::new(&mut bend_book)?;
 
-    Ok(Py::new(py, book).unwrap())
+    Py::new(py, book)
 }
 
 /// Bjit decorator
@@ -129,6 +158,8 @@ pub fn load_book_from_file(
 /// # Fields
 ///
 /// * `wraps` - A `function` representing the wrapped Python function
+/// * `cache` - The translations of the function, by its code and the types of its
+///   arguments
 ///
 /// # Examples
 ///
@@ -144,6 +175,24 @@ pub fn load_book_from_file(
 #[pyclass(name = "bjit")]
 pub struct PyBjit {
     wraps: Py<PyAny>,
+    cache: Mutex<HashMap<CacheKey, Arc<Compiled>>>,
+}
+
+/// The address of the `__code__` of the function and the module and qualified
+/// name of the types of the arguments
+type CacheKey = (usize, Vec<String>);
+
+/// A function translated by `bjit`, which only needs its arguments, the values
+/// of its constants and `main` to run
+struct Compiled {
+    /// The `__code__` of the function, kept so that its address isn't reused
+    code: Py<PyAny>,
+    graph: CallGraph,
+    book: bend::fun::Book,
+    /// The shape of the tuples the function returns
+    return_shape: TupleShape,
+    /// The numeric types the integer arguments are converted into
+    arg_types: Vec<(String, NumType)>,
 }
 
 #[pymethods]
@@ -159,8 +208,42 @@ impl PyBjit {
     /// Returns a new `bjit` instance that wraps the given Python function.
     #[new]
     fn __new__(wraps: Py<PyAny>) -> Self {
-        PyBjit { wraps }
+        PyBjit {
+            wraps,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Binds the function to the instance it's accessed from, so that `bjit`
+    /// can decorate the methods of a class
+    ///
+    /// # Arguments
+    ///
+    /// * `instance` - The instance the function is accessed from, or `None` if it's
+    ///   accessed from the class
+    /// * `_owner` - The class the function is accessed from
+    ///
+    /// # Returns
+    ///
+    /// A method that calls the function with the instance as its first argument, or the
+    /// `bjit` object itself if there's no instance.
+    fn __get__(
+        slf: Bound<'_, Self>,
+        instance: Option<Bound<'_, PyAny>>,
+        _owner: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+
+        match instance {
+            Some(instance) => Ok(py
+                .import_bound("types")?
+                .getattr("MethodType")?
+                .call1((slf, instance))?
+                .unbind()),
+            None => Ok(slf.into_any().unbind()),
+        }
     }
+
     /// Call the Bend-compiled version of the wrapped Python function
     ///
     /// This method implements the runtime compilation and execution of the wrapped Python function.
@@ -169,127 +252,624 @@ impl PyBjit {
     /// # Arguments
     ///
     /// * `args` - A tuple of positional arguments to be passed to the function
+    /// * `kwargs` - The keyword arguments, matched to the parameters of the function like
+    ///   Python does, which also gives the omitted parameters their default values
     ///
     /// # Returns
     ///
-    /// Returns a `Any` containing the result of the Bend-compiled function execution.
+    /// Returns the result of the Bend-compiled function as a Python value. Numbers, tuples,
+    /// lists, `None` and the dataclasses named by the return annotation of the function are
+    /// converted into their Python equivalents.
+    ///
+    /// # Errors
+    ///
+    /// Raises an `UnsupportedPythonConstruct` if the function uses Python code that can't
+    /// be translated to Bend, an `ArgumentConversionError` if an argument can't be converted,
+    /// and a `BendCompileError` or `BendRuntimeError` if the translated code fails.
     ///
     /// # Process
     ///
     /// 1. Extracts function metadata (name, filename, argument names, etc.)
-    /// 2. Parses the function's Python code
+    /// 2. Parses the function's Python code and the definitions it reaches, in its module and
+    ///    in the other modules of its package
     /// 3. Compiles the Python code to Bend code
     /// 4. Executes the compiled Bend code with the provided arguments
-    /// 5. Returns the result of the execution
+    /// 5. Converts the result of the execution into a Python value, guided by the return annotation
     ///
     /// # Notes
     ///
     /// - The compilation process occurs at runtime, which may introduce some overhead on the first call.
-    /// - Subsequent calls to the same function may benefit from caching, though this is not explicitly implemented in the current version.
-    #[pyo3(signature = (*args, **_kwargs))]
+    /// - The translated Book is cached, so later calls with arguments of the same types only
+    ///   convert their arguments and run the Book, until the source of the module, or of the
+    ///   modules the function uses definitions of, changes. The sources are only read again
+    ///   when their files are modified, or on every call for the ones without a file, like
+    ///   the functions of notebooks.
+    /// - The source of the function is retrieved like `inspect.getsource` does, so functions
+    ///   of notebooks, methods, nested functions and callable objects can be compiled too.
+    ///   The instance of a method or callable object is given as its `self` argument, so it
+    ///   has to be a value that can be converted to Bend, like a dataclass.
+    #[pyo3(signature = (*args, **kwargs))]
     fn __call__(
         &self,
         py: Python<'_>,
         args: &Bound<'_, PyTuple>,
-        _kwargs: Option<&Bound<'_, PyDict>>,
+        kwargs: Option<&Bound<'_, PyDict>>,
     ) -> PyResult<Py<PyAny>> {
-        let arg_names_temp: Bound<PyAny>;
-
-        let (name, filename, arg_names, argcount) =
-            match self.wraps.downcast_bound::<PyFunction>(py) {
-                Ok(inner) => {
-                    let name = inner.getattr("__name__").unwrap();
-                    let code = inner.getattr("__code__").unwrap();
-                    let filename = code.getattr("co_filename").unwrap();
-
-                    arg_names_temp = code.getattr("co_varnames").unwrap();
-                    let arg_names =
-                        arg_names_temp.downcast::<PyTuple>().unwrap();
-                    let argcount = code
-                        .getattr("co_argcount")
-                        .unwrap()
-                        .to_string()
-                        .parse::<u32>()
-                        .unwrap();
-
-                    (name, filename, arg_names, argcount)
-                }
-                Err(_) => todo!(),
-            };
+        let (inner, instance) = jitted_function(self.wraps.bind(py))?;
+
+        let name = inner.getattr("__name__")?.to_string();
+        let qualname = inner.getattr("__qualname__")?.to_string();
+        let code = inner.getattr("__code__")?;
+        let filename = code.getattr("co_filename")?.to_string();
+        let arg_names = code.getattr("co_varnames")?;
+        let arg_names = arg_names.downcast::<PyTuple>()?;
+        let argcount = code.getattr("co_argcount")?.extract::<usize>()?;
+
+        // Methods and callable objects get their instance as `self`
+        let args: Vec<Bound<PyAny>> =
+            instance.into_iter().chain(args.iter()).collect();
+        let args = bind_args(&inner, args, kwargs)?;
 
         let mut arg_list: Vec<String> = vec![];
 
         for (index, arg) in arg_names.iter().enumerate() {
-            if index >= argcount.to_usize().unwrap() {
+            if index >= argcount {
                 break;
             }
 
             arg_list.push(arg.to_string());
         }
 
-        let mut parsed_types: Vec<(String, Bound<PyAny>)> = vec![];
+        if args.len() != argcount {
+            return Err(PyTypeError::new_err(format!(
+                "Function {} takes {} arguments but {} were given",
+                name,
+                argcount,
+                args.len()
+            )));
+        }
 
-        for (index, arg) in
-            args.downcast::<PyTuple>().unwrap().iter().enumerate()
-        {
-            let var_name = arg_list.get(index).unwrap().to_string();
+        let mut parsed_types: Vec<(String, Bound<PyAny>)> = vec![];
 
-            parsed_types.push((var_name.clone(), arg));
+        for (var_name, arg) in arg_list.iter().zip(args.iter()) {
+            parsed_types.push((var_name.clone(), arg.clone()));
         }
 
-        let code = std::fs::read_to_string(filename.to_string()).unwrap();
-        let module = parse(code.as_str(), Mode::Module, "main.py").unwrap();
+        let key = (
+            code.as_ptr() as usize,
+            args.iter().map(type_key).collect::<PyResult<Vec<_>>>()?,
+        );
+
+        let cached = self.cache.lock().unwrap().get(&key).cloned();
+        let cached = match cached {
+            Some(compiled)
+                if compiled.code.is(&code)
+                    && compiled.graph.is_fresh(&inner)? =>
+            {
+                Some(compiled)
+            }
+            _ => None,
+        };
+        let compiled = match cached {
+            Some(compiled) => compiled,
+            None => {
+                let top_level = qualname == name;
+                let sources = Sources::of(&inner)?;
+                let graph = CallGraph::new(
+                    &inner, top_level, &filename, sources, &args,
+                )?;
+                let compiled = Arc::new(compile(
+                    code.clone().unbind(),
+                    graph,
+                    &name,
+                    &parsed_types,
+                )?);
+                self.cache.lock().unwrap().insert(key, compiled.clone());
+                compiled
+            }
+        };
 
-        let mut val: Option<Bound<PyString>> = None;
+        let mut parser = Parser::with_book(
+            compiled.graph.statements.clone(),
+            parsed_types,
+            &compiled.graph.source,
+            compiled.book.clone(),
+        );
+        parser.set_arg_types(compiled.arg_types.clone());
+        for constant in &compiled.graph.constants {
+            parser.add_constant(
+                &constant.name,
+                &constant.value(py)?,
+                constant.num_type,
+            )?;
+        }
+        let return_val = parser.run(&name, &[])?;
 
-        match module {
-            rustpython_parser::ast::Mod::Module(mods) => {
-                for stmt in mods.body.iter() {
-                    if let rustpython_parser::ast::Stmt::FunctionDef(fun_def) =
-                        stmt
+        let returns =
+            compiled
+                .graph
+                .statements
+                .iter()
+                .find_map(|stmt| match stmt {
+                    rustpython_parser::ast::Stmt::FunctionDef(fun_def)
+                        if fun_def.name.as_str() == name =>
                     {
-                        if fun_def.name == name.to_string() {
-                            let mut parser = Parser::new(
-                                mods.body.clone(),
-                                parsed_types.clone(),
-                            );
-                            let return_val =
-                                parser.parse(name.to_string().as_ref(), &[]);
-
-                            match return_val {
-                                Ok(v) => {
-                                    val = Some(PyString::new_bound(
-                                        py,
-                                        v.as_str(),
-                                    ))
-                                }
-                                Err(e) => {
-                                    return Err(PyException::new_err(
-                                        e.to_string(),
-                                    ));
-                                }
-                            }
-
-                            break;
-                        }
+                        fun_def.returns.as_deref()
                     }
-                }
-            }
-            _ => unimplemented!(),
+                    _ => None,
+                });
+
+        let globals = inner.getattr("__globals__")?;
+        let globals = globals.downcast::<PyDict>()?;
+
+        ResultTypes::new(
+            py,
+            &compiled.graph.statements,
+            globals,
+            &compiled.book,
+        )
+        .to_py(&return_val, returns, &compiled.return_shape)
+    }
+}
+
+/// Matches the arguments of a call to the parameters of a function, like
+/// `inspect.signature(function).bind` does, and gives the omitted parameters
+/// their default values
+///
+/// # Returns
+///
+/// The arguments in the order of the parameters.
+///
+/// # Errors
+///
+/// Raises a `TypeError` if the arguments don't match the parameters, or the
+/// function has keyword-only parameters.
+fn bind_args<'py>(
+    function: &Bound<'py, PyFunction>,
+    args: Vec<Bound<'py, PyAny>>,
+    kwargs: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Vec<Bound<'py, PyAny>>> {
+    let py = function.py();
+    let signature = py
+        .import_bound("inspect")?
+        .getattr("signature")?
+        .call1((function,))?;
+
+    let bound = signature
+        .getattr("bind")?
+        .call(PyTuple::new_bound(py, args), kwargs)?;
+    bound.call_method0("apply_defaults")?;
+
+    if bound.getattr("kwargs")?.downcast::<PyDict>()?.len() != 0 {
+        return Err(PyTypeError::new_err(format!(
+            "Function {} has keyword-only parameters, which bjit can't pass",
+            function.getattr("__name__")?
+        )));
+    }
+
+    bound
+        .getattr("args")?
+        .downcast::<PyTuple>()?
+        .iter()
+        .map(Ok)
+        .collect()
+}
+
+/// Finds the Python function that `bjit` translates
+///
+/// Functions wrapped by other decorators with `functools.wraps` are unwrapped,
+/// like `inspect.unwrap` does, and so are `staticmethod`s and methods.
+///
+/// # Arguments
+///
+/// * `wraps` - The object given to `bjit`
+///
+/// # Returns
+///
+/// The function, and the instance it is bound to if `wraps` is a method or a
+/// callable object.
+///
+/// # Errors
+///
+/// Raises an `UnsupportedPythonConstruct` if `wraps` isn't a function, a method
+/// or an object with a `__call__` method.
+fn jitted_function<'py>(
+    wraps: &Bound<'py, PyAny>,
+) -> PyResult<(Bound<'py, PyFunction>, Option<Bound<'py, PyAny>>)> {
+    let py = wraps.py();
+    let inspect = py.import_bound("inspect")?;
+    let obj = inspect.getattr("unwrap")?.call1((wraps,))?;
+
+    if let Ok(function) = obj.downcast::<PyFunction>() {
+        return Ok((function.clone(), None));
+    }
+
+    // A `staticmethod` or a bound method, whose `__self__` is its instance, or
+    // the `__call__` method of a callable object
+    let method = match obj.hasattr("__func__")? {
+        true => Some(obj.clone()),
+        false => obj.getattr("__call__").ok(),
+    };
+
+    if let Some(method) = method {
+        let function = method.getattr("__func__").ok();
+        if let Some(function) =
+            function.and_then(|function| function.downcast_into().ok())
+        {
+            return Ok((function, method.getattr("__self__").ok()));
         }
+    }
+
+    Err(UnsupportedPythonConstruct::new_err(format!(
+        "bjit can only compile functions, methods and callable objects, got {}",
+        obj.get_type().name()?
+    )))
+}
 
-        Ok(val.unwrap().into())
+/// Names the type of an argument by its module and qualified name, as types
+/// of different modules or classes can have the same name, and for numbers
+/// by the numeric type they are translated with, as negative `int`s are
+/// `i24`s
+fn type_key(arg: &Bound<PyAny>) -> PyResult<String> {
+    let ty = arg.get_type();
+    let key = format!("{}.{}", ty.getattr("__module__")?, ty.qualname()?);
+    Ok(match NumType::of_value(arg) {
+        Some(num_type) => format!("{}:{}", key, num_type),
+        None => key,
+    })
+}
+
+/// Translates the definitions a function reaches into a Bend Book
+///
+/// # Arguments
+///
+/// * `code` - The `__code__` of the function
+/// * `graph` - The definitions the function reaches
+/// * `name` - The name of the function
+/// * `args` - The names and values of the arguments of the call, whose types
+///   the parameters without an annotation take
+///
+/// # Errors
+///
+/// Raises an `UnsupportedPythonConstruct` if a definition can't be translated, and
+/// a `BendCompileError` if its translation isn't valid Bend.
+fn compile(
+    code: Py<PyAny>,
+    graph: CallGraph,
+    name: &str,
+    args: &[(String, Bound<PyAny>)],
+) -> PyResult<Compiled> {
+    let mut parser =
+        Parser::new(graph.statements.clone(), args.to_vec(), &graph.source);
+    parser.set_called(name);
+    for (offset, line) in &graph.segments {
+        parser.add_segment(*offset, *line);
+    }
+    for constant in &graph.constants {
+        if let Some(num_type) = constant.num_type {
+            parser.add_constant_type(&constant.name, num_type);
+        }
     }
+    parser.compile()?;
+
+    Ok(Compiled {
+        code,
+        book: parser.book().clone(),
+        return_shape: parser.return_shape(name),
+        arg_types: parser.arg_types().to_vec(),
+        graph,
+    })
 }
 
 #[pymodule]
-fn benda(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
+fn benda(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(switch, m)?)?;
     m.add_function(wrap_pyfunction!(load_book_from_file, m)?)?;
     m.add_function(wrap_pyfunction!(load_book, m)?)?;
     m.add_class::<BendRuntime>()?;
     m.add_class::<PyBjit>()?;
     m.add_class::<U24>()?;
+    m.add_class::<I24>()?;
+    m.add_class::<F24>()?;
+    m.add_class::<RunResult>()?;
+    m.add_class::<RunConfig>()?;
     m.add_class::<Fan>()?;
+    m.add("BendError", py.get_type_bound::<BendError>())?;
+    m.add("BendSyntaxError", py.get_type_bound::<BendSyntaxError>())?;
+    m.add("BendCompileError", py.get_type_bound::<BendCompileError>())?;
+    m.add("BendRuntimeError", py.get_type_bound::<BendRuntimeError>())?;
+    m.add(
+        "ArgumentConversionError",
+        py.get_type_bound::<ArgumentConversionError>(),
+    )?;
+    m.add(
+        "UnsupportedPythonConstruct",
+        py.get_type_bound::<UnsupportedPythonConstruct>(),
+    )?;
     Ok(())
 }
+
+#[cfg(test)]
+mod bjit_tests {
+    use std::fs::File;
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+
+    /// Writes a module into a directory of its own and imports it
+    fn import_module<'py>(
+        py: Python<'py>,
+        name: &str,
+        code: &str,
+    ) -> (Bound<'py, PyModule>, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!(
+            "benda_{}_{}",
+            name,
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join(format!("{}.py", name));
+        std::fs::write(&file, code).unwrap();
+
+        let path = py.import_bound("sys").unwrap().getattr("path").unwrap();
+        path.call_method1("insert", (0, dir.to_string_lossy()))
+            .unwrap();
+
+        (py.import_bound(name).unwrap(), file)
+    }
+
+    fn call(py: Python, bjit: &PyBjit, arg: impl ToPyObject) -> String {
+        let args = PyTuple::new_bound(py, [arg.to_object(py)]);
+        let result = bjit.__call__(py, &args, None).unwrap();
+        result.bind(py).repr().unwrap().to_string()
+    }
+
+    /// The translation for arguments of a single type
+    fn cached(bjit: &PyBjit, ty: &str) -> Arc<Compiled> {
+        let cache = bjit.cache.lock().unwrap();
+        let (_, compiled) =
+            cache.iter().find(|((_, types), _)| types == &[ty]).unwrap();
+        compiled.clone()
+    }
+
+    #[test]
+    fn cache() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "SCALE = 2\n\ndef pair(x):\n    return x, SCALE\n";
+
+        Python::with_gil(|py| {
+            let (module, file) = import_module(py, "bjit_cache", code);
+            let bjit = PyBjit::__new__(module.getattr("pair").unwrap().into());
+
+            assert_eq!(call(py, &bjit, 3), "(3, 2)");
+            let first = cached(&bjit, "builtins.int:u24");
+            assert_eq!(call(py, &bjit, 4), "(4, 2)");
+            assert!(Arc::ptr_eq(&first, &cached(&bjit, "builtins.int:u24")));
+
+            // Arguments of another type are translated on their own, and so
+            // are negative integers, which are i24s
+            assert_eq!(call(py, &bjit, 1.5), "(1.5, 2)");
+            assert_eq!(bjit.cache.lock().unwrap().len(), 2);
+            assert_eq!(call(py, &bjit, -3), "(-3, 2)");
+            assert_eq!(bjit.cache.lock().unwrap().len(), 3);
+
+            // A constant with another type changes the translation
+            module.setattr("SCALE", -2).unwrap();
+            assert_eq!(call(py, &bjit, 3), "(3, -2)");
+            let second = cached(&bjit, "builtins.int:u24");
+            assert!(!Arc::ptr_eq(&first, &second));
+
+            // And so does a change of the source of the function
+            std::fs::write(
+                &file,
+                "SCALE = 2\n\ndef pair(x):\n    return SCALE, x\n",
+            )
+            .unwrap();
+            File::options()
+                .write(true)
+                .open(&file)
+                .unwrap()
+                .set_modified(SystemTime::now() + Duration::from_secs(10))
+                .unwrap();
+            assert_eq!(call(py, &bjit, 3), "(-2, 3)");
+            assert!(!Arc::ptr_eq(&second, &cached(&bjit, "builtins.int:u24")));
+
+            std::fs::remove_dir_all(file.parent().unwrap()).unwrap();
+        });
+    }
+
+    #[test]
+    fn tuple_results() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "def triple(a):\n    return a, a + 1, (a + 2, a + 3, a + 4)\n\ndef scaled(a) -> tuple[float, ...]:\n    return a, a * 2, a * 3\n";
+
+        Python::with_gil(|py| {
+            let (module, file) = import_module(py, "bjit_tuples", code);
+            let jit =
+                |name| PyBjit::__new__(module.getattr(name).unwrap().unbind());
+
+            // Without annotations, the arity of the returned tuples tells
+            // how to undo the nesting of the HVM
+            assert_eq!(call(py, &jit("triple"), 1), "(1, 2, (3, 4, 5))");
+            assert_eq!(call(py, &jit("scaled"), 1), "(1.0, 2.0, 3.0)");
+
+            std::fs::remove_dir_all(file.parent().unwrap()).unwrap();
+        });
+    }
+
+    #[test]
+    fn int_arguments() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "def add(a, b):\n    return a + b\n";
+
+        Python::with_gil(|py| {
+            let (module, file) = import_module(py, "bjit_ints", code);
+            let add = PyBjit::__new__(module.getattr("add").unwrap().unbind());
+            let call = |a: PyObject, b: PyObject| {
+                let args = PyTuple::new_bound(py, [a, b]);
+                let result = add.__call__(py, &args, None).unwrap();
+                result.bind(py).repr().unwrap().to_string()
+            };
+
+            // Integer arguments take the type of the numbers they are
+            // operated with, like Python converts them
+            assert_eq!(call(2.into_py(py), 1.5.into_py(py)), "3.5");
+            assert_eq!(call(2.into_py(py), 3.into_py(py)), "5");
+            assert_eq!(call((-2).into_py(py), 3.into_py(py)), "1");
+            assert_eq!(add.cache.lock().unwrap().len(), 3);
+
+            std::fs::remove_dir_all(file.parent().unwrap()).unwrap();
+        });
+    }
+
+    #[test]
+    fn float_constants() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "LIMIT = 1.5\n\ndef triple(x):\n    return x * 3\n\ndef scaled(x):\n    return x * LIMIT\n\ndef fun(a):\n    return triple(a) + LIMIT, scaled(a) + 1\n";
+
+        Python::with_gil(|py| {
+            let (module, file) = import_module(py, "bjit_limit", code);
+            let fun = PyBjit::__new__(module.getattr("fun").unwrap().unbind());
+            let args = PyTuple::new_bound(py, [2]);
+            let result = fun.__call__(py, &args, None).unwrap();
+
+            // The integers operated with a float constant are converted
+            assert_eq!(
+                result.bind(py).repr().unwrap().to_string(),
+                "(7.5, 4.0)"
+            );
+
+            std::fs::remove_dir_all(file.parent().unwrap()).unwrap();
+        });
+    }
+
+    #[test]
+    fn bound_arguments() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "from dataclasses import dataclass\n\n@dataclass\nclass Box:\n    size: int\n\n    def grow(self, by=1):\n        match self:\n            case Box(size):\n                return size + by\n\ndef area(w, h=2):\n    return w * h\n\ndef scale(x, *, by):\n    return x * by\n";
+
+        Python::with_gil(|py| {
+            let (module, file) = import_module(py, "bjit_bound", code);
+            let jit = |obj: Bound<PyAny>| {
+                Py::new(py, PyBjit::__new__(obj.unbind())).unwrap()
+            };
+            let kwargs = |key: &str, val: u32| {
+                let kwargs = PyDict::new_bound(py);
+                kwargs.set_item(key, val).unwrap();
+                kwargs
+            };
+            let repr = |obj: Bound<PyAny>| obj.repr().unwrap().to_string();
+
+            // Decorating a method in the body of a class binds its instance
+            let class = module.getattr("Box").unwrap();
+            let grow = class.getattr("grow").unwrap();
+            class.setattr("grow", jit(grow)).unwrap();
+            let boxed = class.call1((3,)).unwrap();
+            let grow = boxed.getattr("grow").unwrap();
+
+            assert_eq!(repr(grow.call1((2,)).unwrap()), "5");
+            assert_eq!(
+                repr(grow.call((), Some(&kwargs("by", 4))).unwrap()),
+                "7"
+            );
+            assert_eq!(repr(grow.call0().unwrap()), "4");
+
+            // Keyword arguments are matched to the parameters, and the
+            // omitted ones take their default values
+            let area = jit(module.getattr("area").unwrap()).into_bound(py);
+            assert_eq!(
+                repr(area.call((3,), Some(&kwargs("h", 5))).unwrap()),
+                "15"
+            );
+            assert_eq!(
+                repr(area.call((), Some(&kwargs("w", 3))).unwrap()),
+                "6"
+            );
+
+            let err = area.call((3,), Some(&kwargs("d", 5))).unwrap_err();
+            assert!(err.is_instance_of::<PyTypeError>(py));
+
+            let scale = jit(module.getattr("scale").unwrap()).into_bound(py);
+            let err = scale.call((3,), Some(&kwargs("by", 5))).unwrap_err();
+            assert!(err.is_instance_of::<PyTypeError>(py));
+            assert!(err.to_string().contains("keyword-only parameters"));
+
+            std::fs::remove_dir_all(file.parent().unwrap()).unwrap();
+        });
+    }
+
+    #[test]
+    fn jitted_functions() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "import functools\nfrom dataclasses import dataclass\n\ndef logged(f):\n    @functools.wraps(f)\n    def wrapper(*args):\n        return f(*args)\n    return wrapper\n\n@logged\ndef double(x):\n    return x * 2\n\n@dataclass\nclass Counter:\n    step: int\n\n    def advance(self, x):\n        match self:\n            case Counter(step):\n                return x + step\n\n    @staticmethod\n    def triple(x):\n        return x * 3\n\n    def __call__(self, x):\n        match self:\n            case Counter(step):\n                return x - step\n\ncounter = Counter(5)\n";
+
+        Python::with_gil(|py| {
+            let (module, file) = import_module(py, "bjit_jitted", code);
+            let double = module.getattr("double").unwrap();
+            let counter = module.getattr("counter").unwrap();
+            let advance = counter.getattr("advance").unwrap();
+            let triple = module
+                .getattr("Counter")
+                .and_then(|class| class.getattr("__dict__"))
+                .and_then(|dict| dict.get_item("triple"))
+                .unwrap();
+            let jit =
+                |obj: &Bound<PyAny>| PyBjit::__new__(obj.clone().unbind());
+
+            // Decorated functions are unwrapped, like `inspect.unwrap` does
+            let (function, instance) = jitted_function(&double).unwrap();
+            assert_eq!(
+                function.getattr("__name__").unwrap().to_string(),
+                "double"
+            );
+            assert!(instance.is_none());
+            assert_eq!(call(py, &jit(&double), 4), "8");
+
+            // Bound methods and callable objects get their instance as `self`
+            let (_, instance) = jitted_function(&advance).unwrap();
+            assert!(instance.unwrap().is(&counter));
+            assert_eq!(call(py, &jit(&advance), 1), "6");
+            assert_eq!(call(py, &jit(&counter), 10), "5");
+
+            // Static methods have no instance
+            let (_, instance) = jitted_function(&triple).unwrap();
+            assert!(instance.is_none());
+            assert_eq!(call(py, &jit(&triple), 2), "6");
+
+            let err =
+                jitted_function(&42.to_object(py).into_bound(py)).unwrap_err();
+            assert!(err.is_instance_of::<UnsupportedPythonConstruct>(py));
+            assert!(err.to_string().contains(
+                "bjit can only compile functions, methods and callable objects, got int"
+            ));
+
+            std::fs::remove_dir_all(file.parent().unwrap()).unwrap();
+        });
+    }
+
+    #[test]
+    fn type_keys() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let globals = PyDict::new_bound(py);
+            py.run_bound(
+                "class A:\n    class Point: pass\n\nclass B:\n    class Point: pass\n\na = A.Point()\nb = B.Point()\n",
+                Some(&globals),
+                None,
+            )
+            .unwrap();
+
+            let a = type_key(&globals.get_item("a").unwrap().unwrap());
+            let b = type_key(&globals.get_item("b").unwrap().unwrap());
+            assert_eq!(a.unwrap(), "builtins.A.Point");
+            assert_eq!(b.unwrap(), "builtins.B.Point");
+        });
+    }
+}
@@ -0,0 +1,1201 @@
+//! The definitions a `bjit` function needs
+//!
+//! Only the definitions the jitted function reaches are translated: the
+//! functions it calls, the dataclasses and unions it uses, and the ones those
+//! need in turn, so the rest of its module doesn't have to be translatable.
+//! Names imported from other modules of the same package, or of the directory
+//! of the script, are followed into those modules, and so are the attributes
+//! of imported modules, like `helpers.triple`. Uses of aliases are
+//! replaced by the names of the definitions they refer to.
+//!
+//! The module level constants they use, like `MAX_DEPTH = 20`, aren't
+//! translated with them: their values are read from the globals of the
+//! function each time it runs.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use pyo3::exceptions::PyNameError;
+use pyo3::prelude::*;
+use pyo3::types::{
+    PyDict, PyFloat, PyFunction, PyInt, PyString, PyTuple, PyType,
+};
+use rustpython_parser::ast::fold::{self, Fold};
+use rustpython_parser::ast::{
+    Arg, Expr as rExpr, ExprAttribute, ExprCall, ExprContext, ExprName,
+    Identifier, Mod, PatternMatchAs, PatternMatchStar, Ranged, Stmt as rStmt,
+    StmtFunctionDef,
+};
+use rustpython_parser::text_size::{TextRange, TextSize};
+use rustpython_parser::{parse_starts_at, Mode};
+
+use super::num_type::NumType;
+use crate::errors::UnsupportedPythonConstruct;
+use crate::PyBjit;
+
+/// The Python code of a function compiled by `bjit`
+pub struct Sources {
+    /// The dedented source of the function
+    function: String,
+    /// The line of its file where the function starts
+    line: usize,
+    /// The source of the module of the function, unless it has none, like the
+    /// functions defined in a REPL
+    module: Option<String>,
+    /// The file of the function and when it was modified, taken before
+    /// reading the sources
+    modified: Option<(PathBuf, SystemTime)>,
+}
+
+impl Sources {
+    /// Retrieves the source of a function and of its module like
+    /// `inspect.getsource` does, so functions of notebooks and of the REPLs
+    /// that keep their input have a source too
+    ///
+    /// # Errors
+    ///
+    /// Raises an `UnsupportedPythonConstruct` if the source of the function
+    /// can't be found.
+    pub fn of(function: &Bound<PyFunction>) -> PyResult<Self> {
+        let py = function.py();
+        let inspect = py.import_bound("inspect")?;
+        let file = function.getattr("__code__")?.getattr("co_filename")?;
+        let modified = modified_at(file.to_string());
+
+        let (function_source, line) =
+            object_source(function)?.ok_or_else(|| {
+                UnsupportedPythonConstruct::new_err(format!(
+                    "Could not find the source of {}",
+                    function.getattr("__qualname__").map_or_else(
+                        |_| function.to_string(),
+                        |name| name.to_string()
+                    ),
+                ))
+            })?;
+
+        let module = inspect
+            .getattr("getmodule")?
+            .call1((function,))?
+            .extract::<Option<Bound<PyModule>>>()?
+            .and_then(|module| module_source(&module));
+
+        Ok(Sources {
+            function: function_source,
+            line,
+            module,
+            modified,
+        })
+    }
+
+    /// The stamp of the sources, to check later if they changed
+    fn stamp(&self) -> Stamp {
+        let mut hasher = DefaultHasher::new();
+        (&self.function, self.line, &self.module).hash(&mut hasher);
+
+        Stamp {
+            hash: hasher.finish(),
+            modified: self.modified.clone(),
+        }
+    }
+}
+
+/// When a file was last modified, if it's a file
+fn modified_at(file: impl AsRef<Path>) -> Option<(PathBuf, SystemTime)> {
+    let file = file.as_ref();
+    let modified = std::fs::metadata(file).and_then(|meta| meta.modified());
+    modified.ok().map(|modified| (file.to_path_buf(), modified))
+}
+
+/// The hash of a source the translation depends on when it was translated,
+/// and when its file was modified, so that the source is only read again to
+/// compare it once the file changes. Sources without a file, like the ones of
+/// notebooks, are read again on every check.
+struct Stamp {
+    hash: u64,
+    modified: Option<(PathBuf, SystemTime)>,
+}
+
+impl Stamp {
+    /// Checks if the source is still the one that was translated
+    ///
+    /// # Arguments
+    ///
+    /// * `read` - Reads the current hash of the source, or `None` if it's gone
+    fn is_current(
+        &self,
+        read: impl FnOnce() -> PyResult<Option<u64>>,
+    ) -> PyResult<bool> {
+        if let Some((file, modified)) = &self.modified {
+            if modified_at(file).map(|(_, at)| at) == Some(*modified) {
+                return Ok(true);
+            }
+        }
+
+        Ok(read()? == Some(self.hash))
+    }
+}
+
+/// The dedented source of a function or class, and the line of its file
+/// where it starts
+fn object_source(obj: &Bound<PyAny>) -> PyResult<Option<(String, usize)>> {
+    let py = obj.py();
+    let inspect = py.import_bound("inspect")?;
+    let dedent = py.import_bound("textwrap")?.getattr("dedent")?;
+
+    let Ok(lines) = inspect.getattr("getsourcelines")?.call1((obj,)) else {
+        return Ok(None);
+    };
+    let (lines, line): (Vec<String>, usize) = lines.extract()?;
+
+    Ok(Some((dedent.call1((lines.concat(),))?.extract()?, line)))
+}
+
+/// The source of a module, if it has one
+fn module_source(module: &Bound<PyModule>) -> Option<String> {
+    let inspect = module.py().import_bound("inspect").ok()?;
+    inspect
+        .getattr("getsource")
+        .ok()?
+        .call1((module,))
+        .ok()?
+        .extract()
+        .ok()
+}
+
+fn hash_of(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Collects the names that a definition uses and the ones it binds itself,
+/// like its parameters and variables
+#[derive(Default)]
+struct Names {
+    used: Vec<String>,
+    bound: Vec<String>,
+}
+
+impl Fold<TextRange> for Names {
+    type TargetU = TextRange;
+    type Error = Infallible;
+    type UserContext = ();
+
+    fn will_map_user(&mut self, _user: &TextRange) -> Self::UserContext {}
+
+    fn map_user(
+        &mut self,
+        user: TextRange,
+        _context: Self::UserContext,
+    ) -> Result<TextRange, Infallible> {
+        Ok(user)
+    }
+
+    fn fold_expr_name(
+        &mut self,
+        node: ExprName<TextRange>,
+    ) -> Result<ExprName<TextRange>, Infallible> {
+        match node.ctx {
+            ExprContext::Load => self.used.push(node.id.to_string()),
+            _ => self.bound.push(node.id.to_string()),
+        }
+        Ok(node)
+    }
+
+    /// The attributes of globals, like `helpers.triple`, are used by their
+    /// dotted name, as the global can be a module
+    fn fold_expr_attribute(
+        &mut self,
+        node: ExprAttribute<TextRange>,
+    ) -> Result<ExprAttribute<TextRange>, Infallible> {
+        match node.value.as_ref() {
+            rExpr::Name(nam) if nam.ctx.is_load() => {
+                self.used.push(format!("{}.{}", nam.id, node.attr));
+                Ok(node)
+            }
+            _ => fold::fold_expr_attribute(self, node),
+        }
+    }
+
+    fn fold_arg(
+        &mut self,
+        node: Arg<TextRange>,
+    ) -> Result<Arg<TextRange>, Infallible> {
+        self.bound.push(node.arg.to_string());
+        fold::fold_arg(self, node)
+    }
+
+    fn fold_stmt_function_def(
+        &mut self,
+        node: StmtFunctionDef<TextRange>,
+    ) -> Result<StmtFunctionDef<TextRange>, Infallible> {
+        self.bound.push(node.name.to_string());
+        fold::fold_stmt_function_def(self, node)
+    }
+
+    fn fold_pattern_match_as(
+        &mut self,
+        node: PatternMatchAs<TextRange>,
+    ) -> Result<PatternMatchAs<TextRange>, Infallible> {
+        self.bound
+            .extend(node.name.iter().map(|name| name.to_string()));
+        fold::fold_pattern_match_as(self, node)
+    }
+
+    fn fold_pattern_match_star(
+        &mut self,
+        node: PatternMatchStar<TextRange>,
+    ) -> Result<PatternMatchStar<TextRange>, Infallible> {
+        self.bound
+            .extend(node.name.iter().map(|name| name.to_string()));
+        fold::fold_pattern_match_star(self, node)
+    }
+}
+
+/// The global names that a top level definition uses, leaving out its
+/// decorators and, for dataclasses, the methods that aren't translated
+fn used_names(stmt: &rStmt) -> Vec<String> {
+    let mut stmt = stmt.clone();
+
+    match &mut stmt {
+        rStmt::FunctionDef(fun_def) => fun_def.decorator_list.clear(),
+        rStmt::ClassDef(class) => {
+            class.decorator_list.clear();
+            class
+                .body
+                .retain(|stmt| matches!(stmt, rStmt::AnnAssign(_)));
+        }
+        _ => {}
+    }
+
+    let mut names = Names::default();
+    // Collecting the names can't fail
+    let _ = names.fold_stmt(stmt);
+
+    // Variables are local to the whole function, so a name that it binds
+    // anywhere isn't a global
+    let Names { mut used, bound } = names;
+    used.retain(|name| {
+        let global = name.split('.').next().unwrap_or(name);
+        !bound.iter().any(|bound| bound == global)
+    });
+    used
+}
+
+/// Replaces the global names of a definition that refer to definitions with
+/// other names, like the ones imported with `from module import f as g`, or
+/// used through their module, like `helpers.triple`
+struct Renames<'a>(&'a HashMap<String, String>);
+
+impl Fold<TextRange> for Renames<'_> {
+    type TargetU = TextRange;
+    type Error = Infallible;
+    type UserContext = ();
+
+    fn will_map_user(&mut self, _user: &TextRange) -> Self::UserContext {}
+
+    fn map_user(
+        &mut self,
+        user: TextRange,
+        _context: Self::UserContext,
+    ) -> Result<TextRange, Infallible> {
+        Ok(user)
+    }
+
+    fn fold_expr(
+        &mut self,
+        node: rExpr<TextRange>,
+    ) -> Result<rExpr<TextRange>, Infallible> {
+        let name = match &node {
+            rExpr::Name(nam) if nam.ctx.is_load() => Some(nam.id.to_string()),
+            rExpr::Attribute(att) => match att.value.as_ref() {
+                rExpr::Name(nam) if nam.ctx.is_load() => {
+                    Some(format!("{}.{}", nam.id, att.attr))
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        match name.and_then(|name| self.0.get(&name)) {
+            Some(renamed) => Ok(rExpr::Name(ExprName {
+                id: Identifier::new(renamed),
+                ctx: ExprContext::Load,
+                range: node.range(),
+            })),
+            None => fold::fold_expr(self, node),
+        }
+    }
+}
+
+/// Collects the calls of a function by its name
+struct Calls<'a> {
+    fun: &'a str,
+    calls: Vec<ExprCall<TextRange>>,
+}
+
+impl Fold<TextRange> for Calls<'_> {
+    type TargetU = TextRange;
+    type Error = Infallible;
+    type UserContext = ();
+
+    fn will_map_user(&mut self, _user: &TextRange) -> Self::UserContext {}
+
+    fn map_user(
+        &mut self,
+        user: TextRange,
+        _context: Self::UserContext,
+    ) -> Result<TextRange, Infallible> {
+        Ok(user)
+    }
+
+    fn fold_expr_call(
+        &mut self,
+        node: ExprCall<TextRange>,
+    ) -> Result<ExprCall<TextRange>, Infallible> {
+        if matches!(node.func.as_ref(), rExpr::Name(nam) if nam.id.as_str() == self.fun)
+        {
+            self.calls.push(node.clone());
+        }
+        fold::fold_expr_call(self, node)
+    }
+}
+
+/// The calls of the function `fun` in a statement, including the nested ones
+pub(crate) fn calls_of(stmt: &rStmt, fun: &str) -> Vec<ExprCall> {
+    let mut calls = Calls { fun, calls: vec![] };
+    // Collecting the calls can't fail
+    let _ = calls.fold_stmt(stmt.clone());
+    calls.calls
+}
+
+/// The name a top level statement defines, if it is a function, a class or
+/// an assignment to a single name, like a union of dataclasses
+fn defined_name(stmt: &rStmt) -> Option<&str> {
+    match stmt {
+        rStmt::FunctionDef(fun_def) => Some(fun_def.name.as_str()),
+        rStmt::ClassDef(class) => Some(class.name.as_str()),
+        rStmt::Assign(assign) => match assign.targets.as_slice() {
+            [rExpr::Name(nam)] => Some(nam.id.as_str()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Checks if a global is a value that can be a constant: a number, a string,
+/// or a tuple of them
+fn is_constant(value: &Bound<PyAny>) -> bool {
+    match value.downcast::<PyTuple>() {
+        Ok(tuple) => tuple.len() > 1 && tuple.iter().all(|el| is_constant(&el)),
+        Err(_) => {
+            value.is_instance_of::<PyInt>()
+                || value.is_instance_of::<PyFloat>()
+                || value.is_instance_of::<PyString>()
+        }
+    }
+}
+
+/// A module level constant that the reached definitions use
+pub struct Constant {
+    pub name: String,
+    /// The globals of the module of the constant
+    globals: Py<PyDict>,
+    /// The numeric type of its annotation, like the one of `MAX: u24 = 20`
+    annotation: Option<NumType>,
+    /// The numeric type the definitions were translated with
+    pub num_type: Option<NumType>,
+}
+
+impl Constant {
+    /// The current value of the constant
+    ///
+    /// # Errors
+    ///
+    /// Raises a `NameError` if the constant was deleted from its module.
+    pub fn value<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.globals.bind(py).get_item(&self.name)?.ok_or_else(|| {
+            PyNameError::new_err(format!("name '{}' is not defined", self.name))
+        })
+    }
+
+    /// The numeric type of a value of the constant
+    fn type_of(&self, value: &Bound<PyAny>) -> Option<NumType> {
+        self.annotation.or_else(|| NumType::of_value(value))
+    }
+}
+
+/// Definitions of Python code, from a module or from a single function or
+/// class whose module has no source, like the ones of a notebook
+struct Unit<'py> {
+    /// The name of the module, followed by the name of the function or class
+    /// if the unit only has its definition
+    key: String,
+    /// The global variables of the definitions
+    globals: Option<Bound<'py, PyDict>>,
+    statements: Vec<rStmt>,
+}
+
+impl<'py> Unit<'py> {
+    fn definition(&self, name: &str) -> Option<&rStmt> {
+        self.statements
+            .iter()
+            .find(|stmt| defined_name(stmt) == Some(name))
+    }
+
+    /// The module a name is imported from by a `from module import name`
+    /// statement, and its name there
+    fn import_of(&self, name: &str) -> Option<(String, String)> {
+        self.statements.iter().find_map(|stmt| {
+            let rStmt::ImportFrom(import) = stmt else {
+                return None;
+            };
+
+            let alias = import.names.iter().find(|alias| {
+                alias.asname.as_ref().unwrap_or(&alias.name).as_str() == name
+            })?;
+
+            let level = import.level.map_or(0, |level| level.to_usize());
+            let module = format!(
+                "{}{}",
+                ".".repeat(level),
+                import.module.as_ref().map_or("", |module| module.as_str())
+            );
+
+            Some((module, alias.name.to_string()))
+        })
+    }
+}
+
+/// A module or a definition the translation depends on, with the stamp of its
+/// source when it was translated
+struct Dependency {
+    module: String,
+    attr: Option<String>,
+    stamp: Stamp,
+}
+
+/// The definitions reached from a jitted function
+///
+/// # Fields
+///
+/// * `statements` - The reached definitions, with the dataclasses and unions
+///   before the functions
+/// * `source` - The sources of the modules the definitions come from, joined,
+///   which the ranges of the statements point into
+/// * `segments` - Where each of the joined sources starts, and the line of its
+///   file it starts at
+/// * `constants` - The module level constants the definitions use
+/// * `sources` - The stamp of the sources of the jitted function
+/// * `dependencies` - The other modules and definitions the translation
+///   depends on
+pub struct CallGraph {
+    pub statements: Vec<rStmt>,
+    pub source: String,
+    pub segments: Vec<(usize, usize)>,
+    pub constants: Vec<Constant>,
+    sources: Stamp,
+    dependencies: Vec<Dependency>,
+}
+
+/// Builds a `CallGraph`, loading the modules of the definitions as they are
+/// reached
+struct Builder<'py> {
+    py: Python<'py>,
+    /// Modules in this directory are the ones that can be followed into
+    root: PathBuf,
+    graph: CallGraph,
+    units: Vec<Unit<'py>>,
+    /// The reached definitions, by name, and the key of their unit
+    reached: HashMap<String, String>,
+}
+
+impl CallGraph {
+    /// Finds the definitions a function reaches, starting from its module
+    ///
+    /// A function that isn't defined at the top level of its module, like a
+    /// method or a nested function, is taken from its own source as if it
+    /// were, and so is a function whose module has no source.
+    ///
+    /// # Arguments
+    ///
+    /// * `function` - The jitted function
+    /// * `top_level` - Whether the function is defined at the top level of its module
+    /// * `filename` - The file of the function, for the errors
+    /// * `sources` - The source of the function and of its module
+    /// * `args` - The arguments of the call
+    ///
+    /// # Errors
+    ///
+    /// Raises an `UnsupportedPythonConstruct` if the sources can't be parsed, or
+    /// two reached definitions of different modules have the same name.
+    pub fn new(
+        function: &Bound<PyFunction>,
+        top_level: bool,
+        filename: &str,
+        sources: Sources,
+        args: &[Bound<PyAny>],
+    ) -> PyResult<Self> {
+        let py = function.py();
+        let name = function.getattr("__name__")?.to_string();
+        let module = py
+            .import_bound("inspect")?
+            .getattr("getmodule")?
+            .call1((function,))?
+            .extract::<Option<Bound<PyModule>>>()?;
+
+        let mut builder = Builder {
+            py,
+            root: root_dir(module.as_ref())?,
+            graph: CallGraph {
+                statements: vec![],
+                source: String::new(),
+                segments: vec![],
+                constants: vec![],
+                sources: sources.stamp(),
+                dependencies: vec![],
+            },
+            units: vec![],
+            reached: HashMap::new(),
+        };
+
+        let mut statements = match &sources.module {
+            Some(source) => builder.append(source, filename, 1)?,
+            None => vec![],
+        };
+
+        let defined = statements.iter().any(|stmt| {
+            matches!(stmt, rStmt::FunctionDef(fun_def) if fun_def.name.as_str() == name)
+        });
+
+        if !top_level || !defined {
+            // The function takes the place of the definitions of the module
+            // with its name
+            statements.retain(|stmt| {
+                !matches!(stmt, rStmt::FunctionDef(fun_def) if fun_def.name.as_str() == name)
+            });
+            statements.extend(builder.append(
+                &sources.function,
+                filename,
+                sources.line,
+            )?);
+        }
+
+        let key = match &module {
+            Some(module) => module.name()?.to_string(),
+            None => String::new(),
+        };
+        builder.units.push(Unit {
+            key,
+            globals: Some(function.getattr("__globals__")?.downcast_into()?),
+            statements,
+        });
+
+        builder.reach(0, &name)?;
+
+        // The dataclasses of the arguments, like the instance of a callable
+        // dataclass, are needed to convert them
+        for arg in args {
+            if !arg.hasattr("__dataclass_fields__")? {
+                continue;
+            }
+            if let Some((unit, name)) =
+                builder.follow_object(0, arg.get_type().as_any())?
+            {
+                builder.reach(unit, &name)?;
+            }
+        }
+
+        // Dataclasses are added to the Book before the unions of them, and
+        // both before the functions that use them
+        let rank = |stmt: &rStmt| match stmt {
+            rStmt::ClassDef(_) => 0,
+            rStmt::Assign(_) => 1,
+            _ => 2,
+        };
+        builder.graph.statements.sort_by_key(rank);
+
+        Ok(builder.graph)
+    }
+
+    /// Checks if the sources of the function, and of the other modules and
+    /// definitions the translation depends on, are still the ones that were
+    /// translated, and the constants still have the types they were translated
+    /// with
+    ///
+    /// The sources are only read again when their files were modified since
+    /// the translation, or when they have no file, like in notebooks.
+    pub fn is_fresh(&self, function: &Bound<PyFunction>) -> PyResult<bool> {
+        let py = function.py();
+        let sources = self.sources.is_current(|| {
+            Ok(Sources::of(function)
+                .ok()
+                .map(|sources| sources.stamp().hash))
+        })?;
+        if !sources {
+            return Ok(false);
+        }
+
+        for constant in &self.constants {
+            match constant.value(py) {
+                Ok(value)
+                    if is_constant(&value)
+                        && constant.type_of(&value) == constant.num_type => {}
+                _ => return Ok(false),
+            }
+        }
+
+        let modules = py.import_bound("sys")?.getattr("modules")?;
+
+        for dep in &self.dependencies {
+            let current = dep.stamp.is_current(|| {
+                let Ok(mut obj) = modules.get_item(&dep.module) else {
+                    return Ok(None);
+                };
+                if let Some(attr) = &dep.attr {
+                    let Ok(attr) = obj.getattr(attr.as_str()) else {
+                        return Ok(None);
+                    };
+                    obj = attr;
+                }
+
+                let source = match &dep.attr {
+                    Some(_) => object_source(&obj)?.map(|(source, _)| source),
+                    None => module_source(obj.downcast()?),
+                };
+                Ok(source.map(|source| hash_of(&source)))
+            })?;
+
+            if !current {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+impl<'py> Builder<'py> {
+    /// Appends a source to the joined sources and parses it
+    fn append(
+        &mut self,
+        source: &str,
+        filename: &str,
+        line: usize,
+    ) -> PyResult<Vec<rStmt>> {
+        let code = &mut self.graph.source;
+        if !code.is_empty() {
+            code.push('\n');
+        }
+
+        let offset = code.len();
+        code.push_str(source);
+        self.graph.segments.push((offset, line));
+
+        let offset = TextSize::try_from(offset).unwrap_or_default();
+        match parse_starts_at(source, Mode::Module, filename, offset) {
+            Ok(Mod::Module(module)) => Ok(module.body),
+            Ok(_) => unreachable!("Mode::Module always parses into a Module"),
+            Err(e) => Err(UnsupportedPythonConstruct::new_err(e.to_string())),
+        }
+    }
+
+    /// Adds the definition of a name, as seen from a unit, and the ones it
+    /// uses
+    fn reach(&mut self, unit: usize, name: &str) -> PyResult<()> {
+        let mut pending: Vec<_> =
+            self.resolve(unit, name)?.into_iter().collect();
+
+        while let Some((unit, name)) = pending.pop() {
+            if let Some(value) = self.constant(unit, &name)? {
+                if self.mark_reached(unit, &name)? {
+                    self.add_constant(unit, name, &value);
+                }
+                continue;
+            }
+
+            let Some(stmt) = self.units[unit].definition(&name).cloned() else {
+                continue;
+            };
+            if !self.mark_reached(unit, &name)? {
+                continue;
+            }
+
+            // The names the definition uses are replaced by the names of the
+            // definitions they refer to
+            let mut renames = HashMap::new();
+            for used in used_names(&stmt) {
+                // The attributes of globals that aren't modules are uses of
+                // the global
+                let used = match used.split_once('.') {
+                    Some((global, _))
+                        if self.module(unit, global)?.is_none() =>
+                    {
+                        global.to_string()
+                    }
+                    _ => used,
+                };
+                let Some((found, found_name)) = self.resolve(unit, &used)?
+                else {
+                    continue;
+                };
+                if used != found_name {
+                    renames.insert(used, found_name.clone());
+                }
+                pending.push((found, found_name));
+            }
+
+            // Renaming can't fail
+            let stmt = Renames(&renames)
+                .fold_stmt(stmt)
+                .unwrap_or_else(|e| match e {});
+            self.graph.statements.push(stmt);
+        }
+
+        Ok(())
+    }
+
+    /// Finds the unit that defines a name used by a unit, following its
+    /// imports, and the name of the definition there
+    ///
+    /// The attribute of a module, like `helpers.triple`, is found in the
+    /// module, while the ones of other globals are uses of the global.
+    fn resolve(
+        &mut self,
+        unit: usize,
+        name: &str,
+    ) -> PyResult<Option<(usize, String)>> {
+        if let Some((global, attr)) = name.split_once('.') {
+            return match self.module(unit, global)? {
+                Some(module) => self.resolve(module, attr),
+                None => self.resolve(unit, global),
+            };
+        }
+
+        let (mut unit, mut name) = (unit, name.to_string());
+        loop {
+            if self.constant(unit, &name)?.is_some()
+                || self.units[unit].definition(&name).is_some()
+            {
+                return Ok(Some((unit, name)));
+            }
+            match self.follow(unit, &name)? {
+                Some(found) => (unit, name) = found,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// The unit of a module that a unit has as a global, like the one of
+    /// `import pkg.helpers as helpers`, if it can be followed into
+    fn module(&mut self, unit: usize, name: &str) -> PyResult<Option<usize>> {
+        let value = match &self.units[unit].globals {
+            Some(globals) => globals.get_item(name)?,
+            None => None,
+        };
+        match value.map(|value| value.downcast_into::<PyModule>()) {
+            Some(Ok(module)) => self.load(&module),
+            _ => Ok(None),
+        }
+    }
+
+    /// The value of a global of a unit, if it's a constant
+    fn constant(
+        &self,
+        unit: usize,
+        name: &str,
+    ) -> PyResult<Option<Bound<'py, PyAny>>> {
+        let value = match &self.units[unit].globals {
+            Some(globals) => globals.get_item(name)?,
+            None => None,
+        };
+        Ok(value.filter(is_constant))
+    }
+
+    /// Marks a name of a unit as reached
+    ///
+    /// # Returns
+    ///
+    /// `false` if the name was already reached.
+    ///
+    /// # Errors
+    ///
+    /// Raises an `UnsupportedPythonConstruct` if a name of another unit with
+    /// the same name was reached.
+    fn mark_reached(&mut self, unit: usize, name: &str) -> PyResult<bool> {
+        let key = &self.units[unit].key;
+
+        match self.reached.get(name) {
+            Some(reached) if reached == key => Ok(false),
+            Some(reached) => Err(UnsupportedPythonConstruct::new_err(format!(
+                "The definitions of {} in {} and {} can't be translated together, as they have the same name",
+                name, reached, key
+            ))),
+            None => {
+                self.reached.insert(name.to_string(), key.clone());
+                Ok(true)
+            }
+        }
+    }
+
+    /// Adds a constant of the globals of a unit, with the type of its
+    /// annotation in the unit, if it has one
+    fn add_constant(
+        &mut self,
+        unit: usize,
+        name: String,
+        value: &Bound<'py, PyAny>,
+    ) {
+        let unit = &self.units[unit];
+        let annotation = unit.statements.iter().find_map(|stmt| match stmt {
+            rStmt::AnnAssign(assign) => match assign.target.as_ref() {
+                rExpr::Name(nam) if nam.id.as_str() == name => {
+                    NumType::from_annotation(&assign.annotation)
+                }
+                _ => None,
+            },
+            _ => None,
+        });
+
+        let Some(globals) = &unit.globals else {
+            return;
+        };
+        let mut constant = Constant {
+            name,
+            globals: globals.clone().unbind(),
+            annotation,
+            num_type: None,
+        };
+        constant.num_type = constant.type_of(value);
+        self.graph.constants.push(constant);
+    }
+
+    /// Finds the unit that defines a name that a unit imports or has in its
+    /// globals
+    ///
+    /// # Returns
+    ///
+    /// The unit and the name of the definition there, or `None` if the name
+    /// isn't defined by code that can be followed, like the standard library.
+    fn follow(
+        &mut self,
+        unit: usize,
+        name: &str,
+    ) -> PyResult<Option<(usize, String)>> {
+        let value = match &self.units[unit].globals {
+            Some(globals) => globals.get_item(name)?,
+            None => None,
+        };
+
+        // Functions and classes know their module
+        if let Some(mut value) = value {
+            if let Ok(bjit) = value.downcast::<PyBjit>() {
+                value = bjit.borrow().wraps.bind(self.py).clone();
+            }
+
+            if value.is_instance_of::<PyFunction>()
+                || value.is_instance_of::<PyType>()
+            {
+                return self.follow_object(unit, &value);
+            }
+        }
+
+        // Other values, like unions, are found through their import
+        let Some((module, attr)) = self.units[unit].import_of(name) else {
+            return Ok(None);
+        };
+
+        let package = match &self.units[unit].globals {
+            Some(globals) => globals.get_item("__package__")?,
+            None => None,
+        };
+        let module = self
+            .py
+            .import_bound("importlib")?
+            .getattr("import_module")?
+            .call1((module, package));
+
+        match module.ok().and_then(|module| module.downcast_into().ok()) {
+            Some(module) => Ok(self.load(&module)?.map(|unit| (unit, attr))),
+            None => Ok(None),
+        }
+    }
+
+    /// Finds the unit of a function or class
+    fn follow_object(
+        &mut self,
+        unit: usize,
+        obj: &Bound<'py, PyAny>,
+    ) -> PyResult<Option<(usize, String)>> {
+        let name = obj.getattr("__name__")?.to_string();
+        let modules = self.py.import_bound("sys")?.getattr("modules")?;
+        let Ok(module) = obj
+            .getattr("__module__")
+            .and_then(|module| modules.get_item(module))
+        else {
+            return Ok(None);
+        };
+        let module = module.downcast_into::<PyModule>()?;
+        let module_name = module.name()?.to_string();
+
+        if module_name == self.units[unit].key {
+            if self.units[unit].definition(&name).is_some() {
+                return Ok(Some((unit, name)));
+            }
+
+            // A definition of the module of the unit that isn't in its
+            // source, like the ones of the other cells of a notebook
+            let key = format!("{}.{}", module_name, name);
+            if let Some(index) = self.units.iter().position(|u| u.key == key) {
+                return Ok(Some((index, name)));
+            }
+
+            let Some((source, line)) = object_source(obj)? else {
+                return Ok(None);
+            };
+            let statements = self.append(&source, &key, line)?;
+
+            self.graph.dependencies.push(Dependency {
+                module: module_name,
+                attr: Some(name.clone()),
+                stamp: Stamp {
+                    hash: hash_of(&source),
+                    modified: None,
+                },
+            });
+            self.units.push(Unit {
+                key,
+                globals: Some(module.dict()),
+                statements,
+            });
+            return Ok(Some((self.units.len() - 1, name)));
+        }
+
+        Ok(self.load(&module)?.map(|unit| (unit, name)))
+    }
+
+    /// Loads a module as a unit, if it's one of the modules that can be
+    /// followed into
+    fn load(
+        &mut self,
+        module: &Bound<'py, PyModule>,
+    ) -> PyResult<Option<usize>> {
+        let key = module.name()?.to_string();
+        if let Some(index) = self.units.iter().position(|u| u.key == key) {
+            return Ok(Some(index));
+        }
+
+        if !self.is_followed(module) {
+            return Ok(None);
+        }
+
+        let filename = module.filename()?.to_string();
+        let modified = modified_at(&filename);
+        let Some(source) = module_source(module) else {
+            return Ok(None);
+        };
+        let statements = self.append(&source, &filename, 1)?;
+
+        self.graph.dependencies.push(Dependency {
+            module: key.clone(),
+            attr: None,
+            stamp: Stamp {
+                hash: hash_of(&source),
+                modified,
+            },
+        });
+        self.units.push(Unit {
+            key,
+            globals: Some(module.dict()),
+            statements,
+        });
+
+        Ok(Some(self.units.len() - 1))
+    }
+
+    /// Checks if a module is in the directory of the code of the jitted
+    /// function, and not an installed package
+    fn is_followed(&self, module: &Bound<PyModule>) -> bool {
+        let Ok(file) = module.filename() else {
+            return false;
+        };
+        let Ok(file) = Path::new(&file.to_string()).canonicalize() else {
+            return false;
+        };
+
+        file.starts_with(&self.root)
+            && !file.components().any(|part| {
+                part.as_os_str() == "site-packages"
+                    || part.as_os_str() == "dist-packages"
+            })
+    }
+}
+
+/// The directory of the code of a jitted function: the one of its top level
+/// package, the one of its script, or the working directory for the
+/// functions of a REPL or notebook
+fn root_dir(module: Option<&Bound<PyModule>>) -> PyResult<PathBuf> {
+    let Some((module, file)) = module
+        .and_then(|module| Some((module, module.filename().ok()?.to_string())))
+    else {
+        return Ok(std::env::current_dir()?);
+    };
+
+    let package = module
+        .getattr("__package__")
+        .ok()
+        .and_then(|package| package.extract::<String>().ok())
+        .unwrap_or_default();
+
+    let mut dir = Path::new(&file).canonicalize()?;
+    dir.pop();
+
+    // The module `a.b.c` is in `a/b`, and its package is the directory `a`
+    let depth = package.split('.').filter(|part| !part.is_empty()).count();
+    for _ in 1..depth {
+        dir.pop();
+    }
+
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod call_graph_tests {
+    use bend::fun::Name;
+    use rustpython_parser::parse;
+
+    use super::*;
+
+    /// Writes the modules of a package into a directory of its own and
+    /// imports one of them
+    fn import_package<'py>(
+        py: Python<'py>,
+        package: &str,
+        modules: &[(&str, &str)],
+        module: &str,
+    ) -> (Bound<'py, PyModule>, PathBuf) {
+        let dir = std::env::temp_dir().join(format!(
+            "benda_{}_{}",
+            package,
+            std::process::id()
+        ));
+        std::fs::create_dir_all(dir.join(package)).unwrap();
+        for (name, code) in modules {
+            let file = dir.join(package).join(format!("{}.py", name));
+            std::fs::write(file, code).unwrap();
+        }
+
+        let path = py.import_bound("sys").unwrap().getattr("path").unwrap();
+        path.call_method1("insert", (0, dir.to_string_lossy()))
+            .unwrap();
+
+        let name = format!("{}.{}", package, module);
+        (py.import_bound(name.as_str()).unwrap(), dir)
+    }
+
+    /// Builds the graph of a function of a module
+    fn graph_of(function: &Bound<PyAny>) -> PyResult<CallGraph> {
+        let function = function.downcast::<PyFunction>()?;
+        let name = function.getattr("__name__")?.to_string();
+        let qualname = function.getattr("__qualname__")?.to_string();
+        let filename = function
+            .getattr("__code__")?
+            .getattr("co_filename")?
+            .to_string();
+
+        CallGraph::new(
+            function,
+            qualname == name,
+            &filename,
+            Sources::of(function)?,
+            &[],
+        )
+    }
+
+    /// The names of the definitions of a graph
+    fn defined(graph: &CallGraph) -> Vec<String> {
+        graph
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                rStmt::FunctionDef(fun_def) => Some(fun_def.name.to_string()),
+                rStmt::ClassDef(class) => Some(class.name.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reached_definitions() {
+        pyo3::prepare_freethreaded_python();
+
+        let modules = [
+            ("__init__", ""),
+            ("shapes", "def area(w, h):\n    return w * h\n\ndef unused():\n    yield 0\n"),
+            ("other", "def scale(x):\n    return x * 3\n"),
+            ("helpers", "def triple(x):\n    return x * 3\n"),
+            ("main", "from .shapes import area\nfrom .other import scale as tripled\nfrom graph_pkg.helpers import triple as t3\nimport graph_pkg.helpers as helpers\n\ndef unreached(x):\n    yield x\n\ndef fun(x):\n    return area(x, 2)\n\ndef scale(x):\n    return x * 2\n\ndef clash(x):\n    return scale(x) + tripled(x)\n\ndef aliased(x):\n    return t3(x) + helpers.triple(x)\n\ndef make():\n    def fun(x):\n        return x + 1\n    return fun\n"),
+        ];
+
+        Python::with_gil(|py| {
+            let (main, dir) = import_package(py, "graph_pkg", &modules, "main");
+
+            // `area` is followed into the module it's imported from, while the
+            // definitions `fun` doesn't reach are left out, even the ones that
+            // can't be translated
+            let graph = graph_of(&main.getattr("fun").unwrap()).unwrap();
+            assert_eq!(defined(&graph), ["fun", "area"]);
+
+            let book =
+                crate::compile(py.None(), graph, "fun", &[]).unwrap().book;
+            assert!(book.defs.contains_key(&Name::new("area")));
+            assert!(!book.defs.contains_key(&Name::new("unreached")));
+            assert!(!book.defs.contains_key(&Name::new("unused")));
+
+            // Aliased imports and the attributes of imported modules are
+            // replaced by the names of the definitions they refer to
+            let aliased = main.getattr("aliased").unwrap();
+            let graph = graph_of(&aliased).unwrap();
+            assert_eq!(defined(&graph), ["aliased", "triple"]);
+
+            let aliased = crate::PyBjit::__new__(aliased.unbind());
+            let aliased = Py::new(py, aliased).unwrap().into_bound(py);
+            let result = aliased.call1((2,)).unwrap();
+            assert_eq!(result.extract::<u32>().unwrap(), 12);
+
+            // Two reached definitions can't have the same name
+            let err = graph_of(&main.getattr("clash").unwrap()).err().unwrap();
+            assert!(err.is_instance_of::<UnsupportedPythonConstruct>(py));
+            assert!(err.to_string().contains(
+                "The definitions of scale in graph_pkg.other and graph_pkg.main can't be translated together"
+            ));
+
+            // A nested function takes the place of the definition of the
+            // module with its name
+            let nested = main.getattr("make").unwrap().call0().unwrap();
+            let graph = graph_of(&nested).unwrap();
+            assert_eq!(defined(&graph), ["fun"]);
+
+            std::fs::remove_dir_all(dir).unwrap();
+        });
+    }
+
+    #[test]
+    fn global_names() {
+        let code = "@bjit\ndef fun(xs: list[Point], n):\n    total = helper(n) + shapes.area(n, n)\n    for x in xs:\n        match x:\n            case Point(a, b):\n                total += a * b\n    return [y + total for y in other(xs)]\n";
+
+        let Ok(Mod::Module(module)) = parse(code, Mode::Module, "test.py")
+        else {
+            unreachable!()
+        };
+
+        assert_eq!(
+            used_names(&module.body[0]),
+            ["list", "Point", "helper", "shapes.area", "Point", "other"]
+        );
+    }
+}
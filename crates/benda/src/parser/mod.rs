@@ -1,20 +1,162 @@
 #![allow(clippy::cmp_owned)]
-use core::panic;
+use std::path::Path;
 use std::vec;
 
 use bend::fun::{self, Adt, Book, CtrField, Name, Op, Rule, STRINGS};
 use bend::imp::{self, Expr, MatchArm, Stmt};
 use indexmap::IndexMap;
 use num_traits::cast::ToPrimitive;
-use pyo3::{Bound, PyAny};
+use num_type::{coerce, int_literal_type, is_arithmetic, is_signed, NumType};
+use pyo3::types::{
+    PyAnyMethods, PyBool, PyFloat, PyInt, PyTuple, PyTupleMethods,
+};
+use pyo3::{Bound, PyAny, PyResult};
 use rustpython_parser::ast::{
-    located, CmpOp as rCmpOp, Expr as rExpr, ExprAttribute, ExprBinOp,
-    Operator as rOperator, Pattern as rPattern, Stmt as rStmt, StmtAssign,
-    StmtClassDef, StmtExpr, StmtFunctionDef, StmtIf, StmtMatch,
+    located, Arguments, BoolOp as rBoolOp, CmpOp as rCmpOp, Comprehension,
+    Expr as rExpr, ExprAttribute, ExprBinOp, ExprBoolOp, ExprCall, ExprCompare,
+    ExprConstant, ExprSubscript, ExprUnaryOp, Operator as rOperator,
+    Pattern as rPattern, Ranged, Stmt as rStmt, StmtAnnAssign, StmtAssign,
+    StmtAugAssign, StmtClassDef, StmtContinue, StmtExpr, StmtFor,
+    StmtFunctionDef, StmtIf, StmtMatch, StmtWhile, UnaryOp as rUnaryOp,
+};
+
+use crate::benda_ffi::{run, RunError};
+use crate::errors::{
+    BendCompileError, BendRuntimeError, UnsupportedPythonConstruct,
+};
+use crate::types::bjit_result::TupleShape;
+use crate::types::i24::I24;
+use crate::types::{
+    extract_num, extract_type, extract_type_expr, maybe_none, maybe_some,
 };
 
-use crate::benda_ffi::run;
-use crate::types::{extract_type, extract_type_expr};
+pub(crate) mod call_graph;
+pub(crate) mod num_type;
+
+/// Bend definitions used by the translated code, like the ones behind the
+/// `in`, `//` and `**` operators
+const PRELUDE: &str = include_str!("prelude.bend");
+
+/// The definitions of the prelude that translate operators
+const OPERATORS: [&str; 5] = [
+    "benda/pow",
+    "benda/floordiv/i24",
+    "benda/floordiv/f24",
+    "benda/mod/i24",
+    "benda/mod/f24",
+];
+
+/// How many calls of functions without a return annotation are followed to
+/// find the type they return, which stops at recursive functions
+const MAX_RETURN_DEPTH: usize = 8;
+
+/// Returns the built-in definitions of Bend together with the prelude
+pub(crate) fn prelude() -> Book {
+    bend::fun::load_book::do_parse_book(
+        PRELUDE,
+        Path::new("prelude.bend"),
+        Book::builtins(),
+    )
+    .expect("The bjit prelude is valid Bend code")
+}
+
+/// Calls a definition of the prelude
+fn call_prelude(fun: &str, args: Vec<Expr>) -> Expr {
+    Expr::Call {
+        fun: Box::new(Expr::Var {
+            nam: Name::new(format!("benda/{}", fun)),
+        }),
+        args,
+        kwargs: vec![],
+    }
+}
+
+/// Finds the type wrapped by an optional type annotation, like
+/// `Optional[T]`, `T | None` or `None | T`
+pub(crate) fn optional_inner(annotation: &rExpr) -> Option<&rExpr> {
+    match annotation {
+        rExpr::Subscript(sub) => match sub.value.as_ref() {
+            rExpr::Name(nam) if nam.id.as_str() == "Optional" => {
+                Some(&sub.slice)
+            }
+            rExpr::Attribute(att) if att.attr.as_str() == "Optional" => {
+                Some(&sub.slice)
+            }
+            _ => None,
+        },
+        rExpr::BinOp(bin) if bin.op == rOperator::BitOr => {
+            match (is_none(&bin.left), is_none(&bin.right)) {
+                (false, true) => Some(&bin.left),
+                (true, false) => Some(&bin.right),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Checks if an expression is the `None` literal
+fn is_none(expr: &rExpr) -> bool {
+    matches!(expr, rExpr::Constant(c) if c.value.is_none())
+}
+
+/// The name of the variable a pattern binds, if it's a single variable
+fn target_var(pat: &imp::AssignPattern) -> Option<&str> {
+    match pat {
+        imp::AssignPattern::Var(nam) => Some(nam.as_ref()),
+        _ => None,
+    }
+}
+
+/// Checks if the return type of a function is annotated as `Optional`
+fn returns_optional(fun_def: &StmtFunctionDef) -> bool {
+    fun_def
+        .returns
+        .as_deref()
+        .is_some_and(|returns| optional_inner(returns).is_some())
+}
+
+/// Rebinds an `Optional` variable to its value in a block where it's known
+/// not to be `None`
+fn unwrap_var(nam: &str, block: Stmt) -> Stmt {
+    Stmt::Assign {
+        pat: imp::AssignPattern::Var(Name::new(nam)),
+        val: Box::new(call_prelude(
+            "unwrap",
+            vec![Expr::Var {
+                nam: Name::new(nam),
+            }],
+        )),
+        nxt: Some(Box::new(block)),
+    }
+}
+
+/// Finds the variable tested by `x is None` or `x is not None`, and whether
+/// the test holds when the variable is `None`
+fn none_test(test: &rExpr) -> Option<(&str, bool)> {
+    let rExpr::Compare(comp) = test else {
+        return None;
+    };
+
+    let ([op], [right]) = (comp.ops.as_slice(), comp.comparators.as_slice())
+    else {
+        return None;
+    };
+
+    let rExpr::Name(nam) = comp.left.as_ref() else {
+        return None;
+    };
+
+    match op {
+        rCmpOp::Is | rCmpOp::Eq if is_none(right) => {
+            Some((nam.id.as_str(), true))
+        }
+        rCmpOp::IsNot | rCmpOp::NotEq if is_none(right) => {
+            Some((nam.id.as_str(), false))
+        }
+        _ => None,
+    }
+}
 
 #[derive(Clone, Debug)]
 enum FromExpr {
@@ -33,6 +175,349 @@ impl FromExpr {
     }
 }
 
+/// Keeps only the statements of a parsed block, to be used as the `nxt` of
+/// another statement
+fn into_nxt(nxt: Option<FromExpr>) -> Option<Box<Stmt>> {
+    match nxt {
+        Some(FromExpr::Statement(nxt)) => Some(Box::new(nxt)),
+        _ => None,
+    }
+}
+
+/// Describes an expression in the errors of constructs that can't be translated
+fn expr_kind(expr: &rExpr) -> &'static str {
+    match expr {
+        rExpr::BoolOp(_) => "Boolean operators",
+        rExpr::NamedExpr(_) => "Assignment expressions",
+        rExpr::BinOp(_) => "Binary operators",
+        rExpr::UnaryOp(_) => "Unary operators",
+        rExpr::Lambda(_) => "Lambdas",
+        rExpr::IfExp(_) => "Conditional expressions",
+        rExpr::Dict(_) => "Dict literals",
+        rExpr::Set(_) => "Set literals",
+        rExpr::ListComp(_) => "List comprehensions",
+        rExpr::SetComp(_) => "Set comprehensions",
+        rExpr::DictComp(_) => "Dict comprehensions",
+        rExpr::GeneratorExp(_) => "Generator expressions",
+        rExpr::Await(_) => "`await` expressions",
+        rExpr::Yield(_) | rExpr::YieldFrom(_) => "`yield` expressions",
+        rExpr::Compare(_) => "Comparisons",
+        rExpr::Call(_) => "Calls",
+        rExpr::FormattedValue(_) | rExpr::JoinedStr(_) => "f-strings",
+        rExpr::Constant(_) => "Constants",
+        rExpr::Attribute(_) => "Attribute accesses",
+        rExpr::Subscript(_) => "Subscripts",
+        rExpr::Starred(_) => "Starred expressions",
+        rExpr::Name(_) => "Names",
+        rExpr::List(_) => "List literals",
+        rExpr::Tuple(_) => "Tuples",
+        rExpr::Slice(_) => "Slices",
+    }
+}
+
+/// Describes a statement in the errors of constructs that can't be translated
+fn stmt_kind(stmt: &rStmt) -> &'static str {
+    match stmt {
+        rStmt::FunctionDef(_) | rStmt::AsyncFunctionDef(_) => {
+            "Nested function definitions"
+        }
+        rStmt::ClassDef(_) => "Nested class definitions",
+        rStmt::Return(_) => "`return` statements",
+        rStmt::Delete(_) => "`del` statements",
+        rStmt::Assign(_) => "Assignments",
+        rStmt::TypeAlias(_) => "`type` statements",
+        rStmt::AugAssign(_) => "Augmented assignments",
+        rStmt::AnnAssign(_) => "Annotated assignments",
+        rStmt::For(_) | rStmt::AsyncFor(_) => "`for` loops",
+        rStmt::While(_) => "`while` loops",
+        rStmt::If(_) => "`if` statements",
+        rStmt::With(_) | rStmt::AsyncWith(_) => "`with` statements",
+        rStmt::Match(_) => "`match` statements",
+        rStmt::Raise(_) => "`raise` statements",
+        rStmt::Try(_) | rStmt::TryStar(_) => "`try` statements",
+        rStmt::Assert(_) => "`assert` statements",
+        rStmt::Import(_) | rStmt::ImportFrom(_) => "Imports",
+        rStmt::Global(_) => "`global` statements",
+        rStmt::Nonlocal(_) => "`nonlocal` statements",
+        rStmt::Expr(_) => "Expression statements",
+        rStmt::Pass(_) => "`pass` statements",
+        rStmt::Break(_) => "`break` statements",
+        rStmt::Continue(_) => "`continue` statements",
+    }
+}
+
+/// Collects the names of the variables assigned by the target of an assignment
+fn target_names(target: &rExpr, names: &mut Vec<String>) {
+    match target {
+        rExpr::Name(nam) => {
+            if !names.iter().any(|name| name == nam.id.as_str()) {
+                names.push(nam.id.to_string());
+            }
+        }
+        rExpr::Tuple(tup) => {
+            tup.elts.iter().for_each(|elt| target_names(elt, names))
+        }
+        rExpr::List(lst) => {
+            lst.elts.iter().for_each(|elt| target_names(elt, names))
+        }
+        _ => {}
+    }
+}
+
+/// Collects the names of the variables assigned by a block of statements,
+/// including the ones assigned inside its nested blocks
+fn assigned_names(stmts: &[rStmt], names: &mut Vec<String>) {
+    for stmt in stmts {
+        match stmt {
+            rStmt::Assign(assign) => {
+                for target in &assign.targets {
+                    target_names(target, names);
+                }
+            }
+            rStmt::AugAssign(assign) => target_names(&assign.target, names),
+            rStmt::AnnAssign(assign) => target_names(&assign.target, names),
+            rStmt::For(stmt_for) => {
+                target_names(&stmt_for.target, names);
+                assigned_names(&stmt_for.body, names);
+                assigned_names(&stmt_for.orelse, names);
+            }
+            rStmt::While(stmt_while) => {
+                assigned_names(&stmt_while.body, names);
+                assigned_names(&stmt_while.orelse, names);
+            }
+            rStmt::If(stmt_if) => {
+                assigned_names(&stmt_if.body, names);
+                assigned_names(&stmt_if.orelse, names);
+            }
+            rStmt::Match(m) => {
+                for case in &m.cases {
+                    assigned_names(&case.body, names);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Finds a `return` statement in a block or in one of its nested blocks
+fn find_return(stmts: &[rStmt]) -> Option<&rStmt> {
+    stmts.iter().find_map(|stmt| match stmt {
+        rStmt::Return(_) => Some(stmt),
+        rStmt::For(stmt_for) => {
+            find_return(&stmt_for.body).or(find_return(&stmt_for.orelse))
+        }
+        rStmt::While(stmt_while) => {
+            find_return(&stmt_while.body).or(find_return(&stmt_while.orelse))
+        }
+        rStmt::If(stmt_if) => {
+            find_return(&stmt_if.body).or(find_return(&stmt_if.orelse))
+        }
+        rStmt::Match(m) => m.cases.iter().find_map(|c| find_return(&c.body)),
+        _ => None,
+    })
+}
+
+/// Collects the variables an assignment binds to integer literals, with their
+/// literals
+fn int_literals<'a>(
+    pat: &'a imp::AssignPattern,
+    val: &'a mut Expr,
+    literals: &mut Vec<(&'a Name, &'a mut Expr)>,
+) {
+    match (pat, val) {
+        (imp::AssignPattern::Var(nam), val)
+            if int_literal_type(val).is_some() =>
+        {
+            literals.push((nam, val))
+        }
+        (imp::AssignPattern::Tup(pats), Expr::Tup { els })
+            if pats.len() == els.len() =>
+        {
+            for (pat, val) in pats.iter().zip(els) {
+                int_literals(pat, val, literals);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The type of a Python integer literal, like `2` or `-1`
+fn int_literal(expr: &rExpr) -> Option<NumType> {
+    match expr {
+        rExpr::Constant(c) if c.value.is_int() => Some(NumType::U24),
+        rExpr::UnaryOp(unary) if unary.op == rUnaryOp::USub => {
+            int_literal(&unary.operand).map(|_| NumType::I24)
+        }
+        _ => None,
+    }
+}
+
+/// Converts a number into an `f24`, whatever its type
+fn to_f24(expr: &mut Expr) {
+    let num = std::mem::replace(expr, Expr::Era);
+    *expr = Expr::Call {
+        fun: Box::new(Expr::Var {
+            nam: Name::new("to_f24"),
+        }),
+        args: vec![num],
+        kwargs: vec![],
+    };
+}
+
+/// Collects the values a block returns, including the ones of its nested
+/// blocks
+fn returned_values<'a>(stmts: &'a [rStmt], values: &mut Vec<&'a rExpr>) {
+    for stmt in stmts {
+        match stmt {
+            rStmt::Return(ret) => values.extend(ret.value.as_deref()),
+            rStmt::If(stmt_if) => {
+                returned_values(&stmt_if.body, values);
+                returned_values(&stmt_if.orelse, values);
+            }
+            rStmt::Match(m) => {
+                for case in &m.cases {
+                    returned_values(&case.body, values);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Checks if a block can leave the function or the loop it's in with a
+/// `return`, a `break` or a `continue`
+fn jumps(stmts: &[rStmt]) -> bool {
+    stmts.iter().any(|stmt| match stmt {
+        rStmt::Return(_) | rStmt::Break(_) | rStmt::Continue(_) => true,
+        rStmt::If(stmt_if) => jumps(&stmt_if.body) || jumps(&stmt_if.orelse),
+        _ => false,
+    })
+}
+
+/// Rewrites a block so that the statements following an `if` that jumps run
+/// only on the paths that don't jump, which is needed since Bend has no early
+/// returns. Inside loops, this makes every path end with a `break` or a
+/// `continue`, which is how the paths of a loop turned into a function return.
+///
+/// The statements that follow an `if` that jumps are copied into both of its
+/// branches, and the statements that follow a jump are dropped since they
+/// never run.
+fn close_paths(stmts: &[rStmt]) -> Vec<rStmt> {
+    let mut closed = vec![];
+
+    for (i, stmt) in stmts.iter().enumerate() {
+        match stmt {
+            rStmt::Return(_) | rStmt::Break(_) | rStmt::Continue(_) => {
+                closed.push(stmt.clone());
+                return closed;
+            }
+            rStmt::If(stmt_if)
+                if jumps(&stmt_if.body) || jumps(&stmt_if.orelse) =>
+            {
+                let rest = &stmts[i + 1..];
+                let mut stmt_if = stmt_if.clone();
+                stmt_if.body = close_paths(&[&stmt_if.body, rest].concat());
+                stmt_if.orelse = close_paths(&[&stmt_if.orelse, rest].concat());
+                closed.push(rStmt::If(stmt_if));
+                return closed;
+            }
+            _ => closed.push(stmt.clone()),
+        }
+    }
+
+    closed
+}
+
+/// Appends `tail` after the last statement of a block
+fn push_stmt(stmt: &mut Stmt, tail: Stmt) {
+    match stmt {
+        Stmt::Assign { nxt, .. }
+        | Stmt::If { nxt, .. }
+        | Stmt::Match { nxt, .. }
+        | Stmt::Switch { nxt, .. }
+        | Stmt::Bend { nxt, .. }
+        | Stmt::Fold { nxt, .. }
+        | Stmt::With { nxt, .. } => match nxt {
+            Some(nxt) => push_stmt(nxt, tail),
+            None => *nxt = Some(Box::new(tail)),
+        },
+        Stmt::InPlace { nxt, .. }
+        | Stmt::Ask { nxt, .. }
+        | Stmt::Open { nxt, .. }
+        | Stmt::Use { nxt, .. }
+        | Stmt::LocalDef { nxt, .. } => push_stmt(nxt, tail),
+        Stmt::Return { .. } | Stmt::Err => {}
+    }
+}
+
+/// Turns names into the variables that refer to them
+fn vars_of(names: &[Name]) -> Vec<Expr> {
+    names
+        .iter()
+        .map(|nam| Expr::Var { nam: nam.clone() })
+        .collect()
+}
+
+/// Returns the `range` call that a loop or comprehension iterates over, if any
+fn range_call(iter: &rExpr) -> Option<&ExprCall> {
+    match iter {
+        rExpr::Call(call) if matches!(call.func.as_ref(), rExpr::Name(nam) if nam.id.as_str() == "range") => {
+            Some(call)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the pattern that binds the variables carried by a loop and the
+/// expression holding their values, a tuple unless there's a single one
+fn loop_state(carried: &[Name]) -> (imp::AssignPattern, Expr) {
+    match carried {
+        [nam] => (
+            imp::AssignPattern::Var(nam.clone()),
+            Expr::Var { nam: nam.clone() },
+        ),
+        _ => (
+            imp::AssignPattern::Tup(
+                carried
+                    .iter()
+                    .map(|nam| imp::AssignPattern::Var(nam.clone()))
+                    .collect(),
+            ),
+            Expr::Tup {
+                els: vars_of(carried),
+            },
+        ),
+    }
+}
+
+/// Refers to a lifted nested function, applied to the variables it captures
+fn lifted_ref(lifted: &Name, captures: &[Name]) -> Expr {
+    let fun = Expr::Var {
+        nam: lifted.clone(),
+    };
+
+    if captures.is_empty() {
+        fun
+    } else {
+        Expr::Call {
+            fun: Box::new(fun),
+            args: vars_of(captures),
+            kwargs: vec![],
+        }
+    }
+}
+
+/// Returns the expression with the values bound by a pattern
+fn pattern_expr(pat: &imp::AssignPattern) -> Expr {
+    match pat {
+        imp::AssignPattern::Tup(pats) => Expr::Tup {
+            els: pats.iter().map(pattern_expr).collect(),
+        },
+        imp::AssignPattern::Var(nam) => Expr::Var { nam: nam.clone() },
+        _ => Expr::Era,
+    }
+}
+
 #[derive(PartialEq)]
 enum CurContext {
     Match,
@@ -47,111 +532,411 @@ struct Context {
 
 pub struct Parser<'py> {
     statements: Vec<rStmt>,
+    source: String,
     book: Book,
     definitions: Vec<imp::Definition>,
     ctx: Option<Context>,
     fun_args: Vec<(String, Bound<'py, PyAny>)>,
+    /// Variables bound so far in the function being parsed
+    locals: Vec<String>,
+    /// Number of loops and boolean operators translated so far, used to name
+    /// their bindings and helpers
+    loops: usize,
+    /// Name of the function being parsed
+    fun_name: String,
+    /// What `break` and `continue` turn into inside the current `while` loop
+    jumps: Option<LoopJumps>,
+    /// Functions nested in the function being parsed, with the name of their
+    /// lifted definition and the variables they capture
+    lifted: Vec<(String, Name, Vec<Name>)>,
+    /// Whether the function being parsed returns an `Optional` value
+    optional_return: bool,
+    /// Numeric types of the variables of the function being parsed, taken
+    /// from their annotations and the values assigned to them
+    num_types: Vec<(String, NumType)>,
+    /// Variables bound to integers whose type is given by their uses, like
+    /// the ones of `x = 1`, with the index of the integer in `int_types`
+    int_vars: Vec<(String, usize)>,
+    /// The type each integer bound in `int_vars` was given by its uses, if
+    /// any, and the type of its value
+    int_types: Vec<(Option<NumType>, NumType)>,
+    /// The function called with `fun_args`, whose parameters without an
+    /// annotation take the types of the arguments
+    called: Option<String>,
+    /// The types the integer arguments of the call were given by their uses
+    arg_types: Vec<(String, NumType)>,
+    /// Numeric types of the module level constants the functions use
+    constants: Vec<(String, NumType)>,
+    /// The definitions of the values of the constants
+    constant_defs: Vec<Name>,
+    /// Numeric type the function being parsed is annotated to return
+    return_type: Option<NumType>,
+    /// Where the sources joined into `source` start, like the ones of the
+    /// modules the function imports, and the line of their file they start at
+    segments: Vec<(usize, usize)>,
+    /// The shapes of the tuples each function returns
+    return_shapes: Vec<(String, TupleShape)>,
+}
+
+/// The bounds of a `range` call and the direction of its steps
+struct Range {
+    start: Expr,
+    stop: Expr,
+    /// Advances the counter by `step`
+    step_op: Op,
+    /// Compares the counter with `stop` to know if the range goes on
+    cond_op: Op,
+    step: u32,
+    /// The numeric type of the counter
+    ty: Option<NumType>,
+}
+
+/// The results of `break` and `continue` inside the helper function of a
+/// `while` loop
+#[derive(Clone)]
+struct LoopJumps {
+    brk: Expr,
+    cont: Expr,
 }
 
 impl<'py> Parser<'py> {
     pub fn new(
         statements: Vec<rStmt>,
         fun_args: Vec<(String, Bound<'py, PyAny>)>,
+        source: &str,
+    ) -> Self {
+        Self::with_book(statements, fun_args, source, prelude())
+    }
+
+    /// Creates a parser that adds its definitions to an existing Book, like
+    /// the Book of a module that was already compiled
+    ///
+    /// # Arguments
+    ///
+    /// * `statements` - The statements of the Python module
+    /// * `fun_args` - The names and values of the arguments of the call
+    /// * `source` - The source code of the Python module
+    /// * `book` - The Book to add the definitions to
+    pub fn with_book(
+        statements: Vec<rStmt>,
+        fun_args: Vec<(String, Bound<'py, PyAny>)>,
+        source: &str,
+        book: Book,
     ) -> Self {
         Self {
             statements,
-            book: bend::fun::Book::builtins(),
+            source: source.to_string(),
+            book,
             definitions: vec![],
             ctx: None,
             fun_args,
+            locals: vec![],
+            loops: 0,
+            fun_name: String::new(),
+            jumps: None,
+            lifted: vec![],
+            optional_return: false,
+            num_types: vec![],
+            int_vars: vec![],
+            int_types: vec![],
+            called: None,
+            arg_types: vec![],
+            constants: vec![],
+            constant_defs: vec![],
+            return_type: None,
+            segments: vec![],
+            return_shapes: vec![],
+        }
+    }
+
+    /// Marks the source after `offset` as joined to the previous one, like
+    /// the source of a method appended to the one of its module, so that the
+    /// errors point to the lines of the file it comes from
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Where the joined source starts
+    /// * `line` - The line of its file where the joined source starts
+    pub fn add_segment(&mut self, offset: usize, line: usize) {
+        self.segments.push((offset, line));
+    }
+
+    /// Declares the numeric type of a module level constant, so that the
+    /// numbers operated with it take its type
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the constant
+    /// * `num_type` - The numeric type of its value
+    pub fn add_constant_type(&mut self, name: &str, num_type: NumType) {
+        self.constants.push((name.to_string(), num_type));
+    }
+
+    /// Declares the function called with the arguments of the parser, so that
+    /// its parameters without an annotation take the numeric types of the
+    /// arguments, and the integers the type their uses give them
+    pub fn set_called(&mut self, fun: &str) {
+        self.called = Some(fun.to_string());
+    }
+
+    /// The numeric types the integer arguments of the call were given by
+    /// their uses in the called function
+    pub fn arg_types(&self) -> &[(String, NumType)] {
+        &self.arg_types
+    }
+
+    /// Converts the integer arguments of the call into the numeric types they
+    /// were given when the called function was translated
+    pub fn set_arg_types(&mut self, arg_types: Vec<(String, NumType)>) {
+        self.arg_types = arg_types;
+    }
+
+    /// Defines a module level constant with its value, as a definition
+    /// without parameters
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the constant
+    /// * `value` - Its value, a number, a string or a tuple of them
+    /// * `num_type` - The numeric type it was declared with, if it's a number
+    ///
+    /// # Errors
+    ///
+    /// Returns an `ArgumentConversionError` if the value can't be converted.
+    pub fn add_constant(
+        &mut self,
+        name: &str,
+        value: &Bound<'py, PyAny>,
+        num_type: Option<NumType>,
+    ) -> PyResult<()> {
+        let val = self.constant_value(value, num_type)?;
+        let nam = Name::new(name.to_string());
+
+        let def = fun::Definition {
+            name: nam.clone(),
+            rules: vec![Rule {
+                pats: vec![],
+                body: val.to_fun(),
+            }],
+            builtin: false,
+        };
+        self.book.defs.insert(nam.clone(), def);
+        self.constant_defs.push(nam);
+
+        Ok(())
+    }
+
+    /// Replaces the references to constants left in a result by their
+    /// values, as the HVM doesn't expand the ones that are only stored, like
+    /// the elements of a returned tuple
+    fn expand_constants(&self, term: &mut fun::Term) {
+        let value = match term {
+            fun::Term::Ref { nam } if self.constant_defs.contains(nam) => self
+                .book
+                .defs
+                .get(nam)
+                .and_then(|def| def.rules.first())
+                .map(|rule| rule.body.clone()),
+            _ => None,
+        };
+
+        if let Some(value) = value {
+            *term = value;
+            return;
+        }
+
+        for child in term.children_mut() {
+            self.expand_constants(child);
+        }
+    }
+
+    /// Converts the value of a constant, with the elements of tuples taking
+    /// the types of their values
+    fn constant_value(
+        &self,
+        value: &Bound<'py, PyAny>,
+        num_type: Option<NumType>,
+    ) -> PyResult<Expr> {
+        if let Ok(tuple) = value.downcast::<PyTuple>() {
+            let els = tuple
+                .iter()
+                .map(|el| self.constant_value(&el, NumType::of_value(&el)))
+                .collect::<PyResult<_>>()?;
+            return Ok(Expr::Tup { els });
+        }
+
+        let is_num = (value.is_instance_of::<PyInt>()
+            && !value.is_instance_of::<PyBool>())
+            || value.is_instance_of::<PyFloat>();
+
+        match num_type {
+            Some(ty) if is_num => extract_num(value.clone(), ty.builtin_type()),
+            _ => extract_type(value.clone(), &self.book),
         }
     }
 
-    fn parse_switch_expr(&self, att: ExprAttribute) -> Option<FromExpr> {
-        if let Some(lib) =
-            self.parse_expr_type(*att.value).unwrap().get_var_name()
+    /// Returns an `UnsupportedPythonConstruct` error pointing to the Python code
+    /// of `node`
+    fn unsupported<T>(&self, node: &impl Ranged, what: &str) -> PyResult<T> {
+        let start = usize::from(node.start());
+        let (from, first_line) = self
+            .segments
+            .iter()
+            .filter(|(offset, _)| *offset <= start)
+            .max_by_key(|(offset, _)| *offset)
+            .copied()
+            .unwrap_or((0, 1));
+        let line = self
+            .source
+            .get(from..start)
+            .map_or(0, |before| before.matches('\n').count() + first_line);
+        let code = self
+            .source
+            .get(start..usize::from(node.end()))
+            .and_then(|code| code.lines().next())
+            .unwrap_or_default();
+
+        Err(UnsupportedPythonConstruct::new_err(format!(
+            "{} can't be translated to Bend (line {}: {})",
+            what,
+            line,
+            code.trim()
+        )))
+    }
+
+    fn parse_switch_expr(
+        &mut self,
+        att: ExprAttribute,
+    ) -> PyResult<Option<FromExpr>> {
+        if let Some(lib) = self
+            .parse_expr_type(*att.value)?
+            .and_then(|value| value.get_var_name())
         {
             let fun = att.attr.to_string();
             if lib.to_string() == "benda" && fun == "switch" {
-                return Some(FromExpr::Expr(Expr::Call {
+                return Ok(Some(FromExpr::Expr(Expr::Call {
                     fun: Box::new(Expr::Var {
                         nam: Name::new("switch"),
                     }),
                     args: vec![],
                     kwargs: vec![],
-                }));
+                })));
             }
         }
-        None
+        Ok(None)
+    }
+
+    /// Parses an expression that must be translated into something
+    fn parse_required(&mut self, expr: rExpr) -> PyResult<FromExpr> {
+        match self.parse_expr_type(expr.clone())? {
+            Some(parsed) => Ok(parsed),
+            None => self.unsupported(&expr, "This expression"),
+        }
     }
 
-    fn parse_expr_type(&self, expr: rExpr) -> Option<FromExpr> {
+    /// Parses an expression that must be translated into a Bend expression
+    fn parse_expr(&mut self, expr: rExpr) -> PyResult<Expr> {
+        match self.parse_expr_type(expr.clone())? {
+            Some(FromExpr::Expr(parsed)) => Ok(parsed),
+            _ => self.unsupported(&expr, "This expression"),
+        }
+    }
+
+    fn parse_expr_type(&mut self, expr: rExpr) -> PyResult<Option<FromExpr>> {
         match expr {
             rExpr::Attribute(att) => {
-                if let Some(switch) = self.parse_switch_expr(att) {
-                    return Some(switch);
+                if let Some(switch) = self.parse_switch_expr(att.clone())? {
+                    return Ok(Some(switch));
                 }
-                None
+                self.unsupported(&att, "Attribute accesses")
             }
-            rExpr::Compare(comp) => {
-                let left = self.parse_expr_type(*comp.left).unwrap();
-                let right = self
-                    .parse_expr_type(comp.comparators.first().unwrap().clone())
-                    .unwrap();
+            rExpr::Compare(comp) => self.parse_compare(comp),
+            rExpr::UnaryOp(unary) => self.parse_unary_op(unary),
+            rExpr::BoolOp(bool_op) => self.parse_bool_op(bool_op),
+            rExpr::BinOp(bin_op) => self.parse_bin_op(bin_op),
+            rExpr::Tuple(tup) => {
+                if tup.elts.len() < 2 {
+                    return self.unsupported(
+                        &tup,
+                        "Tuples with less than two elements",
+                    );
+                }
 
-                let op = match comp.ops.first().unwrap() {
-                    rCmpOp::Eq => Op::EQ,
-                    rCmpOp::NotEq => Op::NEQ,
-                    rCmpOp::Lt => Op::LT,
-                    rCmpOp::LtE => todo!(),
-                    rCmpOp::Gt => Op::GT,
-                    rCmpOp::GtE => todo!(),
-                    rCmpOp::Is => todo!(),
-                    rCmpOp::IsNot => todo!(),
-                    rCmpOp::In => todo!(),
-                    rCmpOp::NotIn => todo!(),
-                };
+                let els = tup
+                    .elts
+                    .iter()
+                    .map(|elt| self.parse_expr(elt.clone()))
+                    .collect::<PyResult<_>>()?;
 
-                if let (FromExpr::Expr(left), FromExpr::Expr(right)) =
-                    (left, right)
-                {
-                    return Some(FromExpr::Expr(Expr::Opr {
-                        op,
-                        lhs: Box::new(left),
-                        rhs: Box::new(right),
-                    }));
-                }
-                None
+                Ok(Some(FromExpr::Expr(Expr::Tup { els })))
             }
-            rExpr::BinOp(bin_op) => self.parse_bin_op(bin_op),
-            rExpr::Constant(c) => match c.value {
-                located::Constant::None => todo!(),
-                located::Constant::Bool(_) => todo!(),
+            rExpr::Constant(c) => match &c.value {
+                located::Constant::None => {
+                    Ok(Some(FromExpr::Expr(maybe_none())))
+                }
+                // Bend has no booleans, conditions are numbers
+                located::Constant::Bool(val) => {
+                    Ok(Some(FromExpr::Expr(imp::Expr::Num {
+                        val: bend::fun::Num::U24(*val as u32),
+                    })))
+                }
                 located::Constant::Str(str) => {
                     let nam = Name::new(str.clone());
                     let adt = self.book.adts.get(&nam);
 
                     if let Some(_adt) = adt {
-                        return Some(FromExpr::Expr(imp::Expr::Var { nam }));
+                        return Ok(Some(FromExpr::Expr(imp::Expr::Var {
+                            nam,
+                        })));
                     }
-                    Some(FromExpr::Expr(Expr::Str {
+                    Ok(Some(FromExpr::Expr(Expr::Str {
                         val: STRINGS.get(str.as_str()),
-                    }))
+                    })))
+                }
+                located::Constant::Bytes(_) => {
+                    self.unsupported(&c, "Bytes literals")
                 }
-                located::Constant::Bytes(_) => todo!(),
                 located::Constant::Int(val) => {
-                    Some(FromExpr::Expr(imp::Expr::Num {
-                        val: bend::fun::Num::U24(val.to_u32().unwrap()),
-                    }))
+                    match val.to_u32().filter(|val| *val <= 0xFFFFFF) {
+                        Some(val) => Ok(Some(FromExpr::Expr(imp::Expr::Num {
+                            val: bend::fun::Num::U24(val),
+                        }))),
+                        None => self.unsupported(
+                            &c,
+                            "Integers that don't fit in a u24",
+                        ),
+                    }
+                }
+                located::Constant::Tuple(elts) => {
+                    if elts.len() < 2 {
+                        return self.unsupported(
+                            &c,
+                            "Tuples with less than two elements",
+                        );
+                    }
+
+                    let els = elts
+                        .iter()
+                        .map(|elt| {
+                            self.parse_expr(rExpr::Constant(ExprConstant {
+                                range: c.range,
+                                value: elt.clone(),
+                                kind: None,
+                            }))
+                        })
+                        .collect::<PyResult<_>>()?;
+
+                    Ok(Some(FromExpr::Expr(Expr::Tup { els })))
                 }
-                located::Constant::Tuple(_) => todo!(),
                 located::Constant::Float(val) => {
-                    Some(FromExpr::Expr(imp::Expr::Num {
-                        val: bend::fun::Num::F24(val.to_f32().unwrap()),
-                    }))
+                    Ok(Some(FromExpr::Expr(imp::Expr::Num {
+                        val: bend::fun::Num::F24(*val as f32),
+                    })))
+                }
+                located::Constant::Complex { real: _, imag: _ } => {
+                    self.unsupported(&c, "Complex numbers")
                 }
-                located::Constant::Complex { real: _, imag: _ } => todo!(),
-                located::Constant::Ellipsis => todo!(),
+                located::Constant::Ellipsis => self.unsupported(&c, "`...`"),
             },
 
             rExpr::Name(n) => {
@@ -171,441 +956,2594 @@ impl<'py> Parser<'py> {
                     }
                 }
 
-                Some(FromExpr::Expr(imp::Expr::Var {
+                if let Some((_, lifted, captures)) =
+                    self.lifted.iter().rev().find(|(nam, ..)| *nam == name)
+                {
+                    return Ok(Some(FromExpr::Expr(lifted_ref(
+                        lifted, captures,
+                    ))));
+                }
+
+                Ok(Some(FromExpr::Expr(imp::Expr::Var {
                     nam: Name::new(name),
-                }))
+                })))
+            }
+
+            rExpr::Lambda(lambda) => {
+                let names = self.parse_params(&lambda.args, &lambda)?;
+                let bod = self.with_vars(&names, |parser| {
+                    parser.parse_expr(*lambda.body.clone())
+                })?;
+
+                Ok(Some(FromExpr::Expr(Expr::Lam {
+                    names: names.into_iter().map(|nam| (nam, false)).collect(),
+                    bod: Box::new(bod),
+                })))
             }
 
             rExpr::Call(c) => {
                 let fun = c.clone().func;
 
-                let expr = self.parse_expr_type(*fun);
+                let expr = self.parse_expr_type(*fun)?;
 
                 if let Some(FromExpr::Expr(Expr::Var { ref nam })) = expr {
-                    if let Some(var) = extract_type_expr(c.clone()) {
-                        return Some(FromExpr::Expr(var));
+                    if let Some(var) = extract_type_expr(c.clone())? {
+                        return Ok(Some(FromExpr::Expr(var)));
+                    }
+
+                    if !c.keywords.is_empty() {
+                        return self.unsupported(&c, "Keyword arguments");
                     }
 
                     let mut args: Vec<Expr> = vec![];
 
-                    for arg in c.args {
-                        let arg = self.parse_expr_type(arg);
+                    for arg in c.args.iter() {
+                        args.push(self.parse_expr(arg.clone())?);
+                    }
 
-                        if let Some(FromExpr::Expr(e)) = arg {
-                            args.push(e);
-                        }
+                    self.expect_param_types(nam.as_ref(), &mut args, &c)?;
+
+                    if let Some(builtin) = self.builtin(nam.as_ref(), &args) {
+                        return Ok(Some(FromExpr::Expr(call_prelude(
+                            builtin, args,
+                        ))));
                     }
 
                     if let Some(val) = self.find_in_ctrs(nam) {
-                        return Some(FromExpr::Expr(imp::Expr::Ctr {
+                        return Ok(Some(FromExpr::Expr(imp::Expr::Ctr {
                             name: val.clone(),
                             args,
                             kwargs: vec![],
-                        }));
+                        })));
                     }
-                    return Some(FromExpr::Expr(imp::Expr::Call {
+                    return Ok(Some(FromExpr::Expr(imp::Expr::Call {
                         fun: Box::new(Expr::Var {
                             nam: Name::new(nam.to_string()),
                         }),
                         args,
                         kwargs: vec![],
-                    }));
+                    })));
                 }
-                expr
-            }
-            _ => todo!(),
-        }
-    }
 
-    fn parse_adt_create(
-        &self,
-        left: &FromExpr,
-        right: &FromExpr,
-    ) -> Option<FromExpr> {
-        if let (Some(nam_l), Some(nam_r)) =
-            (left.get_var_name(), right.get_var_name())
-        {
-            let adt_l = self.book.adts.get(&nam_l);
-            let adt_r = self.book.adts.get(&nam_r);
+                // Calls of lambdas, lifted functions and other expressions,
+                // while attributes are only `benda.switch()`
+                if let (Some(FromExpr::Expr(fun)), false) =
+                    (&expr, matches!(*c.func, rExpr::Attribute(_)))
+                {
+                    if !c.keywords.is_empty() {
+                        return self.unsupported(&c, "Keyword arguments");
+                    }
 
-            let mut is_adt = false;
+                    let args = c
+                        .args
+                        .iter()
+                        .map(|arg| self.parse_expr(arg.clone()))
+                        .collect::<PyResult<Vec<_>>>()?;
 
-            match (adt_l, adt_r) {
-                (None, None) => {}
-                (_, _) => {
-                    is_adt = true;
+                    let call = match fun.clone() {
+                        Expr::Call {
+                            fun,
+                            args: mut first,
+                            kwargs,
+                        } => {
+                            first.extend(args);
+                            Expr::Call {
+                                fun,
+                                args: first,
+                                kwargs,
+                            }
+                        }
+                        fun => Expr::Call {
+                            fun: Box::new(fun),
+                            args,
+                            kwargs: vec![],
+                        },
+                    };
+
+                    return Ok(Some(FromExpr::Expr(call)));
                 }
+                Ok(expr)
             }
+            rExpr::List(lst) => {
+                let els = lst
+                    .elts
+                    .iter()
+                    .map(|elt| self.parse_expr(elt.clone()))
+                    .collect::<PyResult<_>>()?;
 
-            let mut fields: Vec<CtrField> = vec![];
-
-            if is_adt {
-                fields.push(CtrField {
-                    nam: nam_l.clone(),
-                    rec: false,
-                });
-                fields.push(CtrField {
-                    nam: nam_r.clone(),
-                    rec: false,
-                });
-                return Some(FromExpr::CtrField(fields));
+                Ok(Some(FromExpr::Expr(Expr::Lst { els })))
+            }
+            rExpr::ListComp(comp) => {
+                self.parse_comprehension(&comp.elt, &comp.generators, &comp)
             }
+            // Generators are only consumed by the functions they're passed to,
+            // like `sum`, so they can be built as lists
+            rExpr::GeneratorExp(comp) => {
+                self.parse_comprehension(&comp.elt, &comp.generators, &comp)
+            }
+            rExpr::Subscript(sub) => self.parse_subscript(&sub),
+            _ => self.unsupported(&expr, expr_kind(&expr)),
         }
-
-        None
     }
 
-    fn parse_bin_op(&self, bin: ExprBinOp) -> Option<FromExpr> {
-        // TODO(#5): Treat case where expr type returns None
-
-        let left: FromExpr = self.parse_expr_type(*bin.left).unwrap();
-        let right: FromExpr = self.parse_expr_type(*bin.right).unwrap();
-
-        let op: Op = match bin.op {
-            rOperator::Add => Op::ADD,
-            rOperator::Sub => Op::SUB,
-            rOperator::Mult => Op::MUL,
-            rOperator::MatMult => todo!(),
-            rOperator::Div => Op::DIV,
-            rOperator::Mod => todo!(),
-            rOperator::Pow => Op::POW,
-            rOperator::LShift => Op::SHL,
-            rOperator::RShift => Op::SHR,
-            rOperator::BitOr => Op::OR,
-            rOperator::BitXor => Op::XOR,
-            rOperator::BitAnd => Op::AND,
-            rOperator::FloorDiv => todo!(),
-        };
-
-        if let Some(adt_op) = self.parse_adt_create(&left, &right) {
-            return Some(adt_op);
+    /// Finds the numeric type of an expression from the types of the
+    /// variables and functions it uses
+    ///
+    /// # Returns
+    ///
+    /// The type, or `None` if it's not known, like for integer literals,
+    /// which take the type of the numbers they are operated with.
+    fn infer(&self, expr: &Expr) -> Option<NumType> {
+        match expr {
+            Expr::Num {
+                val: val @ fun::Num::F24(_),
+            } => Some(NumType::of(val)),
+            Expr::Var { nam } => self
+                .num_types
+                .iter()
+                .find(|(var, _)| var == nam.as_ref())
+                .map(|(_, ty)| *ty)
+                .or_else(|| {
+                    self.int_var(nam.as_ref())
+                        .and_then(|int| self.int_types[int].0)
+                })
+                .or_else(|| {
+                    self.constants
+                        .iter()
+                        .find(|(constant, _)| {
+                            constant == nam.as_ref()
+                                && !self
+                                    .locals
+                                    .iter()
+                                    .any(|var| var == constant)
+                        })
+                        .map(|(_, ty)| *ty)
+                }),
+            Expr::Opr { op, lhs, rhs } if is_arithmetic(op) => {
+                self.infer(lhs).or_else(|| self.infer(rhs))
+            }
+            Expr::Call { fun, args, .. } => match fun.as_ref() {
+                Expr::Var { nam } if nam.as_ref() == "benda/len" => {
+                    Some(NumType::U24)
+                }
+                // The operators of the prelude result in the type of their
+                // first operand
+                Expr::Var { nam } if OPERATORS.contains(&nam.as_ref()) => {
+                    args.first().and_then(|arg| self.operand_type(arg))
+                }
+                Expr::Var { nam } if nam.as_ref() == "to_f24" => {
+                    Some(NumType::F24)
+                }
+                Expr::Var { nam } => {
+                    let def = self.find_def(nam.as_ref()).filter(|_| {
+                        !self.locals.iter().any(|var| var == nam.as_ref())
+                    })?;
+                    let args: Vec<_> =
+                        args.iter().map(|arg| self.default_type(arg)).collect();
+                    self.returned_type(def, &args, 0)
+                }
+                _ => None,
+            },
+            _ => None,
         }
+    }
 
-        if let (FromExpr::Expr(left), FromExpr::Expr(right)) = (left, right) {
-            let operation = imp::Expr::Opr {
-                op,
-                lhs: Box::new(left),
-                rhs: Box::new(right),
-            };
+    /// Finds the numeric type of an operand like `infer`, with the literals
+    /// and the integers of `int_vars` being `i24`s when some of them are
+    /// negative
+    fn operand_type(&self, expr: &Expr) -> Option<NumType> {
+        self.infer(expr).or_else(|| {
+            (is_signed(expr) || self.has_signed_int(expr))
+                .then_some(NumType::I24)
+        })
+    }
+
+    /// Finds the numeric type of an expression like `infer`, with the integers
+    /// whose type isn't known yet having the type of their value
+    fn default_type(&self, expr: &Expr) -> Option<NumType> {
+        self.infer(expr)
+            .or_else(|| int_literal_type(expr))
+            .or_else(|| self.int_default(expr))
+    }
 
-            return Some(FromExpr::Expr(operation));
+    /// The type of the value of the integers of `int_vars` an expression is
+    /// made of, if it is made of them and literals
+    fn int_default(&self, expr: &Expr) -> Option<NumType> {
+        match expr {
+            Expr::Var { nam } => {
+                self.int_var(nam.as_ref()).map(|int| self.int_type(int))
+            }
+            Expr::Opr { op, lhs, rhs } if is_arithmetic(op) => {
+                let lhs = self.int_default(lhs).or(int_literal_type(lhs))?;
+                let rhs = self.int_default(rhs).or(int_literal_type(rhs))?;
+                Some(match (lhs, rhs) {
+                    (NumType::U24, NumType::U24) => NumType::U24,
+                    _ => NumType::I24,
+                })
+            }
+            _ => None,
         }
-        todo!()
     }
 
-    fn parse_assign(&mut self, assign: &StmtAssign) -> Option<FromExpr> {
-        self.parse_expr_type(*assign.value.clone())
+    /// Checks if an expression uses an integer of `int_vars` that is negative
+    /// and wasn't given a type yet
+    fn has_signed_int(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Var { nam } => self
+                .int_var(nam.as_ref())
+                .is_some_and(|int| self.int_types[int] == (None, NumType::I24)),
+            Expr::Opr { op, lhs, rhs } if is_arithmetic(op) => {
+                self.has_signed_int(lhs) || self.has_signed_int(rhs)
+            }
+            _ => false,
+        }
     }
 
-    fn parse_match(
+    /// Gives both operands of an operation the same numeric type, so that
+    /// literals take the type of the other operand
+    ///
+    /// # Errors
+    ///
+    /// Returns an `UnsupportedPythonConstruct` if the operands have different
+    /// types.
+    fn unify(
         &mut self,
-        m: &StmtMatch,
-        stmts: &Vec<rStmt>,
-        index: &usize,
-    ) -> Option<imp::Stmt> {
-        let mut arms: Vec<imp::MatchArm> = vec![];
-        let mut patt: Vec<String> = vec![];
+        lhs: &mut Expr,
+        rhs: &mut Expr,
+        node: &impl Ranged,
+    ) -> PyResult<()> {
+        match (self.infer(lhs), self.infer(rhs)) {
+            // Integers operated with floats are converted, like Python does
+            (Some(NumType::F24), Some(NumType::U24 | NumType::I24)) => {
+                to_f24(rhs);
+                Ok(())
+            }
+            (Some(NumType::U24 | NumType::I24), Some(NumType::F24)) => {
+                to_f24(lhs);
+                Ok(())
+            }
+            (Some(left), Some(right)) if left != right => self.unsupported(
+                node,
+                &format!("Operations between {} and {} numbers", left, right),
+            ),
+            (Some(ty), _) => self.expect_type(rhs, ty, node),
+            (_, Some(ty)) => self.expect_type(lhs, ty, node),
+            (None, None) => Ok(()),
+        }
+    }
 
-        for case in &m.cases {
-            let pat = match &case.pattern {
-                rPattern::MatchValue(val) => {
-                    let expr =
-                        self.parse_expr_type(*val.value.clone()).unwrap();
-                    match expr {
-                        FromExpr::Expr(imp::Expr::Var { nam }) => Some(nam),
-                        _ => None,
+    /// Gives an expression the numeric type it's annotated with, or the one
+    /// of the numbers it's operated with
+    ///
+    /// # Errors
+    ///
+    /// Returns an `UnsupportedPythonConstruct` if the expression has another
+    /// type, has literals that can't have the type, or calls a function whose
+    /// type isn't known where an `i24` or `f24` value is expected.
+    fn expect_type(
+        &mut self,
+        expr: &mut Expr,
+        ty: NumType,
+        node: &impl Ranged,
+    ) -> PyResult<()> {
+        if let Some(actual) = self.infer(expr).filter(|actual| *actual != ty) {
+            return self.unsupported(
+                node,
+                &format!("{} values where {} values are expected", actual, ty),
+            );
+        }
+
+        // Values of an unknown type are taken as the default `u24`s, which
+        // the results of a function without annotations most likely are
+        if ty != NumType::U24 {
+            if let Some(fun) = self.untyped_call(expr) {
+                return self.unsupported(
+                    node,
+                    &format!(
+                        "Results of {}, whose type isn't known, where {} values are expected",
+                        fun, ty
+                    ),
+                );
+            }
+        }
+
+        if !coerce(expr, ty) {
+            return self.unsupported(
+                node,
+                &format!("Number literals that aren't a valid {}", ty),
+            );
+        }
+
+        self.give_int_type(expr, ty, node)
+    }
+
+    /// Gives a numeric type to the integers of `int_vars` an expression uses
+    /// that don't have one yet
+    ///
+    /// # Errors
+    ///
+    /// Returns an `UnsupportedPythonConstruct` if a negative integer would be
+    /// a `u24`.
+    fn give_int_type(
+        &mut self,
+        expr: &Expr,
+        ty: NumType,
+        node: &impl Ranged,
+    ) -> PyResult<()> {
+        match expr {
+            Expr::Var { nam } => {
+                let Some(int) = self.int_var(nam.as_ref()) else {
+                    return Ok(());
+                };
+                match self.int_types[int] {
+                    (None, NumType::I24) if ty == NumType::U24 => self
+                        .unsupported(
+                            node,
+                            "i24 values where u24 values are expected",
+                        ),
+                    (None, _) => {
+                        self.int_types[int].0 = Some(ty);
+                        Ok(())
                     }
+                    (Some(_), _) => Ok(()),
                 }
+            }
+            Expr::Opr { op, lhs, rhs } if is_arithmetic(op) => {
+                self.give_int_type(lhs, ty, node)?;
+                self.give_int_type(rhs, ty, node)
+            }
+            _ => Ok(()),
+        }
+    }
 
-                rPattern::MatchClass(class) => {
-                    let expr =
-                        self.parse_expr_type(*class.cls.clone()).unwrap();
+    /// Finds a call, in an expression of an unknown type, of a function of
+    /// the module whose numeric type isn't known
+    fn untyped_call<'a>(&self, expr: &'a Expr) -> Option<&'a Name> {
+        if self.infer(expr).is_some() {
+            return None;
+        }
 
-                    for val in class.patterns.clone() {
-                        if let rPattern::MatchAs(match_as) = val {
-                            if let Some(name) = &match_as.name {
-                                patt.push(name.to_string());
-                            }
-                        }
-                    }
+        match expr {
+            Expr::Call { fun, .. } => match fun.as_ref() {
+                Expr::Var { nam }
+                    if self.find_def(nam.as_ref()).is_some()
+                        && !self
+                            .locals
+                            .iter()
+                            .any(|var| var == nam.as_ref()) =>
+                {
+                    Some(nam)
+                }
+                _ => None,
+            },
+            Expr::Opr { op, lhs, rhs } if is_arithmetic(op) => {
+                self.untyped_call(lhs).or_else(|| self.untyped_call(rhs))
+            }
+            _ => None,
+        }
+    }
 
-                    match expr {
-                        FromExpr::Expr(imp::Expr::Var { nam }) => Some(nam),
-                        _ => None,
+    /// Gives the arguments of a call to a function of the module the numeric
+    /// types its parameters are annotated with
+    fn expect_param_types(
+        &mut self,
+        fun: &str,
+        args: &mut [Expr],
+        node: &impl Ranged,
+    ) -> PyResult<()> {
+        if self.locals.iter().any(|local| local == fun) {
+            return Ok(());
+        }
+        let Some(def) = self.find_def(fun) else {
+            return Ok(());
+        };
+
+        let types: Vec<_> = def
+            .args
+            .args
+            .iter()
+            .map(|param| {
+                param
+                    .def
+                    .annotation
+                    .as_deref()
+                    .and_then(NumType::from_annotation)
+            })
+            .collect();
+        // The integers passed to parameters without an annotation keep the
+        // type of their value, which the callee's result is inferred from
+        for (arg, ty) in args.iter_mut().zip(types) {
+            let ty = ty.or_else(|| {
+                self.infer(arg).is_none().then(|| self.int_default(arg))?
+            });
+            if let Some(ty) = ty {
+                self.expect_type(arg, ty, node)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records the numeric type of a variable, or forgets it when it's not
+    /// known
+    fn set_num_type(&mut self, name: &str, ty: Option<NumType>) {
+        self.num_types.retain(|(var, _)| var != name);
+        self.int_vars.retain(|(var, _)| var != name);
+        if let Some(ty) = ty {
+            self.num_types.push((name.to_string(), ty));
+        }
+    }
+
+    /// Binds a variable to an integer whose type is given by its uses, like
+    /// the one of `x = 1`
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable
+    /// * `ty` - The type of the integer while its uses don't give it one
+    ///
+    /// # Returns
+    ///
+    /// The index of the integer in `int_types`.
+    fn bind_int(&mut self, name: &str, ty: NumType) -> usize {
+        self.set_num_type(name, None);
+        self.int_types.push((None, ty));
+        let int = self.int_types.len() - 1;
+        self.int_vars.push((name.to_string(), int));
+        int
+    }
+
+    /// The index in `int_types` of the integer a variable is bound to, if it
+    /// is one of `int_vars`
+    fn int_var(&self, name: &str) -> Option<usize> {
+        self.int_vars
+            .iter()
+            .rev()
+            .find(|(var, _)| var == name)
+            .map(|(_, int)| *int)
+    }
+
+    /// The type an integer of `int_types` was given by its uses, or the one of
+    /// its value
+    fn int_type(&self, int: usize) -> NumType {
+        let (given, ty) = self.int_types[int];
+        given.unwrap_or(ty)
+    }
+
+    /// Records the numeric types of the variables bound by an assignment,
+    /// with the integer literals having the type of their value until their
+    /// uses give them one
+    fn bind_num_types(&mut self, pat: &imp::AssignPattern, val: &Expr) {
+        match (pat, val) {
+            (imp::AssignPattern::Var(nam), val) => {
+                let ty = self.infer(val);
+                match ty.or_else(|| int_literal_type(val)) {
+                    Some(int) if ty.is_none() => {
+                        self.bind_int(nam.as_ref(), int);
                     }
+                    _ => self.set_num_type(nam.as_ref(), ty),
+                }
+            }
+            (imp::AssignPattern::Tup(pats), Expr::Tup { els })
+                if pats.len() == els.len() =>
+            {
+                for (pat, val) in pats.iter().zip(els) {
+                    self.bind_num_types(pat, val);
+                }
+            }
+            (imp::AssignPattern::Tup(pats), _) => {
+                for pat in pats {
+                    self.bind_num_types(pat, &Expr::Era);
                 }
-                rPattern::MatchSingleton(_) => todo!(),
-                rPattern::MatchSequence(_) => todo!(),
-                rPattern::MatchMapping(_) => todo!(),
-                rPattern::MatchStar(_) => todo!(),
-                rPattern::MatchAs(_) => todo!(),
-                rPattern::MatchOr(_) => todo!(),
+            }
+            _ => {}
+        }
+    }
+
+    /// Records the numeric types of the parameters of a function, given by
+    /// their annotations or by the calls of the function
+    fn bind_param_types(&mut self, fun_def: &StmtFunctionDef) {
+        let call_types = self.call_types(fun_def);
+        for (index, arg) in fun_def.args.args.iter().enumerate() {
+            let ty = match arg.def.annotation.as_deref() {
+                Some(annotation) => NumType::from_annotation(annotation),
+                None => call_types.get(index).copied().flatten(),
             };
+            self.set_num_type(arg.def.arg.as_str(), ty);
+        }
+    }
 
-            let sub = self.parse_expr_type(*m.subject.clone());
+    /// Finds the numeric types of the arguments the other functions call a
+    /// function with, so that its parameters without an annotation can have
+    /// them, like the `x` of `def half(x): return x / 2` called as
+    /// `half(1.5)`
+    ///
+    /// # Returns
+    ///
+    /// The type of each parameter, for the ones that all the calls give the
+    /// same type.
+    fn call_types(&self, fun_def: &StmtFunctionDef) -> Vec<Option<NumType>> {
+        let name = fun_def.name.as_str();
+        if self.called.as_deref() == Some(name) {
+            return vec![];
+        }
 
-            if let Some(FromExpr::Expr(Expr::Var { nam })) = sub {
-                self.ctx = Some(Context {
-                    now: CurContext::Match,
-                    vars: patt.clone(),
-                    subs: vec![nam.to_string()],
+        let mut types: Option<Vec<Option<NumType>>> = None;
+        for stmt in &self.statements {
+            let rStmt::FunctionDef(caller) = stmt else {
+                continue;
+            };
+            // The recursive calls get their types from the other calls
+            if caller.name.as_str() == name {
+                continue;
+            }
+
+            // The called function has the types of the arguments it's run
+            // with
+            let caller_args = self.runtime_types(caller);
+            for call in call_graph::calls_of(stmt, name) {
+                let call_types =
+                    fun_def.args.args.iter().enumerate().map(|(index, _)| {
+                        let arg = call.args.get(index)?;
+                        int_literal(arg).or_else(|| {
+                            self.python_type(arg, caller, &caller_args, 0)
+                        })
+                    });
+                types = Some(match types {
+                    None => call_types.collect(),
+                    Some(types) => types
+                        .into_iter()
+                        .zip(call_types)
+                        .map(|(ty, call_ty)| {
+                            ty.filter(|ty| Some(*ty) == call_ty)
+                        })
+                        .collect(),
                 });
             }
+        }
+        types.unwrap_or_default()
+    }
 
-            let stmt_arm = self.parse_vec(&case.body.clone(), 0);
+    /// The numeric types of the arguments of the call for the parameters of
+    /// the called function, or none for the other functions
+    fn runtime_types(&self, fun_def: &StmtFunctionDef) -> Vec<Option<NumType>> {
+        if self.called.as_deref() != Some(fun_def.name.as_str()) {
+            return vec![];
+        }
+        fun_def
+            .args
+            .args
+            .iter()
+            .map(|param| {
+                let name = param.def.arg.as_str();
+                let (_, value) =
+                    self.fun_args.iter().find(|(arg, _)| arg == name)?;
+                NumType::of_value(value)
+            })
+            .collect()
+    }
 
-            if let Some(pat) = pat {
-                let first = self.find_in_ctrs(&pat);
-                if let Some(FromExpr::Statement(a)) = stmt_arm {
-                    let arm = MatchArm { lft: first, rgt: a };
-                    arms.push(arm);
+    /// Gives the parameters without an annotation of the called function the
+    /// numeric types of the arguments of the call, with the integers taking
+    /// the type their uses give them
+    ///
+    /// # Returns
+    ///
+    /// The parameters bound to integers, and their index in `int_types`.
+    fn bind_arg_types(
+        &mut self,
+        fun_def: &StmtFunctionDef,
+    ) -> Vec<(String, usize)> {
+        if self.called.as_deref() != Some(fun_def.name.as_str()) {
+            return vec![];
+        }
+
+        let mut ints = vec![];
+        for param in &fun_def.args.args {
+            let name = param.def.arg.as_str();
+            let arg = self.fun_args.iter().find(|(arg, _)| arg == name);
+            let ty = match arg {
+                Some((_, value)) if param.def.annotation.is_none() => {
+                    NumType::of_value(value)
+                }
+                _ => None,
+            };
+
+            match ty {
+                Some(NumType::F24) => self.set_num_type(name, ty),
+                Some(ty) => {
+                    ints.push((name.to_string(), self.bind_int(name, ty)))
                 }
+                None => {}
             }
         }
+        ints
+    }
 
-        self.ctx = None;
+    /// Finds the numeric type a function without a return annotation returns
+    /// from the expressions it returns, like `x * 2` for a parameter `x: f24`
+    ///
+    /// # Arguments
+    ///
+    /// * `fun_def` - The function
+    /// * `args` - The types of the arguments of the call, which the
+    ///   parameters without an annotation take
+    /// * `depth` - How many calls were followed to get to the function, to
+    ///   stop at the recursive ones
+    fn returned_type(
+        &self,
+        fun_def: &StmtFunctionDef,
+        args: &[Option<NumType>],
+        depth: usize,
+    ) -> Option<NumType> {
+        if let Some(returns) = fun_def.returns.as_deref() {
+            return NumType::from_annotation(returns);
+        }
+        if depth > MAX_RETURN_DEPTH {
+            return None;
+        }
 
-        if let Some(FromExpr::Expr(subj)) =
-            self.parse_expr_type(*m.subject.clone())
-        {
-            let nxt = self.parse_vec(stmts, index + 1);
+        // A returned integer literal is a `u24` or an `i24`, while the
+        // literals operated with other numbers take their type
+        let mut values = vec![];
+        returned_values(&fun_def.body, &mut values);
+        values.into_iter().find_map(|value| {
+            int_literal(value)
+                .or_else(|| self.python_type(value, fun_def, args, depth))
+        })
+    }
 
-            let my_nxt: Option<Box<Stmt>> = match nxt {
-                Some(FromExpr::Statement(s)) => Some(Box::new(s)),
+    /// Finds the numeric type of a Python expression of a function from the
+    /// types of its parameters and the functions it calls
+    fn python_type(
+        &self,
+        expr: &rExpr,
+        fun_def: &StmtFunctionDef,
+        args: &[Option<NumType>],
+        depth: usize,
+    ) -> Option<NumType> {
+        match expr {
+            rExpr::Constant(c) => match &c.value {
+                located::Constant::Float(_) => Some(NumType::F24),
                 _ => None,
-            };
-
-            let name = match subj.clone() {
-                Expr::Var { nam } => Some(nam),
+            },
+            rExpr::Name(nam) => {
+                let Some((index, param)) = fun_def
+                    .args
+                    .args
+                    .iter()
+                    .enumerate()
+                    .find(|(_, param)| param.def.arg == nam.id)
+                else {
+                    // Other names can be module level constants
+                    return self
+                        .constants
+                        .iter()
+                        .find(|(constant, _)| constant == nam.id.as_str())
+                        .map(|(_, ty)| *ty);
+                };
+                match param.def.annotation.as_deref() {
+                    Some(annotation) => NumType::from_annotation(annotation),
+                    None => args.get(index).copied().flatten(),
+                }
+            }
+            // The integers operated with floats are converted into floats
+            rExpr::BinOp(bin) => {
+                let left = self.python_type(&bin.left, fun_def, args, depth);
+                let right = self.python_type(&bin.right, fun_def, args, depth);
+                match (left, right) {
+                    (Some(NumType::F24), _) | (_, Some(NumType::F24)) => {
+                        Some(NumType::F24)
+                    }
+                    _ => left.or(right),
+                }
+            }
+            rExpr::UnaryOp(unary) if unary.op != rUnaryOp::Not => {
+                self.python_type(&unary.operand, fun_def, args, depth)
+            }
+            rExpr::Call(call) => match call.func.as_ref() {
+                rExpr::Name(nam) if nam.id.as_str() == "len" => {
+                    Some(NumType::U24)
+                }
+                rExpr::Name(nam) => {
+                    let def = self.find_def(nam.id.as_str())?;
+                    let call_args: Vec<_> = call
+                        .args
+                        .iter()
+                        .map(|arg| {
+                            int_literal(arg).or_else(|| {
+                                self.python_type(arg, fun_def, args, depth)
+                            })
+                        })
+                        .collect();
+                    self.returned_type(def, &call_args, depth + 1)
+                }
                 _ => None,
-            };
+            },
+            _ => None,
+        }
+    }
 
-            let ret_match = imp::Stmt::Match {
-                arg: Box::new(subj.clone()),
-                bnd: name,
-                arms,
-                nxt: my_nxt,
-                with_bnd: vec![],
-                with_arg: vec![],
+    /// Checks if an expression is already an `Optional` value, which is
+    /// `None` or the result of a function of the module that returns one
+    fn is_optional(&self, expr: &rExpr) -> bool {
+        if is_none(expr) {
+            return true;
+        }
+
+        let rExpr::Call(call) = expr else {
+            return false;
+        };
+        let rExpr::Name(nam) = call.func.as_ref() else {
+            return false;
+        };
+
+        let shadowed = self.locals.iter().any(|local| local == nam.id.as_str())
+            || self
+                .lifted
+                .iter()
+                .any(|(lifted, ..)| lifted == nam.id.as_str());
+
+        !shadowed
+            && self.find_def(nam.id.as_str()).is_some_and(returns_optional)
+    }
+
+    /// Finds a function defined at the top level of the module
+    fn find_def(&self, name: &str) -> Option<&StmtFunctionDef> {
+        self.statements.iter().find_map(|stmt| match stmt {
+            rStmt::FunctionDef(def) if def.name.as_str() == name => Some(def),
+            _ => None,
+        })
+    }
+
+    /// Finds the definition of the prelude behind a call of a Python builtin,
+    /// like `len(xs)`, unless the name is bound by the Python code itself
+    fn builtin(&self, nam: &str, args: &[Expr]) -> Option<&'static str> {
+        let builtin = match (nam, args.len()) {
+            ("len", 1) => "len",
+            ("sum", 1) => "sum",
+            _ => return None,
+        };
+
+        let shadowed = self.locals.iter().any(|local| local == nam)
+            || self.find_def(nam).is_some();
+
+        (!shadowed).then_some(builtin)
+    }
+
+    /// Parses a list comprehension like `[x * 2 for x in xs if x > 0]`, which
+    /// Bend turns into a fold over the iterated list
+    fn parse_comprehension(
+        &mut self,
+        elt: &rExpr,
+        generators: &[Comprehension],
+        node: &impl Ranged,
+    ) -> PyResult<Option<FromExpr>> {
+        let [generator] = generators else {
+            return self
+                .unsupported(node, "Comprehensions with more than one `for`");
+        };
+
+        if generator.is_async {
+            return self.unsupported(node, "Asynchronous comprehensions");
+        }
+
+        let rExpr::Name(bind) = &generator.target else {
+            return self.unsupported(
+                &generator.target,
+                "Comprehension targets other than a single variable",
+            );
+        };
+
+        let bind = Name::new(bind.id.to_string());
+
+        // The variable of the comprehension doesn't exist outside of it
+        let num_types = self.num_types.clone();
+        let int_vars = self.int_vars.clone();
+        let (mut iter, int) = match range_call(&generator.iter) {
+            Some(call) => {
+                let (iter, ty) = self.range_list(call)?;
+                self.set_num_type(bind.as_ref(), ty);
+                (iter, None)
+            }
+            None => {
+                let mut iter = self.parse_expr(generator.iter.clone())?;
+                let int =
+                    self.bind_list_item(bind.as_ref(), &mut iter, node)?;
+                (iter, int)
+            }
+        };
+
+        let parsed = self.with_vars(&[bind.clone()], |parser| {
+            let term = parser.parse_expr(elt.clone())?;
+
+            // Many conditions must all hold, like in Python
+            let mut cond: Option<Expr> = None;
+            for test in &generator.ifs {
+                let test = parser.parse_expr(test.clone())?;
+                cond = Some(match cond {
+                    Some(acc) => parser.short_circuit(rBoolOp::And, acc, test),
+                    None => test,
+                });
+            }
+            Ok((term, cond))
+        });
+        self.num_types = num_types;
+        self.int_vars = int_vars;
+        let (term, cond) = parsed?;
+        self.type_list_items(&mut iter, int, node)?;
+
+        Ok(Some(FromExpr::Expr(Expr::LstMap {
+            term: Box::new(term),
+            bind,
+            iter: Box::new(iter),
+            cond: cond.map(Box::new),
+        })))
+    }
+
+    /// Binds the variable of a loop over a list literal, like the one of
+    /// `for x in [1, 2.5]`, to the numeric type of the elements of the list,
+    /// which the elements are given
+    ///
+    /// # Returns
+    ///
+    /// The index in `int_types` of the integer the variable is bound to when
+    /// the elements are all integer literals, which take the type the uses of
+    /// the variable give them.
+    fn bind_list_item(
+        &mut self,
+        var: &str,
+        list: &mut Expr,
+        node: &impl Ranged,
+    ) -> PyResult<Option<usize>> {
+        let Expr::Lst { els } = list else {
+            self.set_num_type(var, None);
+            return Ok(None);
+        };
+
+        let literals: Option<Vec<_>> =
+            els.iter().map(int_literal_type).collect();
+        if let Some(literals) = literals.filter(|lits| !lits.is_empty()) {
+            let ty = match literals.contains(&NumType::I24) {
+                true => NumType::I24,
+                false => NumType::U24,
             };
+            return Ok(Some(self.bind_int(var, ty)));
+        }
 
-            return Some(ret_match);
+        let ty = els.iter().find_map(|el| self.infer(el));
+        if let Some(ty) = ty {
+            for el in els.iter_mut() {
+                self.expect_type(el, ty, node)?;
+            }
         }
-        None
+        self.set_num_type(var, ty);
+        Ok(None)
     }
 
-    fn parse_switch(
+    /// Gives the integer literals of a list the type the uses of the variable
+    /// of a loop over it gave them, after `bind_list_item`
+    fn type_list_items(
         &mut self,
-        name: &String,
-        nxt: &Option<FromExpr>,
-        stmts: &[rStmt],
-        index: &usize,
+        list: &mut Expr,
+        int: Option<usize>,
+        node: &impl Ranged,
+    ) -> PyResult<()> {
+        let (Some(int), Expr::Lst { els }) = (int, list) else {
+            return Ok(());
+        };
+
+        let ty = self.int_type(int);
+        for el in els {
+            self.expect_type(el, ty, node)?;
+        }
+        Ok(())
+    }
+
+    /// Parses an indexing like `xs[i]`, where negative literal indices, like
+    /// `xs[-1]`, count from the end of the list
+    fn parse_subscript(
+        &mut self,
+        sub: &ExprSubscript,
+    ) -> PyResult<Option<FromExpr>> {
+        if let rExpr::Slice(slice) = sub.slice.as_ref() {
+            return self.unsupported(slice, "Slices");
+        }
+
+        let list = self.parse_expr(*sub.value.clone())?;
+
+        let index = match sub.slice.as_ref() {
+            rExpr::UnaryOp(ExprUnaryOp {
+                op: rUnaryOp::USub,
+                operand,
+                ..
+            }) if matches!(
+                operand.as_ref(),
+                rExpr::Constant(c) if matches!(c.value, located::Constant::Int(_))
+            ) =>
+            {
+                let offset = self.parse_expr(*operand.clone())?;
+                Expr::Opr {
+                    op: Op::SUB,
+                    lhs: Box::new(call_prelude("len", vec![list.clone()])),
+                    rhs: Box::new(offset),
+                }
+            }
+            index => self.parse_expr(index.clone())?,
+        };
+
+        Ok(Some(FromExpr::Expr(call_prelude(
+            "index",
+            vec![list, index],
+        ))))
+    }
+
+    fn parse_adt_create(
+        &self,
+        left: &FromExpr,
+        right: &FromExpr,
     ) -> Option<FromExpr> {
-        let mut arms: Vec<imp::Stmt> = vec![];
-        if let Some(rStmt::Match(m)) = stmts.get(index + 1) {
-            for case in &m.cases {
-                let stmt_arm = self.parse_vec(&case.body.clone(), 0);
+        if let (Some(nam_l), Some(nam_r)) =
+            (left.get_var_name(), right.get_var_name())
+        {
+            let adt_l = self.book.adts.get(&nam_l);
+            let adt_r = self.book.adts.get(&nam_r);
 
-                if let Some(FromExpr::Statement(a)) = stmt_arm {
-                    arms.push(a);
+            let mut is_adt = false;
+
+            match (adt_l, adt_r) {
+                (None, None) => {}
+                (_, _) => {
+                    is_adt = true;
                 }
             }
 
-            if let Some(FromExpr::Expr(expr)) =
-                self.parse_expr_type(*m.subject.clone())
-            {
-                return Some(FromExpr::Statement(imp::Stmt::Switch {
-                    arg: Box::new(expr),
-                    bnd: Some(Name::new(name)),
-                    arms,
-                    nxt: nxt.clone().map(|n| {
-                        if let FromExpr::Statement(n) = n {
-                            return Box::new(n);
-                        }
+            let mut fields: Vec<CtrField> = vec![];
 
-                        todo!()
-                    }),
-                    with_bnd: vec![],
-                    with_arg: vec![],
-                }));
+            if is_adt {
+                fields.push(CtrField {
+                    nam: nam_l.clone(),
+                    rec: false,
+                });
+                fields.push(CtrField {
+                    nam: nam_r.clone(),
+                    rec: false,
+                });
+                return Some(FromExpr::CtrField(fields));
             }
         }
+
         None
     }
 
-    fn find_in_ctrs(&self, nam: &Name) -> Option<Name> {
-        for ctr in self.book.ctrs.clone() {
-            for ctr_name in ctr.0.split('/') {
-                if nam.to_string() == *ctr_name.to_string() {
-                    return Some(ctr.0);
-                }
-            }
-        }
-        None
+    /// Parses a comparison, turning chained comparisons like `a < b < c` into
+    /// the conjunction of each comparison, `(a < b) & (b < c)`
+    fn parse_compare(
+        &mut self,
+        comp: ExprCompare,
+    ) -> PyResult<Option<FromExpr>> {
+        let mut left_expr = comp.left.as_ref();
+        let mut left = self.parse_expr(left_expr.clone())?;
+        let mut result: Option<Expr> = None;
+
+        for (op, right_expr) in comp.ops.iter().zip(comp.comparators.iter()) {
+            let mut right = self.parse_expr(right_expr.clone())?;
+
+            if matches!(
+                op,
+                rCmpOp::Eq
+                    | rCmpOp::NotEq
+                    | rCmpOp::Lt
+                    | rCmpOp::LtE
+                    | rCmpOp::Gt
+                    | rCmpOp::GtE
+            ) {
+                self.unify(&mut left, &mut right, &comp)?;
+            }
+
+            let compare = |op, lhs: &Expr, rhs: &Expr| Expr::Opr {
+                op,
+                lhs: Box::new(lhs.clone()),
+                rhs: Box::new(rhs.clone()),
+            };
+
+            // `None` is a constructor, which can't be compared with `==`
+            let none_cmp = match (is_none(left_expr), is_none(right_expr)) {
+                (false, true) => {
+                    Some(call_prelude("is_none", vec![left.clone()]))
+                }
+                (true, false) => {
+                    Some(call_prelude("is_none", vec![right.clone()]))
+                }
+                _ => None,
+            };
+
+            // Bend values have no identity, so `is` compares them by value
+            let cmp = match (op, none_cmp) {
+                (rCmpOp::Eq | rCmpOp::Is, Some(is_none)) => is_none,
+                (rCmpOp::NotEq | rCmpOp::IsNot, Some(is_none)) => compare(
+                    Op::EQ,
+                    &is_none,
+                    &Expr::Num {
+                        val: fun::Num::U24(0),
+                    },
+                ),
+                (op, _) => match op {
+                    rCmpOp::Eq | rCmpOp::Is => compare(Op::EQ, &left, &right),
+                    rCmpOp::NotEq | rCmpOp::IsNot => {
+                        compare(Op::NEQ, &left, &right)
+                    }
+                    rCmpOp::Lt => compare(Op::LT, &left, &right),
+                    rCmpOp::LtE => compare(Op::LE, &left, &right),
+                    rCmpOp::Gt => compare(Op::GT, &left, &right),
+                    rCmpOp::GtE => compare(Op::GE, &left, &right),
+                    rCmpOp::In => {
+                        call_prelude("contains", vec![right.clone(), left])
+                    }
+                    rCmpOp::NotIn => compare(
+                        Op::EQ,
+                        &call_prelude("contains", vec![right.clone(), left]),
+                        &Expr::Num {
+                            val: fun::Num::U24(0),
+                        },
+                    ),
+                },
+            };
+
+            result = Some(match result {
+                Some(result) => compare(Op::AND, &result, &cmp),
+                None => cmp,
+            });
+            left = right;
+            left_expr = right_expr;
+        }
+
+        match result {
+            Some(result) => Ok(Some(FromExpr::Expr(result))),
+            None => self.unsupported(&comp, "Comparisons"),
+        }
+    }
+
+    /// Parses a unary operator
+    ///
+    /// Negative number literals become signed numbers, while other operands
+    /// are negated as `0 - x`, `not x` becomes `x == 0` and `~x` flips the
+    /// bits of `x`, with the literals taking the type of `x`.
+    fn parse_unary_op(
+        &mut self,
+        unary: ExprUnaryOp,
+    ) -> PyResult<Option<FromExpr>> {
+        if let (rUnaryOp::USub, rExpr::Constant(c)) =
+            (unary.op, unary.operand.as_ref())
+        {
+            let val = match &c.value {
+                located::Constant::Int(val) => val
+                    .to_i32()
+                    .filter(|val| *val <= -I24::MIN)
+                    .map(|val| fun::Num::I24(-val)),
+                located::Constant::Float(val) => {
+                    Some(fun::Num::F24(-*val as f32))
+                }
+                _ => None,
+            };
+
+            return match val {
+                Some(val) => Ok(Some(FromExpr::Expr(Expr::Num { val }))),
+                None => self.unsupported(
+                    &unary,
+                    "Negative numbers that don't fit in an i24",
+                ),
+            };
+        }
+
+        let mut operand = self.parse_expr(*unary.operand.clone())?;
+        let ty = self.operand_type(&operand);
+        if let Some(ty) = ty {
+            self.expect_type(&mut operand, ty, &unary)?;
+        }
+        let opr = |op, lhs: Expr, rhs: Expr| Expr::Opr {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        };
+        let mut zero = Expr::Num {
+            val: fun::Num::U24(0),
+        };
+        if let Some(ty) = ty {
+            coerce(&mut zero, ty);
+        }
+
+        let expr = match (unary.op, ty) {
+            (rUnaryOp::UAdd, _) => operand,
+            (rUnaryOp::USub, _) => opr(Op::SUB, zero, operand),
+            (rUnaryOp::Not, _) => opr(Op::EQ, operand, zero),
+            (rUnaryOp::Invert, Some(NumType::F24)) => {
+                return self.unsupported(&unary, "The `~` operator on f24s")
+            }
+            (rUnaryOp::Invert, Some(NumType::I24)) => opr(
+                Op::XOR,
+                operand,
+                Expr::Num {
+                    val: fun::Num::I24(-1),
+                },
+            ),
+            (rUnaryOp::Invert, _) => opr(
+                Op::XOR,
+                operand,
+                Expr::Num {
+                    val: fun::Num::U24(0xFFFFFF),
+                },
+            ),
+        };
+
+        Ok(Some(FromExpr::Expr(expr)))
+    }
+
+    /// Parses `and` and `or`, which return one of their operands like in
+    /// Python
+    ///
+    /// The HVM evaluates the arguments of a call right away, so each operator
+    /// becomes a helper definition that receives the variables in scope and
+    /// the value of the left operand, and only evaluates the right operand in
+    /// the branch of the `if` where it's the result.
+    fn parse_bool_op(
+        &mut self,
+        bool_op: ExprBoolOp,
+    ) -> PyResult<Option<FromExpr>> {
+        let mut values = vec![];
+        for value in bool_op.values.iter() {
+            values.push(self.parse_expr(value.clone())?);
+        }
+
+        let Some(mut expr) = values.pop() else {
+            return self.unsupported(&bool_op, "Boolean operators");
+        };
+
+        while let Some(left) = values.pop() {
+            expr = self.short_circuit(bool_op.op, left, expr);
+        }
+
+        Ok(Some(FromExpr::Expr(expr)))
+    }
+
+    /// Adds the helper definition of `left and right` or `left or right` and
+    /// returns the call to it
+    fn short_circuit(&mut self, op: rBoolOp, left: Expr, right: Expr) -> Expr {
+        let kind = match op {
+            rBoolOp::And => "and",
+            rBoolOp::Or => "or",
+        };
+        let name =
+            Name::new(format!("{}__{}{}", self.fun_name, kind, self.loops));
+        self.loops += 1;
+
+        let scope = self.scope();
+        // Python names can't have a `/`, so this one doesn't shadow a variable
+        let value = Name::new("benda/left");
+        let mut zero = Expr::Num {
+            val: fun::Num::U24(0),
+        };
+        if let Some(ty) = self.operand_type(&left) {
+            coerce(&mut zero, ty);
+        }
+
+        let ret = |term| {
+            Box::new(Stmt::Return {
+                term: Box::new(term),
+            })
+        };
+        let left_ret = ret(Expr::Var { nam: value.clone() });
+        let (then, otherwise) = match op {
+            rBoolOp::And => (ret(right), left_ret),
+            rBoolOp::Or => (left_ret, ret(right)),
+        };
+
+        let mut params = scope.clone();
+        params.push(value.clone());
+        self.definitions.push(imp::Definition {
+            name: name.clone(),
+            params,
+            body: Stmt::If {
+                cond: Box::new(Expr::Opr {
+                    op: Op::NEQ,
+                    lhs: Box::new(Expr::Var { nam: value }),
+                    rhs: Box::new(zero),
+                }),
+                then,
+                otherwise,
+                nxt: None,
+            },
+        });
+
+        let mut args = vars_of(&scope);
+        args.push(left);
+        Expr::Call {
+            fun: Box::new(Expr::Var { nam: name }),
+            args,
+            kwargs: vec![],
+        }
+    }
+
+    /// Parses with more variables in scope, like the parameters of a lambda,
+    /// which the helpers of the operators inside must also receive
+    fn with_vars<T>(
+        &mut self,
+        vars: &[Name],
+        parse: impl FnOnce(&mut Self) -> PyResult<T>,
+    ) -> PyResult<T> {
+        let locals = self.locals.clone();
+        for var in vars {
+            if !self.locals.iter().any(|local| local == var.as_ref()) {
+                self.locals.push(var.to_string());
+            }
+        }
+
+        let parsed = parse(self);
+        self.locals = locals;
+        parsed
+    }
+
+    /// The variables in scope, which are the local variables and the fields of
+    /// the value being matched
+    fn scope(&self) -> Vec<Name> {
+        let mut scope: Vec<Name> = self.locals.iter().map(Name::new).collect();
+
+        if let Some(ctx) =
+            self.ctx.as_ref().filter(|ctx| ctx.now == CurContext::Match)
+        {
+            for var in &ctx.vars {
+                scope.push(Name::new(format!(
+                    "{}.{}",
+                    ctx.subs.first().unwrap(),
+                    var
+                )));
+            }
+        }
+        scope
+    }
+
+    /// Translates a binary operator between two expressions, also used by the
+    /// augmented assignments
+    ///
+    /// The operators that the HVM defines differently for some types of
+    /// numbers are translated by the type of their operands, taking numbers
+    /// of an unknown type as `u24`s: `//` and `%` round down for `i24`s and
+    /// `f24`s through the prelude, `**` multiplies integers in a loop, since
+    /// the HVM only raises floats to a power, and `/` is only a true division
+    /// for floats.
+    fn parse_operation(
+        &mut self,
+        op: &rOperator,
+        mut lhs: Expr,
+        mut rhs: Expr,
+        node: &impl Ranged,
+    ) -> PyResult<Expr> {
+        let opr = |op, lhs, rhs| Expr::Opr {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        };
+
+        let is_float = |expr| self.infer(expr) == Some(NumType::F24);
+        if *op == rOperator::Pow && !is_float(&lhs) && !is_float(&rhs) {
+            self.expect_type(&mut rhs, NumType::U24, node)?;
+
+            let ty = self.operand_type(&lhs).unwrap_or(NumType::U24);
+            self.expect_type(&mut lhs, ty, node)?;
+            let mut one = Expr::Num {
+                val: fun::Num::U24(1),
+            };
+            coerce(&mut one, ty);
+            return Ok(call_prelude("pow", vec![lhs, rhs, one]));
+        }
+
+        // The operands are given the type even when it's the default `u24`,
+        // so that the integers of `int_vars` keep the type they are used with
+        self.unify(&mut lhs, &mut rhs, node)?;
+        let ty = self
+            .operand_type(&lhs)
+            .or_else(|| self.operand_type(&rhs))
+            .unwrap_or(NumType::U24);
+        self.expect_type(&mut lhs, ty, node)?;
+        self.expect_type(&mut rhs, ty, node)?;
+
+        Ok(match (op, ty) {
+            (rOperator::Add, _) => opr(Op::ADD, lhs, rhs),
+            (rOperator::Sub, _) => opr(Op::SUB, lhs, rhs),
+            (rOperator::Mult, _) => opr(Op::MUL, lhs, rhs),
+            (rOperator::Pow, _) => opr(Op::POW, lhs, rhs),
+            (rOperator::Div, NumType::F24) => opr(Op::DIV, lhs, rhs),
+            (rOperator::Div, _) => {
+                return self.unsupported(
+                    node,
+                    "The `/` operator on numbers that aren't f24s",
+                )
+            }
+            (rOperator::FloorDiv, NumType::U24) => opr(Op::DIV, lhs, rhs),
+            (rOperator::FloorDiv, ty) => {
+                call_prelude(&format!("floordiv/{}", ty), vec![lhs, rhs])
+            }
+            (rOperator::Mod, NumType::U24) => opr(Op::REM, lhs, rhs),
+            (rOperator::Mod, ty) => {
+                call_prelude(&format!("mod/{}", ty), vec![lhs, rhs])
+            }
+            (
+                rOperator::LShift
+                | rOperator::RShift
+                | rOperator::BitOr
+                | rOperator::BitXor
+                | rOperator::BitAnd,
+                NumType::F24,
+            ) => return self.unsupported(node, "Bitwise operators on f24s"),
+            // The HVM only shifts u24s
+            (rOperator::LShift | rOperator::RShift, NumType::I24) => {
+                return self.unsupported(node, "Shifts of i24s")
+            }
+            (rOperator::LShift, _) => opr(Op::SHL, lhs, rhs),
+            (rOperator::RShift, _) => opr(Op::SHR, lhs, rhs),
+            (rOperator::BitOr, _) => opr(Op::OR, lhs, rhs),
+            (rOperator::BitXor, _) => opr(Op::XOR, lhs, rhs),
+            (rOperator::BitAnd, _) => opr(Op::AND, lhs, rhs),
+            (rOperator::MatMult, _) => {
+                return self.unsupported(node, "The `@` operator")
+            }
+        })
+    }
+
+    fn parse_bin_op(&mut self, bin: ExprBinOp) -> PyResult<Option<FromExpr>> {
+        let left: FromExpr = self.parse_required(*bin.left.clone())?;
+        let right: FromExpr = self.parse_required(*bin.right.clone())?;
+
+        if let Some(adt_op) = self.parse_adt_create(&left, &right) {
+            return Ok(Some(adt_op));
+        }
+
+        if let (FromExpr::Expr(left), FromExpr::Expr(right)) = (left, right) {
+            let operation = self.parse_operation(&bin.op, left, right, &bin)?;
+            return Ok(Some(FromExpr::Expr(operation)));
+        }
+        self.unsupported(&bin, "This expression")
+    }
+
+    fn parse_assign(&mut self, assign: &StmtAssign) -> PyResult<FromExpr> {
+        self.parse_required(*assign.value.clone())
+    }
+
+    fn parse_match(
+        &mut self,
+        m: &StmtMatch,
+        stmts: &Vec<rStmt>,
+        index: &usize,
+    ) -> PyResult<Option<imp::Stmt>> {
+        let mut arms: Vec<imp::MatchArm> = vec![];
+        let mut patt: Vec<String> = vec![];
+
+        for case in &m.cases {
+            if case.guard.is_some() {
+                return self.unsupported(&case.pattern, "Match guards");
+            }
+
+            let pat = match &case.pattern {
+                rPattern::MatchValue(val) => {
+                    match self.parse_expr_type(*val.value.clone())? {
+                        Some(FromExpr::Expr(imp::Expr::Var { nam })) => nam,
+                        _ => {
+                            return self.unsupported(
+                                val,
+                                "Value patterns other than constructors",
+                            )
+                        }
+                    }
+                }
+
+                rPattern::MatchClass(class) => {
+                    let expr = self.parse_expr_type(*class.cls.clone())?;
+
+                    for val in class.patterns.clone() {
+                        match val {
+                            rPattern::MatchAs(match_as)
+                                if match_as.pattern.is_none() =>
+                            {
+                                if let Some(name) = &match_as.name {
+                                    patt.push(name.to_string());
+                                }
+                            }
+                            _ => {
+                                return self
+                                    .unsupported(&val, "Nested patterns")
+                            }
+                        }
+                    }
+
+                    match expr {
+                        Some(FromExpr::Expr(imp::Expr::Var { nam })) => nam,
+                        _ => return self.unsupported(
+                            class,
+                            "Class patterns of types that aren't dataclasses",
+                        ),
+                    }
+                }
+                rPattern::MatchSingleton(p) => {
+                    return self
+                        .unsupported(p, "`None`, `True` and `False` patterns")
+                }
+                rPattern::MatchSequence(p) => {
+                    return self.unsupported(p, "Sequence patterns")
+                }
+                rPattern::MatchMapping(p) => {
+                    return self.unsupported(p, "Mapping patterns")
+                }
+                rPattern::MatchStar(p) => {
+                    return self.unsupported(p, "Star patterns")
+                }
+                rPattern::MatchAs(p) => {
+                    return self.unsupported(p, "Wildcard and capture patterns")
+                }
+                rPattern::MatchOr(p) => {
+                    return self.unsupported(p, "Or patterns")
+                }
+            };
+
+            let sub = self.parse_expr_type(*m.subject.clone())?;
+
+            if let Some(FromExpr::Expr(Expr::Var { nam })) = sub {
+                self.ctx = Some(Context {
+                    now: CurContext::Match,
+                    vars: patt.clone(),
+                    subs: vec![nam.to_string()],
+                });
+            }
+
+            let stmt_arm = self.parse_vec(&case.body.clone(), 0)?;
+
+            let first = self.find_in_ctrs(&pat);
+            if let Some(FromExpr::Statement(a)) = stmt_arm {
+                let arm = MatchArm { lft: first, rgt: a };
+                arms.push(arm);
+            }
+        }
+
+        self.ctx = None;
+
+        let subj = self.parse_expr(*m.subject.clone())?;
+
+        let my_nxt = into_nxt(self.parse_vec(stmts, index + 1)?);
+
+        let name = match subj.clone() {
+            Expr::Var { nam } => Some(nam),
+            _ => None,
+        };
+
+        let ret_match = imp::Stmt::Match {
+            arg: Box::new(subj.clone()),
+            bnd: name,
+            arms,
+            nxt: my_nxt,
+            with_bnd: vec![],
+            with_arg: vec![],
+        };
+
+        Ok(Some(ret_match))
+    }
+
+    /// Parses a `match` whose first case is a sequence pattern, like
+    /// `case (a, b):`, as the unpacking of the subject into a tuple pattern.
+    /// Since tuples have a single shape, the first case always matches and
+    /// the others are never reached.
+    fn parse_unpacking_match(
+        &mut self,
+        m: &StmtMatch,
+        stmts: &[rStmt],
+        index: usize,
+    ) -> PyResult<Option<FromExpr>> {
+        let case = &m.cases[0];
+        if case.guard.is_some() {
+            return self.unsupported(&case.pattern, "Match guards");
+        }
+
+        let pat = self.parse_sequence_pattern(&case.pattern)?;
+        let val = self.parse_expr(*m.subject.clone())?;
+
+        // The rest of the block runs after the body of the case
+        let mut body = case.body.clone();
+        body.extend_from_slice(&stmts[index + 1..]);
+
+        self.add_pattern_locals(&pat);
+        let nxt = into_nxt(self.parse_vec(&body, 0)?);
+
+        Ok(Some(FromExpr::Statement(Stmt::Assign {
+            pat,
+            val: Box::new(val),
+            nxt,
+        })))
+    }
+
+    /// Turns a sequence pattern made of captures and wildcards, like
+    /// `(a, (b, _))`, into the tuple pattern that binds them
+    fn parse_sequence_pattern(
+        &self,
+        pattern: &rPattern,
+    ) -> PyResult<imp::AssignPattern> {
+        match pattern {
+            rPattern::MatchAs(p) if p.pattern.is_none() => Ok(match &p.name {
+                Some(name) => imp::AssignPattern::Var(Name::new(name.as_str())),
+                None => imp::AssignPattern::Eraser,
+            }),
+            rPattern::MatchSequence(seq) if seq.patterns.len() >= 2 => {
+                Ok(imp::AssignPattern::Tup(
+                    seq.patterns
+                        .iter()
+                        .map(|pat| self.parse_sequence_pattern(pat))
+                        .collect::<PyResult<_>>()?,
+                ))
+            }
+            _ => self.unsupported(
+                pattern,
+                "Sequence patterns with anything other than captures",
+            ),
+        }
+    }
+
+    /// Parses a `switch` over the `match` statement that follows the
+    /// assignment of `benda.switch()` at `index`
+    fn parse_switch(
+        &mut self,
+        name: &String,
+        stmts: &Vec<rStmt>,
+        index: &usize,
+    ) -> PyResult<Option<FromExpr>> {
+        let mut arms: Vec<imp::Stmt> = vec![];
+        if let Some(rStmt::Match(m)) = stmts.get(index + 1) {
+            for case in &m.cases {
+                let stmt_arm = self.parse_vec(&case.body.clone(), 0)?;
+
+                if let Some(FromExpr::Statement(a)) = stmt_arm {
+                    arms.push(a);
+                }
+            }
+
+            let expr = self.parse_expr(*m.subject.clone())?;
+
+            // The match is the body of the switch, so the switch continues
+            // after it
+            let nxt = into_nxt(self.parse_vec(stmts, index + 2)?);
+
+            return Ok(Some(FromExpr::Statement(imp::Stmt::Switch {
+                arg: Box::new(expr),
+                bnd: Some(Name::new(name)),
+                arms,
+                nxt,
+                with_bnd: vec![],
+                with_arg: vec![],
+            })));
+        }
+        Ok(None)
+    }
+
+    fn find_in_ctrs(&self, nam: &Name) -> Option<Name> {
+        for ctr in self.book.ctrs.clone() {
+            for ctr_name in ctr.0.split('/') {
+                if nam.to_string() == *ctr_name.to_string() {
+                    return Some(ctr.0);
+                }
+            }
+        }
+        None
+    }
+
+    fn parse_main_call(
+        &mut self,
+        value: &FromExpr,
+        name: &String,
+        stmts: &Vec<rStmt>,
+        index: usize,
+    ) -> PyResult<Option<FromExpr>> {
+        if let Some(ctx) = &self.ctx {
+            if ctx.now == CurContext::Main {
+                if let FromExpr::Expr(Expr::Call {
+                    fun,
+                    args: _,
+                    kwargs: _,
+                }) = value.clone()
+                {
+                    if let Expr::Var { nam } = *fun {
+                        if &nam.to_string() == ctx.subs.first().unwrap() {
+                            if let FromExpr::Expr(e) = value.clone() {
+                                return Ok(Some(FromExpr::Statement(
+                                    Stmt::Return { term: Box::new(e) },
+                                )));
+                            }
+                        }
+                    }
+                }
+            }
+
+            if ctx.now == CurContext::Main && !ctx.vars.contains(name) {
+                return self.parse_vec(stmts, index + 1);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parses an `if` statement, with or without an `else`
+    ///
+    /// When one of its branches jumps out with a `return`, a `break` or a
+    /// `continue`, the statements that follow the `if` are moved into both of
+    /// its branches, so a missing `else` runs them. Otherwise, both branches end
+    /// assigning the variables that exist after the `if`, a missing `else`
+    /// keeping their values, and the `if` continues with the next statements.
+    fn parse_if(
+        &mut self,
+        stmt_if: &StmtIf,
+        stmts: &Vec<rStmt>,
+        index: usize,
+    ) -> PyResult<Option<FromExpr>> {
+        let cond = self.parse_expr(*stmt_if.test.clone())?;
+
+        // The branch where a tested `Optional` variable isn't `None` uses
+        // its value
+        let narrowed = none_test(&stmt_if.test)
+            .filter(|(nam, _)| self.locals.iter().any(|local| local == nam));
+        let narrow = |then: Stmt, otherwise: Stmt| match narrowed {
+            Some((nam, true)) => (then, unwrap_var(nam, otherwise)),
+            Some((nam, false)) => (unwrap_var(nam, then), otherwise),
+            None => (then, otherwise),
+        };
+
+        if jumps(&stmt_if.body) || jumps(&stmt_if.orelse) {
+            let rest = &stmts[index + 1..];
+            let then = close_paths(&[&stmt_if.body, rest].concat());
+            let otherwise = close_paths(&[&stmt_if.orelse, rest].concat());
+
+            let then = self.parse_branch(&then)?;
+            let otherwise = self.parse_branch(&otherwise)?;
+
+            return match (then, otherwise) {
+                (Some(then), Some(otherwise)) => {
+                    let (then, otherwise) = narrow(then, otherwise);
+                    Ok(Some(FromExpr::Statement(Stmt::If {
+                        cond: Box::new(cond),
+                        then: Box::new(then),
+                        otherwise: Box::new(otherwise),
+                        nxt: None,
+                    })))
+                }
+                _ => self.unsupported(
+                    stmt_if,
+                    "`if` statements that don't return on every path",
+                ),
+            };
+        }
+
+        let mut then_names = vec![];
+        assigned_names(&stmt_if.body, &mut then_names);
+        let mut else_names = vec![];
+        assigned_names(&stmt_if.orelse, &mut else_names);
+
+        // The variables that existed before, and the new ones both branches
+        // assign
+        let mut carried: Vec<Name> = self
+            .locals
+            .iter()
+            .filter(|local| {
+                then_names.contains(local) || else_names.contains(local)
+            })
+            .map(Name::new)
+            .collect();
+        carried.extend(
+            then_names
+                .iter()
+                .filter(|nam| else_names.contains(nam))
+                .filter(|nam| !self.locals.contains(nam))
+                .map(Name::new),
+        );
+
+        // An `if` that doesn't change any variable has no effect
+        if carried.is_empty() {
+            return self.parse_vec(stmts, index + 1);
+        }
+
+        let (pat, state) = loop_state(&carried);
+        let end = Stmt::Assign {
+            pat,
+            val: Box::new(state),
+            nxt: None,
+        };
+
+        let close = |branch: Option<Stmt>| match branch {
+            Some(mut branch) => {
+                push_stmt(&mut branch, end.clone());
+                branch
+            }
+            None => end.clone(),
+        };
+
+        let then = close(self.parse_branch(&stmt_if.body)?);
+        let otherwise = close(self.parse_branch(&stmt_if.orelse)?);
+        let (then, otherwise) = narrow(then, otherwise);
+
+        for nam in &carried {
+            self.add_local(nam.as_ref());
+        }
+
+        let nxt = into_nxt(self.parse_vec(stmts, index + 1)?);
+
+        Ok(Some(FromExpr::Statement(Stmt::If {
+            cond: Box::new(cond),
+            then: Box::new(then),
+            otherwise: Box::new(otherwise),
+            nxt,
+        })))
+    }
+
+    /// Parses a branch of an `if`, whose new variables only exist inside it
+    fn parse_branch(&mut self, stmts: &Vec<rStmt>) -> PyResult<Option<Stmt>> {
+        let locals = self.locals.clone();
+        let branch = self.parse_vec(stmts, 0);
+        self.locals = locals;
+
+        Ok(into_nxt(branch?).map(|branch| *branch))
+    }
+
+    fn add_local(&mut self, name: &str) {
+        // Assigning to the name of a nested function shadows it
+        self.lifted.retain(|(nam, ..)| nam != name);
+
+        if !self.locals.iter().any(|local| local == name) {
+            self.locals.push(name.to_string());
+        }
+    }
+
+    /// Translates a `for` loop into a Bend `bend` when it goes over a `range`,
+    /// or into a `fold` over the iterated list otherwise
+    ///
+    /// The variables assigned by the loop that already existed before it are
+    /// carried from one iteration to the next, and are bound to their final
+    /// values after the loop.
+    ///
+    /// # Arguments
+    ///
+    /// * `stmt_for` - The `for` loop
+    /// * `stmts` - The block containing the loop
+    /// * `index` - The position of the loop in the block
+    ///
+    /// # Returns
+    ///
+    /// The loop followed by the rest of the block
+    fn parse_for(
+        &mut self,
+        stmt_for: &StmtFor,
+        stmts: &Vec<rStmt>,
+        index: usize,
+    ) -> PyResult<Option<FromExpr>> {
+        if !stmt_for.orelse.is_empty() {
+            return self.unsupported(stmt_for, "`for` loops with an `else`");
+        }
+
+        if let Some(ret) = find_return(&stmt_for.body) {
+            return self.unsupported(ret, "`return` statements inside loops");
+        }
+
+        let target = self.parse_target(&stmt_for.target)?;
+        let carried = self.carried_vars(Some(&stmt_for.target), &stmt_for.body);
+
+        // A loop that doesn't change any variable has no effect
+        if carried.is_empty() {
+            return self.parse_vec(stmts, index + 1);
+        }
+
+        let vars = vars_of(&carried);
+        let (pat, state) = loop_state(&carried);
+
+        // Every branch of the loop ends assigning the carried variables
+        let base = Stmt::Assign {
+            pat: pat.clone(),
+            val: Box::new(state),
+            nxt: None,
+        };
+
+        let id = self.loops;
+        self.loops += 1;
+        let iter = Name::new(format!("%iter{}", id));
+
+        let lp = match range_call(&stmt_for.iter) {
+            Some(call) => {
+                let Range {
+                    start,
+                    stop,
+                    step_op,
+                    cond_op,
+                    step,
+                    ty,
+                } = self.parse_range(call)?;
+                let signed = ty == Some(NumType::I24);
+                if let Some(target) = target_var(&target) {
+                    self.set_num_type(target, ty);
+                }
+
+                let opr = |op, lhs: &Name, rhs: u32| Expr::Opr {
+                    op,
+                    lhs: Box::new(Expr::Var { nam: lhs.clone() }),
+                    rhs: Box::new(Expr::Num {
+                        val: if signed {
+                            fun::Num::I24(rhs as i32)
+                        } else {
+                            fun::Num::U24(rhs)
+                        },
+                    }),
+                };
+
+                // Going down, an unsigned counter stops at 0 instead of
+                // wrapping around, which ends the loop since the range can't
+                // end below 0
+                let next = match step_op {
+                    Op::SUB if signed => opr(Op::SUB, &iter, step),
+                    Op::SUB => Expr::Opr {
+                        op: Op::MUL,
+                        lhs: Box::new(opr(Op::GT, &iter, step)),
+                        rhs: Box::new(opr(Op::SUB, &iter, step)),
+                    },
+                    _ => opr(step_op, &iter, step),
+                };
+
+                let fork = Expr::Call {
+                    fun: Box::new(Expr::Var {
+                        nam: Name::new("fork"),
+                    }),
+                    args: [next].into_iter().chain(vars.clone()).collect(),
+                    kwargs: vec![],
+                };
+
+                let step = self.parse_loop_body(
+                    stmt_for,
+                    &target,
+                    Expr::Var { nam: iter.clone() },
+                    Stmt::Assign {
+                        pat,
+                        val: Box::new(fork),
+                        nxt: None,
+                    },
+                )?;
+
+                // The end of the range is only evaluated once, like in Python
+                let stop_nam = Name::new(format!("%stop{}", id));
+                let nxt = into_nxt(self.parse_vec(stmts, index + 1)?);
+
+                Stmt::Assign {
+                    pat: imp::AssignPattern::Var(stop_nam.clone()),
+                    val: Box::new(stop),
+                    nxt: Some(Box::new(Stmt::Bend {
+                        bnd: [Some(iter.clone())]
+                            .into_iter()
+                            .chain(carried.into_iter().map(Some))
+                            .collect(),
+                        arg: [start].into_iter().chain(vars).collect(),
+                        cond: Box::new(Expr::Opr {
+                            op: cond_op,
+                            lhs: Box::new(Expr::Var { nam: iter }),
+                            rhs: Box::new(Expr::Var { nam: stop_nam }),
+                        }),
+                        step: Box::new(step),
+                        base: Box::new(base),
+                        nxt,
+                    })),
+                }
+            }
+            None => {
+                let mut list = self.parse_expr(*stmt_for.iter.clone())?;
+                let int = match target_var(&target) {
+                    Some(target) => {
+                        self.bind_list_item(target, &mut list, stmt_for)?
+                    }
+                    None => None,
+                };
+
+                let tail = Expr::Call {
+                    fun: Box::new(Expr::Var {
+                        nam: Name::new(format!("{}.tail", iter)),
+                    }),
+                    args: vars.clone(),
+                    kwargs: vec![],
+                };
+
+                let cons = self.parse_loop_body(
+                    stmt_for,
+                    &target,
+                    Expr::Var {
+                        nam: Name::new(format!("{}.head", iter)),
+                    },
+                    Stmt::Assign {
+                        pat,
+                        val: Box::new(tail),
+                        nxt: None,
+                    },
+                )?;
+
+                let nxt = into_nxt(self.parse_vec(stmts, index + 1)?);
+                self.type_list_items(&mut list, int, stmt_for)?;
+
+                Stmt::Fold {
+                    arg: Box::new(list),
+                    bnd: Some(iter),
+                    with_bnd: carried.into_iter().map(Some).collect(),
+                    with_arg: vars,
+                    arms: vec![
+                        MatchArm {
+                            lft: Some(Name::new("List/Cons")),
+                            rgt: cons,
+                        },
+                        MatchArm {
+                            lft: Some(Name::new("List/Nil")),
+                            rgt: base,
+                        },
+                    ],
+                    nxt,
+                }
+            }
+        };
+
+        Ok(Some(FromExpr::Statement(lp)))
+    }
+
+    /// Parses the body of a loop, binding its target to `item` first and
+    /// ending it with `tail`, the statement that starts the next iteration
+    fn parse_loop_body(
+        &mut self,
+        stmt_for: &StmtFor,
+        target: &imp::AssignPattern,
+        item: Expr,
+        tail: Stmt,
+    ) -> PyResult<Stmt> {
+        let locals = self.locals.clone();
+        self.add_pattern_locals(target);
+
+        // `break` and `continue` can't leave a `for` loop
+        let jumps = self.jumps.take();
+        let body = self.parse_vec(&stmt_for.body, 0);
+        self.jumps = jumps;
+
+        // Variables first assigned inside the loop aren't bound after it
+        self.locals = locals;
+        let body = body?;
+
+        let body = match into_nxt(body) {
+            Some(mut body) => {
+                push_stmt(&mut body, tail);
+                *body
+            }
+            None => tail,
+        };
+
+        Ok(Stmt::Assign {
+            pat: target.clone(),
+            val: Box::new(item),
+            nxt: Some(Box::new(body)),
+        })
+    }
+
+    /// Parses the arguments of a `range` call, whose bounds must be integers
+    /// of the same type
+    fn parse_range(&mut self, call: &ExprCall) -> PyResult<Range> {
+        let (start, stop, step) =
+            match (call.args.as_slice(), call.keywords.is_empty()) {
+                ([stop], true) => (None, stop, None),
+                ([start, stop], true) => (Some(start), stop, None),
+                ([start, stop, step], true) => (Some(start), stop, Some(step)),
+                _ => {
+                    return self.unsupported(
+                        call,
+                        "`range` calls other than `range(stop)`, `range(start, stop)` and `range(start, stop, step)`",
+                    )
+                }
+            };
+
+        let mut start = match start {
+            Some(start) => self.parse_expr(start.clone())?,
+            None => Expr::Num {
+                val: fun::Num::U24(0),
+            },
+        };
+        let mut stop = self.parse_expr(stop.clone())?;
+        let (step_op, cond_op, step) = match step {
+            Some(step) => self.parse_range_step(step)?,
+            None => (Op::ADD, Op::LT, 1),
+        };
+
+        // The counter has the type of the bounds of the range
+        self.unify(&mut start, &mut stop, call)?;
+        let ty = self
+            .operand_type(&start)
+            .or_else(|| self.operand_type(&stop));
+        if ty == Some(NumType::F24) {
+            return self.unsupported(call, "Ranges of floats");
+        }
+        if let Some(ty) = ty {
+            self.expect_type(&mut start, ty, call)?;
+            self.expect_type(&mut stop, ty, call)?;
+        }
+
+        Ok(Range {
+            start,
+            stop,
+            step_op,
+            cond_op,
+            step,
+            ty,
+        })
+    }
+
+    /// Turns a `range` call into the list of its numbers
+    ///
+    /// # Returns
+    ///
+    /// The list and the numeric type of its elements
+    fn range_list(
+        &mut self,
+        call: &ExprCall,
+    ) -> PyResult<(Expr, Option<NumType>)> {
+        let range = self.parse_range(call)?;
+
+        let step = Expr::Num {
+            val: match range.ty {
+                Some(NumType::I24) => fun::Num::I24(range.step as i32),
+                _ => fun::Num::U24(range.step),
+            },
+        };
+        let fun = match range.step_op {
+            Op::SUB => "range/down",
+            _ => "range/up",
+        };
+
+        Ok((
+            call_prelude(fun, vec![range.start, range.stop, step]),
+            range.ty,
+        ))
+    }
+
+    /// Parses the step of a `range`, which must be a non-zero integer literal
+    /// so the direction of the loop is known
+    ///
+    /// # Returns
+    ///
+    /// The operation that advances the loop, the comparison that keeps it
+    /// going and the size of the step
+    fn parse_range_step(&mut self, step: &rExpr) -> PyResult<(Op, Op, u32)> {
+        let (negative, value) = match step {
+            rExpr::UnaryOp(unary) if unary.op.is_u_sub() => {
+                (true, unary.operand.as_ref())
+            }
+            _ => (false, step),
+        };
+
+        let size = match value {
+            rExpr::Constant(c) => c.value.as_int().and_then(|int| int.to_u32()),
+            _ => None,
+        };
+
+        match (size, negative) {
+            (Some(size), false) if size != 0 && size <= 0xFFFFFF => {
+                Ok((Op::ADD, Op::LT, size))
+            }
+            (Some(size), true) if size != 0 && size <= 0xFFFFFF => {
+                Ok((Op::SUB, Op::GT, size))
+            }
+            _ => self.unsupported(
+                step,
+                "`range` steps other than non-zero integer literals",
+            ),
+        }
+    }
+
+    /// Returns the variables that existed before a loop and are assigned by
+    /// it, which must be carried from one iteration to the next
+    fn carried_vars(
+        &self,
+        target: Option<&rExpr>,
+        body: &[rStmt],
+    ) -> Vec<Name> {
+        let mut assigned = vec![];
+        if let Some(target) = target {
+            target_names(target, &mut assigned);
+        }
+        assigned_names(body, &mut assigned);
+
+        self.locals
+            .iter()
+            .filter(|local| assigned.contains(local))
+            .map(Name::new)
+            .collect()
+    }
+
+    /// Translates a `while` loop into a tail-recursive helper definition that
+    /// receives every variable in scope and returns the ones carried by the loop
+    ///
+    /// The helper runs one iteration of the loop and calls itself again, with a
+    /// `break` returning the carried variables and a `continue` calling the
+    /// helper again. The loop itself becomes a call to the helper, whose
+    /// results are bound to the carried variables.
+    ///
+    /// # Arguments
+    ///
+    /// * `stmt_while` - The `while` loop
+    /// * `stmts` - The block containing the loop
+    /// * `index` - The position of the loop in the block
+    ///
+    /// # Returns
+    ///
+    /// The loop followed by the rest of the block
+    fn parse_while(
+        &mut self,
+        stmt_while: &StmtWhile,
+        stmts: &Vec<rStmt>,
+        index: usize,
+    ) -> PyResult<Option<FromExpr>> {
+        if !stmt_while.orelse.is_empty() {
+            return self
+                .unsupported(stmt_while, "`while` loops with an `else`");
+        }
+
+        if let Some(ret) = find_return(&stmt_while.body) {
+            return self.unsupported(ret, "`return` statements inside loops");
+        }
+
+        let carried = self.carried_vars(None, &stmt_while.body);
+
+        // A loop that doesn't change any variable has no effect
+        if carried.is_empty() {
+            return self.parse_vec(stmts, index + 1);
+        }
+
+        let (pat, state) = loop_state(&carried);
+
+        let name = Name::new(format!("{}__while{}", self.fun_name, self.loops));
+        self.loops += 1;
+
+        let params: Vec<Name> = self.locals.iter().map(Name::new).collect();
+        let call = Expr::Call {
+            fun: Box::new(Expr::Var { nam: name.clone() }),
+            args: vars_of(&params),
+            kwargs: vec![],
+        };
+
+        // The end of the body continues with the next iteration
+        let mut body = stmt_while.body.clone();
+        body.push(rStmt::Continue(StmtContinue {
+            range: stmt_while.range(),
+        }));
+
+        let locals = self.locals.clone();
+        let jumps = self.jumps.replace(LoopJumps {
+            brk: state.clone(),
+            cont: call.clone(),
+        });
+
+        let helper =
+            self.parse_expr(*stmt_while.test.clone()).and_then(|cond| {
+                Ok((cond, self.parse_vec(&close_paths(&body), 0)?))
+            });
+
+        self.jumps = jumps;
+        self.locals = locals;
+
+        let (cond, body) = helper?;
+        let Some(FromExpr::Statement(body)) = body else {
+            return self.unsupported(stmt_while, "Empty `while` loops");
+        };
+
+        self.definitions.push(imp::Definition {
+            name,
+            params,
+            body: Stmt::If {
+                cond: Box::new(cond),
+                then: Box::new(body),
+                otherwise: Box::new(Stmt::Return {
+                    term: Box::new(state),
+                }),
+                nxt: None,
+            },
+        });
+
+        let nxt = into_nxt(self.parse_vec(stmts, index + 1)?);
+
+        Ok(Some(FromExpr::Statement(Stmt::Assign {
+            pat,
+            val: Box::new(call),
+            nxt,
+        })))
+    }
+
+    /// Returns the names of the parameters of a function or a lambda, which
+    /// can only be plain positional parameters
+    fn parse_params(
+        &self,
+        args: &Arguments,
+        node: &impl Ranged,
+    ) -> PyResult<Vec<Name>> {
+        if args.vararg.is_some()
+            || args.kwarg.is_some()
+            || !args.kwonlyargs.is_empty()
+        {
+            return self
+                .unsupported(node, "Variadic and keyword-only parameters");
+        }
+
+        let params = args.posonlyargs.iter().chain(args.args.iter());
+
+        if params.clone().any(|arg| arg.default.is_some()) {
+            return self.unsupported(node, "Default parameter values");
+        }
+
+        Ok(params
+            .map(|arg| Name::new(arg.def.arg.to_string()))
+            .collect())
+    }
+
+    /// Lifts a function defined inside another one into a definition of the
+    /// book, named after both functions
+    ///
+    /// The variables of the outer function are passed to the lifted definition
+    /// before its own parameters, and every use of the nested function becomes
+    /// the lifted definition applied to their current values.
+    ///
+    /// # Arguments
+    ///
+    /// * `fun_def` - The nested function
+    /// * `stmts` - The block containing the function
+    /// * `index` - The position of the function in the block
+    ///
+    /// # Returns
+    ///
+    /// The rest of the block
+    fn parse_nested_def(
+        &mut self,
+        fun_def: &StmtFunctionDef,
+        stmts: &Vec<rStmt>,
+        index: usize,
+    ) -> PyResult<Option<FromExpr>> {
+        if !fun_def.decorator_list.is_empty() {
+            return self.unsupported(fun_def, "Decorators on nested functions");
+        }
+
+        let params = self.parse_params(&fun_def.args, fun_def)?;
+        let captures: Vec<Name> = self
+            .locals
+            .iter()
+            .map(Name::new)
+            .filter(|nam| !params.contains(nam))
+            .collect();
+
+        let name = Name::new(format!("{}__{}", self.fun_name, fun_def.name));
+        let nested = (fun_def.name.to_string(), name.clone(), captures.clone());
+
+        let locals = std::mem::replace(
+            &mut self.locals,
+            captures
+                .iter()
+                .chain(params.iter())
+                .map(|nam| nam.to_string())
+                .collect(),
+        );
+        let fun_name = std::mem::replace(&mut self.fun_name, name.to_string());
+        let jumps = self.jumps.take();
+        let lifted = self.lifted.clone();
+        let optional_return = std::mem::replace(
+            &mut self.optional_return,
+            returns_optional(fun_def),
+        );
+        let return_type = std::mem::replace(
+            &mut self.return_type,
+            fun_def
+                .returns
+                .as_deref()
+                .and_then(NumType::from_annotation),
+        );
+        let num_types = self.num_types.clone();
+        let int_vars = self.int_vars.clone();
+        self.bind_param_types(fun_def);
+
+        // The parameters shadow the other nested functions, and the function
+        // can call itself
+        self.lifted.retain(|(nam, ..)| {
+            !params.iter().any(|param| param.as_ref() == nam)
+        });
+        self.lifted.push(nested.clone());
+
+        let body = self.parse_vec(&fun_def.body, 0);
+
+        self.locals = locals;
+        self.fun_name = fun_name;
+        self.jumps = jumps;
+        self.lifted = lifted;
+        self.optional_return = optional_return;
+        self.return_type = return_type;
+        self.num_types = num_types;
+        self.int_vars = int_vars;
+
+        let Some(FromExpr::Statement(body)) = body? else {
+            return self
+                .unsupported(fun_def, "Functions that don't return a value");
+        };
+
+        self.definitions.push(imp::Definition {
+            name,
+            params: captures.into_iter().chain(params).collect(),
+            body,
+        });
+
+        self.lifted.retain(|(nam, ..)| *nam != nested.0);
+        self.lifted.push(nested);
+
+        self.parse_vec(stmts, index + 1)
     }
 
-    fn parse_main_call(
+    fn parse_stmt_expr(
         &mut self,
-        value: &FromExpr,
-        name: &String,
+        expr: &StmtExpr,
         stmts: &Vec<rStmt>,
         index: usize,
-    ) -> Option<FromExpr> {
-        if let Some(ctx) = &self.ctx {
-            if ctx.now == CurContext::Main {
-                if let FromExpr::Expr(Expr::Call {
+    ) -> PyResult<Option<FromExpr>> {
+        // Docstrings and other constants have no effect
+        if let rExpr::Constant(_) = *expr.value {
+            return self.parse_vec(stmts, index + 1);
+        }
+
+        if self
+            .ctx
+            .as_ref()
+            .is_some_and(|ctx| ctx.now == CurContext::Main)
+        {
+            let val = self.parse_expr_type(*expr.value.clone())?;
+
+            if let (Some(FromExpr::Expr(call)), Some(ctx)) = (val, &self.ctx) {
+                if let Expr::Call {
                     fun,
                     args: _,
                     kwargs: _,
-                }) = value.clone()
+                } = call.clone()
                 {
-                    if let Expr::Var { nam } = *fun {
-                        if &nam.to_string() == ctx.subs.first().unwrap() {
-                            if let FromExpr::Expr(e) = value.clone() {
-                                return Some(FromExpr::Statement(
-                                    Stmt::Return { term: Box::new(e) },
-                                ));
-                            }
+                    if let imp::Expr::Var { nam } = *fun {
+                        if nam.to_string() == *ctx.subs.first().unwrap() {
+                            return Ok(Some(FromExpr::Statement(
+                                Stmt::Return {
+                                    term: Box::new(call),
+                                },
+                            )));
                         }
                     }
                 }
             }
+        }
 
-            if ctx.now == CurContext::Main && !ctx.vars.contains(name) {
-                return self.parse_vec(stmts, index + 1);
+        self.unsupported(expr, "Expression statements")
+    }
+
+    /// Turns the target of an assignment into the pattern that binds it
+    fn parse_target(&mut self, target: &rExpr) -> PyResult<imp::AssignPattern> {
+        let elts = match target {
+            rExpr::Name(nam) => {
+                return Ok(imp::AssignPattern::Var(Name::new(
+                    nam.id.to_string(),
+                )))
             }
+            rExpr::Tuple(tup) => &tup.elts,
+            rExpr::List(lst) => &lst.elts,
+            _ => {
+                return self.unsupported(
+                    target,
+                    "Assignments to anything other than variables and tuples of variables",
+                )
+            }
+        };
+
+        if elts.len() < 2 {
+            return self
+                .unsupported(target, "Unpacking into less than two variables");
         }
-        None
+
+        Ok(imp::AssignPattern::Tup(
+            elts.iter()
+                .map(|elt| self.parse_target(elt))
+                .collect::<PyResult<_>>()?,
+        ))
     }
 
-    fn parse_if(
+    /// Marks the variables bound by a pattern as local variables
+    fn add_pattern_locals(&mut self, pat: &imp::AssignPattern) {
+        match pat {
+            imp::AssignPattern::Var(nam) => self.add_local(nam.as_ref()),
+            imp::AssignPattern::Tup(pats) => {
+                pats.iter().for_each(|pat| self.add_pattern_locals(pat))
+            }
+            _ => {}
+        }
+    }
+
+    /// Parses an assignment, which can unpack tuples, like `a, b = b, a`, and
+    /// assign the same value to many targets, like `a = b = 0`
+    fn parse_assign_stmt(
         &mut self,
-        stmt_if: &StmtIf,
+        assign: &StmtAssign,
         stmts: &Vec<rStmt>,
         index: usize,
-    ) -> Option<FromExpr> {
-        let cond = self.parse_expr_type(*stmt_if.test.clone());
-        let then = self.parse_vec(&stmt_if.body, 0);
-        let otherwise = self.parse_vec(&stmt_if.orelse, 0);
+    ) -> PyResult<Option<FromExpr>> {
+        let value = self.parse_assign(assign)?;
 
-        let nxt = self.parse_vec(stmts, index + 1);
+        if let [rExpr::Name(target)] = assign.targets.as_slice() {
+            let name = target.id.to_string();
 
-        let b_nxt = match nxt {
-            Some(FromExpr::Statement(nxt)) => Some(Box::new(nxt)),
-            _ => None,
+            if let Some(main_call) =
+                self.parse_main_call(&value, &name, stmts, index)?
+            {
+                return Ok(Some(main_call));
+            }
+
+            if let FromExpr::Expr(Expr::Call {
+                fun,
+                args: _,
+                kwargs: _,
+            }) = value.clone()
+            {
+                if let Expr::Var { nam } = *fun {
+                    if nam.to_string() == "switch" {
+                        return self.parse_switch(&name, stmts, &index);
+                    }
+                }
+            }
+        }
+
+        let FromExpr::Expr(val) = value else {
+            return self.unsupported(assign, "Type unions inside functions");
         };
 
-        match (cond, then, otherwise, b_nxt) {
-            (
-                Some(FromExpr::Expr(cond)),
-                Some(FromExpr::Statement(then)),
-                Some(FromExpr::Statement(otherwise)),
-                b_nxt,
-            ) => Some(FromExpr::Statement(Stmt::If {
-                cond: Box::new(cond),
-                then: Box::new(then),
-                otherwise: Box::new(otherwise),
-                nxt: b_nxt,
-            })),
-            (_, _, _, _) => {
-                panic!("If Statement must have an else.")
+        let pats = assign
+            .targets
+            .iter()
+            .map(|target| self.parse_target(target))
+            .collect::<PyResult<Vec<_>>>()?;
+
+        pats.iter().for_each(|pat| self.add_pattern_locals(pat));
+        pats.iter().for_each(|pat| self.bind_num_types(pat, &val));
+
+        // The integer literals bound to the variables take the type the uses
+        // of the variables give them, unless many targets share them
+        let (first, rest) =
+            pats.split_first().expect("assignments have targets");
+        let mut val = val;
+        let mut literals = vec![];
+        int_literals(first, &mut val, &mut literals);
+        let ints: Vec<_> = literals
+            .into_iter()
+            .filter_map(|(nam, _)| self.int_var(nam.as_ref()))
+            .collect();
+        if !rest.is_empty() {
+            for int in &ints {
+                self.int_types[*int].0 = Some(self.int_type(*int));
             }
         }
-    }
 
-    fn parse_stmt_expr(&mut self, expr: &StmtExpr) -> Option<FromExpr> {
-        if let Some(ctx) = &self.ctx {
-            if ctx.now == CurContext::Main {
-                let val = self.parse_expr_type(*expr.value.clone());
-
-                if let Some(FromExpr::Expr(call)) = val {
-                    if let Expr::Call {
-                        fun,
-                        args: _,
-                        kwargs: _,
-                    } = call.clone()
-                    {
-                        if let imp::Expr::Var { nam } = *fun {
-                            if nam.to_string() == *ctx.subs.first().unwrap() {
-                                return Some(FromExpr::Statement(
-                                    Stmt::Return {
-                                        term: Box::new(call),
-                                    },
-                                ));
-                            }
-                        }
-                    }
-                }
+        let mut nxt = into_nxt(self.parse_vec(stmts, index + 1)?);
+
+        let mut literals = vec![];
+        int_literals(first, &mut val, &mut literals);
+        for ((_, literal), int) in literals.into_iter().zip(ints) {
+            let ty = self.int_type(int);
+            if !coerce(literal, ty) {
+                return self.unsupported(
+                    assign,
+                    &format!("Number literals that aren't a valid {}", ty),
+                );
             }
         }
 
-        None
+        // The value is computed once, the other targets take it from the first
+        for pat in rest.iter().rev() {
+            nxt = Some(Box::new(Stmt::Assign {
+                pat: pat.clone(),
+                val: Box::new(pattern_expr(first)),
+                nxt,
+            }));
+        }
+
+        Ok(Some(FromExpr::Statement(imp::Stmt::Assign {
+            pat: first.clone(),
+            val: Box::new(val),
+            nxt,
+        })))
     }
 
-    fn parse_assign_stmt(
+    /// Parses an augmented assignment like `x += 1` as `x = x + 1`
+    fn parse_aug_assign(
         &mut self,
-        assign: &StmtAssign,
+        assign: &StmtAugAssign,
         stmts: &Vec<rStmt>,
         index: usize,
-    ) -> Option<FromExpr> {
-        let value = self.parse_assign(assign).unwrap();
-        let name = assign
-            .targets
-            .first()
-            .unwrap()
-            .clone()
-            .name_expr()
-            .unwrap()
-            .id
-            .to_string();
-
-        if let Some(main_call) =
-            self.parse_main_call(&value, &name, stmts, index)
-        {
-            return Some(main_call);
-        }
+    ) -> PyResult<Option<FromExpr>> {
+        let rExpr::Name(target) = assign.target.as_ref() else {
+            return self.unsupported(
+                assign,
+                "Augmented assignments to anything other than a variable",
+            );
+        };
+        let nam = Name::new(target.id.to_string());
 
-        let nxt = self.parse_vec(stmts, index + 1);
+        let value = self.parse_expr(*assign.value.clone())?;
+        let var = Expr::Var { nam: nam.clone() };
+        let val = self.parse_operation(&assign.op, var, value, assign)?;
 
-        if let FromExpr::Expr(Expr::Call {
-            fun,
-            args: _,
-            kwargs: _,
-        }) = value.clone()
-        {
-            if let Expr::Var { nam } = *fun {
-                if nam.to_string() == "switch" {
-                    return self.parse_switch(&name, &nxt, stmts, &index);
-                }
-            }
-        }
+        self.add_local(nam.as_ref());
+        self.set_num_type(nam.as_ref(), self.infer(&val));
+        let nxt = into_nxt(self.parse_vec(stmts, index + 1)?);
 
-        if let FromExpr::Expr(val) = value {
-            return Some(FromExpr::Statement(imp::Stmt::Assign {
-                pat: imp::AssignPattern::Var(Name::new(name)),
-                val: Box::new(val),
-                nxt: nxt.map(|n| {
-                    if let FromExpr::Statement(n) = n {
-                        return Box::new(n);
-                    }
+        Ok(Some(FromExpr::Statement(Stmt::Assign {
+            pat: imp::AssignPattern::Var(nam),
+            val: Box::new(val),
+            nxt,
+        })))
+    }
 
-                    todo!()
-                }),
-            }));
+    /// Parses an annotated assignment like `x: u24 = 0`, while annotations
+    /// without a value only declare the variable and are skipped
+    fn parse_ann_assign(
+        &mut self,
+        assign: &StmtAnnAssign,
+        stmts: &Vec<rStmt>,
+        index: usize,
+    ) -> PyResult<Option<FromExpr>> {
+        let Some(value) = &assign.value else {
+            return self.parse_vec(stmts, index + 1);
+        };
+
+        let pat = self.parse_target(&assign.target)?;
+        let mut value = self.parse_expr(*value.clone())?;
+
+        let ty = NumType::from_annotation(&assign.annotation);
+        if let Some(ty) = ty {
+            self.expect_type(&mut value, ty, assign)?;
+        }
+
+        self.add_pattern_locals(&pat);
+        match (ty, target_var(&pat)) {
+            (Some(ty), Some(var)) => self.set_num_type(var, Some(ty)),
+            _ => self.bind_num_types(&pat, &value),
         }
+        let nxt = into_nxt(self.parse_vec(stmts, index + 1)?);
 
-        Some(value)
+        Ok(Some(FromExpr::Statement(Stmt::Assign {
+            pat,
+            val: Box::new(value),
+            nxt,
+        })))
     }
 
     fn parse_vec(
         &mut self,
         stmts: &Vec<rStmt>,
         index: usize,
-    ) -> Option<FromExpr> {
+    ) -> PyResult<Option<FromExpr>> {
         let stmt = match stmts.get(index) {
             Some(s) => s,
             None => {
-                return None;
+                return Ok(None);
             }
         };
 
@@ -613,40 +3551,96 @@ impl<'py> Parser<'py> {
             rStmt::Assign(assign) => {
                 self.parse_assign_stmt(assign, stmts, index)
             }
+            rStmt::AugAssign(assign) => {
+                self.parse_aug_assign(assign, stmts, index)
+            }
+            rStmt::AnnAssign(assign) => {
+                self.parse_ann_assign(assign, stmts, index)
+            }
             rStmt::If(stmt_if) => self.parse_if(stmt_if, stmts, index),
             rStmt::Return(r) => match &r.value {
                 Some(val) => {
-                    let term = self.parse_expr_type(*val.clone()).unwrap();
-                    if let FromExpr::Expr(term) = term {
-                        return Some(FromExpr::Statement(imp::Stmt::Return {
-                            term: Box::new(term),
-                        }));
+                    self.add_return_shape(TupleShape::of(val));
+                    let mut term = self.parse_expr(*val.clone())?;
+                    if let Some(ty) = self.return_type {
+                        self.expect_type(&mut term, ty, r)?;
                     }
-
-                    todo!()
+                    if self.optional_return && !self.is_optional(val) {
+                        term = maybe_some(term);
+                    }
+                    Ok(Some(FromExpr::Statement(imp::Stmt::Return {
+                        term: Box::new(term),
+                    })))
                 }
-                None => None,
+                None => Ok(None),
             },
-            rStmt::Expr(expr) => self.parse_stmt_expr(expr),
+            rStmt::Expr(expr) => self.parse_stmt_expr(expr, stmts, index),
+            rStmt::For(stmt_for) => self.parse_for(stmt_for, stmts, index),
+            rStmt::FunctionDef(fun_def) => {
+                self.parse_nested_def(fun_def, stmts, index)
+            }
+            rStmt::While(stmt_while) => {
+                self.parse_while(stmt_while, stmts, index)
+            }
+            rStmt::Break(_) | rStmt::Continue(_) if self.jumps.is_some() => {
+                let jumps = self.jumps.clone().unwrap();
+                let term = match stmt {
+                    rStmt::Break(_) => jumps.brk,
+                    _ => jumps.cont,
+                };
+                Ok(Some(FromExpr::Statement(Stmt::Return {
+                    term: Box::new(term),
+                })))
+            }
+            rStmt::Match(m)
+                if matches!(
+                    m.cases.first().map(|case| &case.pattern),
+                    Some(rPattern::MatchSequence(_))
+                ) =>
+            {
+                self.parse_unpacking_match(m, stmts, index)
+            }
             rStmt::Match(m) => {
-                if let Some(val) = self.parse_match(m, stmts, &index) {
-                    return Some(FromExpr::Statement(val));
-                }
-                None
+                Ok(self.parse_match(m, stmts, &index)?.map(FromExpr::Statement))
             }
-            _ => None,
+            _ => self.unsupported(stmt, stmt_kind(stmt)),
+        }
+    }
+
+    /// Records the shape of a tuple returned by the function being parsed,
+    /// joined with the ones of its other `return` statements
+    fn add_return_shape(&mut self, shape: TupleShape) {
+        match self
+            .return_shapes
+            .iter_mut()
+            .find(|(fun, _)| *fun == self.fun_name)
+        {
+            Some((_, known)) => *known = known.join(&shape),
+            None => self.return_shapes.push((self.fun_name.clone(), shape)),
         }
     }
 
-    fn add_adt(&mut self, nam: Name, adt: Adt) {
+    /// The shape of the tuples a translated function returns
+    pub fn return_shape(&self, fun: &str) -> TupleShape {
+        self.return_shapes
+            .iter()
+            .find(|(name, _)| name == fun)
+            .map(|(_, shape)| shape.clone())
+            .unwrap_or_default()
+    }
+
+    fn add_adt(&mut self, nam: Name, adt: Adt) -> PyResult<()> {
         if let Some(adt) = self.book.adts.get(&nam) {
             if adt.builtin {
-                panic!(
+                return Err(BendCompileError::new_err(format!(
                     "{} is a built-in datatype and should not be overridden.",
                     nam
-                );
+                )));
             } else {
-                panic!("Repeated datatype '{}'", nam);
+                return Err(BendCompileError::new_err(format!(
+                    "Repeated datatype '{}'",
+                    nam
+                )));
             }
         } else {
             for ctr in adt.ctrs.keys() {
@@ -661,18 +3655,22 @@ impl<'py> Parser<'py> {
                             .get(e.get())
                             .is_some_and(|adt| adt.builtin)
                         {
-                            panic!(
+                            return Err(BendCompileError::new_err(format!(
                                 "{} is a built-in constructor and should not be overridden.",
                                 e.key()
-                            );
+                            )));
                         } else {
-                            panic!("Repeated constructor '{}'", e.key());
+                            return Err(BendCompileError::new_err(format!(
+                                "Repeated constructor '{}'",
+                                e.key()
+                            )));
                         }
                     }
                 }
             }
         }
         self.book.adts.insert(nam.clone(), adt);
+        Ok(())
     }
 
     // Creates a Bend Definition for each argument for the annotaded function.
@@ -701,7 +3699,7 @@ impl<'py> Parser<'py> {
         &mut self,
         fun_name: &str,
         py_args: &[String],
-    ) -> Option<imp::Definition> {
+    ) -> PyResult<imp::Definition> {
         self.ctx = Some(Context {
             now: CurContext::Main,
             vars: py_args.to_vec(),
@@ -710,11 +3708,46 @@ impl<'py> Parser<'py> {
 
         let mut parsed_types: Vec<(String, imp::Expr)> = vec![];
 
-        for arg in self.fun_args.iter() {
-            parsed_types.push((
-                arg.0.clone(),
-                extract_type(arg.1.clone(), &self.book).unwrap(),
-            ));
+        let params = self
+            .find_def(fun_name)
+            .map(|def| def.args.args.clone())
+            .unwrap_or_default();
+
+        for (name, arg) in self.fun_args.iter() {
+            let annotation = params
+                .iter()
+                .find(|param| param.def.arg.as_str() == name)
+                .and_then(|param| param.def.annotation.as_deref());
+            let optional = annotation.and_then(optional_inner);
+
+            // Numbers take the type of their annotation, also inside an
+            // `Optional`, and integers without one the type their uses gave
+            // them
+            let num_type = optional
+                .or(annotation)
+                .and_then(NumType::from_annotation)
+                .or_else(|| {
+                    self.arg_types
+                        .iter()
+                        .find(|(arg, _)| arg == name)
+                        .map(|(_, ty)| *ty)
+                });
+            let is_num = arg.is_instance_of::<PyInt>()
+                || arg.is_instance_of::<PyFloat>();
+
+            let mut val = match num_type {
+                Some(ty) if is_num => {
+                    extract_num(arg.clone(), ty.builtin_type())?
+                }
+                _ => extract_type(arg.clone(), &self.book)?,
+            };
+
+            // Values passed to `Optional` parameters are wrapped in a `Maybe`
+            if optional.is_some() && !arg.is_none() {
+                val = maybe_some(val);
+            }
+
+            parsed_types.push((name.clone(), val));
         }
 
         self.parse_fun_args(&parsed_types);
@@ -740,14 +3773,14 @@ impl<'py> Parser<'py> {
             }),
         };
 
-        Some(imp::Definition {
+        Ok(imp::Definition {
             name: Name::new("main"),
             params: vec![],
             body: first,
         })
     }
 
-    fn parse_class_def(&mut self, class: &StmtClassDef) {
+    fn parse_class_def(&mut self, class: &StmtClassDef) -> PyResult<()> {
         let is_dataclass = class.decorator_list.iter().any(|exp| {
             if let rExpr::Name(nam) = exp {
                 if nam.id.to_string() == "dataclass" {
@@ -789,52 +3822,80 @@ impl<'py> Parser<'py> {
                             }
                         }
                     }
-                    _ => todo!(),
+                    // Docstrings
+                    rStmt::Expr(expr)
+                        if matches!(*expr.value, rExpr::Constant(_)) => {}
+                    // Methods aren't part of the values of the dataclass,
+                    // the ones that are jitted are translated on their own
+                    rStmt::FunctionDef(_) => {}
+                    _ => {
+                        return self.unsupported(
+                            stmt,
+                            "Dataclass members other than fields and methods",
+                        )
+                    }
                 }
             }
-            self.add_adt(Name::new(iden.clone()), adt);
+            self.add_adt(Name::new(iden.clone()), adt)?;
         }
+        Ok(())
     }
 
-    fn parse_type_alias(&mut self, assign: &StmtAssign) {
-        let iden = assign.targets.first().unwrap();
-
-        let name: String;
-
-        if let rExpr::Name(iden) = iden {
-            name = iden.id.to_string();
-            let mut adt = Adt {
-                ctrs: IndexMap::new(),
-                builtin: false,
-            };
+    fn parse_type_alias(&mut self, assign: &StmtAssign) -> PyResult<()> {
+        let Some(rExpr::Name(iden)) = assign.targets.first() else {
+            return Ok(());
+        };
 
-            let body = self.parse_expr_type(*assign.value.clone());
+        let name = iden.id.to_string();
+        let mut adt = Adt {
+            ctrs: IndexMap::new(),
+            builtin: false,
+        };
 
-            if let Some(FromExpr::CtrField(ctr)) = body {
-                for ct in ctr.clone() {
-                    let new_ctr = self.book.ctrs.swap_remove(&ct.nam);
+        // Only unions of dataclasses, like `Type = A | B`, are type aliases,
+        // any other module level assignment is left out of the book. The
+        // constants are defined with the values they have when a function
+        // runs, with `add_constant`.
+        let Ok(Some(FromExpr::CtrField(ctr))) =
+            self.parse_expr_type(*assign.value.clone())
+        else {
+            return Ok(());
+        };
 
-                    let new_adt = self.book.adts.swap_remove(&new_ctr.unwrap());
+        for ct in ctr.clone() {
+            let new_adt = self
+                .book
+                .ctrs
+                .swap_remove(&ct.nam)
+                .and_then(|new_ctr| self.book.adts.swap_remove(&new_ctr));
 
-                    let mut ctrs: Vec<CtrField> = vec![];
-                    for ca in new_adt.unwrap().ctrs.values() {
-                        for i in ca {
-                            ctrs.push(i.clone());
-                        }
-                    }
+            let Some(new_adt) = new_adt else {
+                return Err(BendCompileError::new_err(format!(
+                    "Type {} of the union {} is not a dataclass",
+                    ct.nam, name
+                )));
+            };
 
-                    adt.ctrs.insert(
-                        Name::new(format!("{}/{}", name, ct.nam)),
-                        ctrs.clone(),
-                    );
+            let mut ctrs: Vec<CtrField> = vec![];
+            for ca in new_adt.ctrs.values() {
+                for i in ca {
+                    ctrs.push(i.clone());
                 }
             }
 
-            self.add_adt(Name::new(name), adt);
+            adt.ctrs.insert(
+                Name::new(format!("{}/{}", name, ct.nam)),
+                ctrs.clone(),
+            );
         }
+
+        self.add_adt(Name::new(name), adt)
     }
 
-    fn parse_function_def(&mut self, fun_def: &StmtFunctionDef) {
+    fn parse_function_def(
+        &mut self,
+        fun_def: &StmtFunctionDef,
+    ) -> PyResult<()> {
         let args = *fun_def.args.clone();
         let mut names: Vec<Name> = vec![];
 
@@ -842,7 +3903,28 @@ impl<'py> Parser<'py> {
             names.push(Name::new(arg.def.arg.to_string()));
         }
 
-        let expr = self.parse_vec(&fun_def.body, 0);
+        self.locals = names.iter().map(|nam| nam.to_string()).collect();
+        self.fun_name = fun_def.name.to_string();
+        self.lifted.clear();
+        self.optional_return = returns_optional(fun_def);
+        self.return_type = fun_def
+            .returns
+            .as_deref()
+            .and_then(NumType::from_annotation);
+        self.num_types.clear();
+        self.int_vars.clear();
+        self.int_types.clear();
+        self.bind_param_types(fun_def);
+        let int_args = self.bind_arg_types(fun_def);
+
+        let expr = self.parse_vec(&fun_def.body, 0)?;
+
+        // The integer arguments are converted into the type their uses gave
+        // them
+        for (name, int) in int_args {
+            let ty = self.int_type(int);
+            self.arg_types.push((name, ty));
+        }
 
         if let Some(FromExpr::Statement(e)) = expr {
             let def = imp::Definition {
@@ -852,38 +3934,79 @@ impl<'py> Parser<'py> {
             };
             self.definitions.push(def);
         }
+        Ok(())
     }
 
-    // Main function of the library, it parses the Python Module
+    /// Translates a function of the Python module and runs it
+    #[cfg(test)]
     pub fn parse(
         &mut self,
         fun: &str,
         py_args: &[String],
-    ) -> Result<String, String> {
+    ) -> PyResult<fun::Term> {
+        self.set_called(fun);
+        self.compile()?;
+        self.run(fun, py_args)
+    }
+
+    /// Translates the functions and types of the statements into the Book,
+    /// without the arguments of the call, so that the Book can be reused by
+    /// other calls
+    ///
+    /// # Errors
+    ///
+    /// Returns an `UnsupportedPythonConstruct` if a function can't be
+    /// translated, and a `BendCompileError` if its translation isn't valid Bend.
+    pub fn compile(&mut self) -> PyResult<()> {
         for stmt in self.statements.clone() {
             match stmt {
                 rStmt::FunctionDef(fun_def) => {
-                    self.parse_function_def(&fun_def)
+                    self.parse_function_def(&fun_def)?
                 }
                 // Treats an type alias, example: Type = A | B
-                rStmt::Assign(assign) => self.parse_type_alias(&assign),
+                rStmt::Assign(assign) => self.parse_type_alias(&assign)?,
                 // Treats an dataclass case
-                rStmt::ClassDef(class) => self.parse_class_def(&class),
+                rStmt::ClassDef(class) => self.parse_class_def(&class)?,
                 _ => {}
             }
         }
 
         // Turns all the parsed functions into Bend functional representation
         for def in &self.definitions {
-            let fun_def = def.clone().to_fun(false).unwrap();
+            let fun_def = def.clone().to_fun(false).map_err(|e| {
+                BendCompileError::new_err(format!(
+                    "In definition '{}':\n{}",
+                    def.name, e
+                ))
+            })?;
             self.book.defs.insert(fun_def.name.clone(), fun_def.clone());
         }
 
-        let main_def = self.parse_main(fun, py_args).unwrap();
+        Ok(())
+    }
+
+    /// The Book with the translated definitions
+    pub fn book(&self) -> &Book {
+        &self.book
+    }
+
+    /// Runs a function of a compiled Book with the arguments of the parser
+    ///
+    /// # Errors
+    ///
+    /// Returns an `ArgumentConversionError` if an argument can't be converted,
+    /// and a `BendCompileError` or `BendRuntimeError` if the Book fails.
+    pub fn run(
+        &mut self,
+        fun: &str,
+        py_args: &[String],
+    ) -> PyResult<fun::Term> {
+        let main_def = self.parse_main(fun, py_args)?;
 
-        self.book
-            .defs
-            .insert(Name::new("main"), main_def.to_fun(true).unwrap());
+        self.book.defs.insert(
+            Name::new("main"),
+            main_def.to_fun(true).map_err(BendCompileError::new_err)?,
+        );
 
         self.book.entrypoint = None;
 
@@ -893,11 +4016,408 @@ impl<'py> Parser<'py> {
 
         match return_val {
             Ok(val) => match val {
-                Some(val) => Ok(val.0.to_string()),
-                None => Err(String::from("Could not parse HVM output")),
+                Some(mut val) => {
+                    self.expand_constants(&mut val.0);
+                    Ok(val.0)
+                }
+                None => {
+                    Err(BendRuntimeError::new_err("Could not parse HVM output"))
+                }
             },
 
-            Err(e) => Err(e.to_string()),
+            // The HVM stops on the errors Python would raise an exception for
+            Err(e @ RunError::Runtime(_)) => Err(BendRuntimeError::new_err(
+                format!("{}\nThe HVM stops if a division by zero happens or if a list index is out of range", e),
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use pyo3::types::{PyDict, PyDictMethods, PyList};
+    use pyo3::{IntoPy, Python};
+    use rustpython_parser::{parse, Mode};
+
+    use super::*;
+
+    fn parse_module(code: &str) -> Vec<rStmt> {
+        let rustpython_parser::ast::Mod::Module(module) =
+            parse(code, Mode::Module, "test.py").unwrap()
+        else {
+            unreachable!()
+        };
+        module.body
+    }
+
+    #[test]
+    fn unsupported_construct() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "def fun(x):\n    total = 0\n    try:\n        total = x\n    finally:\n        return total\n";
+
+        let mut parser = Parser::new(parse_module(code), vec![], code);
+        let err = parser.parse("fun", &[]).unwrap_err();
+
+        Python::with_gil(|py| {
+            assert!(err.is_instance_of::<UnsupportedPythonConstruct>(py));
+            assert!(err.to_string().contains("`try` statements"));
+            assert!(err.to_string().contains("line 3: try:"));
+        });
+    }
+
+    #[test]
+    fn appended_functions() {
+        pyo3::prepare_freethreaded_python();
+
+        let module = "@dataclass\nclass Point:\n    x: int\n\n    def norm(self):\n        return self.x\n";
+        let function = "def fun(x):\n    total = 0\n    try:\n        total = x\n    finally:\n        return total\n";
+        let code = format!("{}\n{}", module, function);
+
+        let mut parser = Parser::new(parse_module(&code), vec![], &code);
+        parser.add_segment(module.len() + 1, 10);
+        let err = parser.parse("fun", &[]).unwrap_err();
+
+        assert!(err.to_string().contains("line 12: try:"));
+    }
+
+    #[test]
+    fn for_loops() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "def fun():\n    total = 0\n    for i in range(10, 0, -3):\n        total = total + i\n    return total\n";
+        let mut parser = Parser::new(parse_module(code), vec![], code);
+        assert_eq!(parser.parse("fun", &[]).unwrap().to_string(), "22");
+
+        let code = "def fun(xs):\n    total = 0\n    count = 0\n    for x in xs:\n        double = x * 2\n        total = total + double\n        count = count + 1\n    return total * count\n";
+
+        Python::with_gil(|py| {
+            let xs = PyList::new_bound(py, [1, 2, 3]).into_any();
+            let mut parser = Parser::new(
+                parse_module(code),
+                vec![("xs".to_string(), xs)],
+                code,
+            );
+            assert_eq!(
+                parser
+                    .parse("fun", &["xs".to_string()])
+                    .unwrap()
+                    .to_string(),
+                "36"
+            );
+        });
+    }
+
+    #[test]
+    fn while_loops() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "def fun():\n    a = 1071\n    b = 462\n    while b != 0:\n        t = b\n        b = a - (a // b) * b\n        a = t\n    return a\n";
+        let mut parser = Parser::new(parse_module(code), vec![], code);
+        assert_eq!(parser.parse("fun", &[]).unwrap().to_string(), "21");
+
+        let code = "def fun():\n    i = 0\n    total = 0\n    while i < 100:\n        i = i + 1\n        if i > 5:\n            break\n        else:\n            total = total + 10\n        if i == 2:\n            continue\n        else:\n            total = total + i\n    return total\n";
+        let mut parser = Parser::new(parse_module(code), vec![], code);
+        assert_eq!(parser.parse("fun", &[]).unwrap().to_string(), "63");
+
+        let code = "def fun():\n    i = 0\n    while i < 10:\n        i = i + 1\n        if i == 5:\n            return i\n    return 0\n";
+        let mut parser = Parser::new(parse_module(code), vec![], code);
+        let err = parser.parse("fun", &[]).unwrap_err();
+
+        Python::with_gil(|py| {
+            assert!(err.is_instance_of::<UnsupportedPythonConstruct>(py));
+            assert!(err
+                .to_string()
+                .contains("`return` statements inside loops"));
+            assert!(err.to_string().contains("line 6: return i"));
+        });
+    }
+
+    #[test]
+    fn operators() {
+        pyo3::prepare_freethreaded_python();
+
+        let cases = [
+            ("(3 <= 3) + (2 >= 3) * 10 + (1 < 2 < 2) * 100", "1"),
+            ("17 % 5 * 10 + 17 // 5", "23"),
+            ("-5 - -2", "-3"),
+            ("(not 0) + (not 3) * 10 + (~0 == 16777215) * 100", "101"),
+            ("(0 and 4) + (0 or 4) * 10 + (2 and 3) * 100", "340"),
+            (
+                "(-7 // 2, -7 % 2, 7 // -2, 7 % -2, -6 // 2)",
+                "(-4, (+1, (-4, (-1, -3))))",
+            ),
+            (
+                "(-7.5 // 2, -7.5 % 2, 7.5 // -2.0, 7.5 % -2.0)",
+                "(-4.000, (0.500, (-4.000, -0.500)))",
+            ),
+            ("(2 ** 10, (-3) ** 3, 4.0 ** 0.5)", "(1024, (-27, 2.000))"),
+            ("(0.0 or 2.5, 1.5 and 0.0)", "(2.500, 0.000)"),
+        ];
+
+        for (expr, expected) in cases {
+            let code = format!("def fun():\n    return {}\n", expr);
+            let mut parser = Parser::new(parse_module(&code), vec![], &code);
+            assert_eq!(
+                parser.parse("fun", &[]).unwrap().to_string(),
+                expected,
+                "{}",
+                expr
+            );
+        }
+
+        let code = "def inv(x: i24) -> i24:\n    return ~x\n\ndef neg(x: i24):\n    return -x, (not x)\n\ndef fun():\n    return inv(5), inv(-1), neg(-3), neg(0)\n";
+        let mut parser = Parser::new(parse_module(code), vec![], code);
+        assert_eq!(
+            parser.parse("fun", &[]).unwrap().to_string(),
+            "(-6, (+0, ((+3, 0), (+0, 1))))"
+        );
+
+        // The right operand is only evaluated when it's the result, otherwise
+        // the recursion wouldn't end
+        let code = "def down(n):\n    return n == 0 or down(n - 1)\n\ndef all_pos(n: i24):\n    return n <= 0 or n > 0 and all_pos(n - 1)\n\ndef pick(n):\n    xs = [1, 2, 3, n]\n    big = (lambda x: x > 5 and x)(n)\n    return sum([x for x in xs if x > 1 if x < 3 or x == n]) + big * 10\n\ndef fun():\n    return down(5), all_pos(4), pick(7)\n";
+        let mut parser = Parser::new(parse_module(code), vec![], code);
+        assert_eq!(
+            parser.parse("fun", &[]).unwrap().to_string(),
+            "(1, (1, 79))"
+        );
+
+        let unsupported = [
+            ("1 / 2", "The `/` operator on numbers that aren't f24s"),
+            ("1.5 ^ 2.0", "Bitwise operators on f24s"),
+            ("~1.5", "The `~` operator on f24s"),
+            ("-4 << 1", "Shifts of i24s"),
+            ("2 ** -1", "Number literals that aren't a valid u24"),
+        ];
+
+        for (expr, error) in unsupported {
+            let code = format!("def fun():\n    return {}\n", expr);
+            let mut parser = Parser::new(parse_module(&code), vec![], &code);
+            let err = parser.parse("fun", &[]).unwrap_err();
+            assert!(err.to_string().contains(error), "{}", expr);
+        }
+
+        let code = "def fun(xs):\n    return (2 in xs) + (5 not in xs) * 10\n";
+
+        Python::with_gil(|py| {
+            let xs = PyList::new_bound(py, [1, 2]).into_any();
+            let mut parser = Parser::new(
+                parse_module(code),
+                vec![("xs".to_string(), xs)],
+                code,
+            );
+            assert_eq!(
+                parser
+                    .parse("fun", &["xs".to_string()])
+                    .unwrap()
+                    .to_string(),
+                "11"
+            );
+        });
+    }
+
+    #[test]
+    fn if_without_else() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "def fun(n):\n    if n == 0:\n        return 100\n    x = 1\n    if n > 3:\n        x = x + n\n    elif n > 1:\n        x = 2\n    return x\n";
+
+        for (n, expected) in [(0, "100"), (1, "1"), (2, "2"), (5, "6")] {
+            let code = format!("{}def run():\n    return fun({})\n", code, n);
+            let mut parser = Parser::new(parse_module(&code), vec![], &code);
+            assert_eq!(parser.parse("run", &[]).unwrap().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn assignments() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "def fun():\n    a, b = 0, 1\n    for i in range(10):\n        a, b = b, a + b\n    x: u24 = a\n    x += 1\n    y = z = x * 2\n    return y + z\n";
+        let mut parser = Parser::new(parse_module(code), vec![], code);
+        assert_eq!(parser.parse("fun", &[]).unwrap().to_string(), "224");
+    }
+
+    #[test]
+    fn closures() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "def apply(f, x):\n    return f(x)\n\ndef fun():\n    k = 3\n    def fact(m):\n        if m == 0:\n            return 1\n        return m * fact(m - 1)\n    def scale(x):\n        return x * k\n    return apply(lambda y: y + k, 1) + scale(fact(3))\n";
+        let mut parser = Parser::new(parse_module(code), vec![], code);
+        assert_eq!(parser.parse("fun", &[]).unwrap().to_string(), "22");
+    }
+
+    #[test]
+    fn tuples() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "def bounds(xs):\n    lo, hi = 100, 0\n    for x in xs:\n        if x < lo:\n            lo = x\n        if x > hi:\n            hi = x\n    return lo, hi\n\ndef fun(xs):\n    pairs = ((1, 2), (3, 4))\n    match pairs:\n        case ((a, _), (_, d)):\n            s = a + d\n    lo, hi = bounds(xs)\n    return (s, (lo, hi))\n";
+        Python::with_gil(|py| {
+            let xs = PyList::new_bound(py, [5, 9, 2]).into_any();
+            let mut parser = Parser::new(
+                parse_module(code),
+                vec![("xs".to_string(), xs)],
+                code,
+            );
+            assert_eq!(
+                parser
+                    .parse("fun", &["xs".to_string()])
+                    .unwrap()
+                    .to_string(),
+                "(5, (+2, +9))"
+            );
+        });
+    }
+
+    #[test]
+    fn lists() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "def fun(n):\n    xs = [1, 2, 3, n]\n    ys = [x * x for x in xs if x > 1]\n    return sum(ys) + len(xs) * 100 + xs[2] * 1000 + xs[-1] * 10000\n\ndef run():\n    return fun(4)\n";
+        let mut parser = Parser::new(parse_module(code), vec![], code);
+        assert_eq!(parser.parse("run", &[]).unwrap().to_string(), "43429");
+
+        let cases = [
+            ("sum([i * 2 for i in range(n)])", "12"),
+            ("len([i for i in range(n, 0, -3)])", "2"),
+            ("sum([i for i in range(10, 0, -3)])", "22"),
+            ("sum([i for i in range(5, -5, -3)])", "+2"),
+            ("len([i for i in range(-3, 4) if i != 0])", "6"),
+        ];
+
+        for (expr, expected) in cases {
+            let code = format!(
+                "def fun(n):\n    return {}\n\ndef run():\n    return fun(4)\n",
+                expr
+            );
+            let mut parser = Parser::new(parse_module(&code), vec![], &code);
+            assert_eq!(
+                parser.parse("run", &[]).unwrap().to_string(),
+                expected,
+                "{}",
+                expr
+            );
         }
+
+        let code = "def fun(n):\n    xs = [1, 2]\n    if n > 0:\n        return xs[n]\n    return xs[n] * 2\n\ndef run():\n    return (fun(0), fun(1), fun(2))\n";
+        let mut parser = Parser::new(parse_module(code), vec![], code);
+        let err = parser.parse("run", &[]).unwrap_err();
+
+        Python::with_gil(|py| {
+            assert!(err.is_instance_of::<BendRuntimeError>(py));
+            assert!(err.to_string().contains("list index is out of range"));
+        });
+    }
+
+    #[test]
+    fn optionals() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "def half(n) -> Optional[int]:\n    if n % 2 == 1:\n        return None\n    return n // 2\n\ndef fun(n):\n    h = half(n)\n    if h is None:\n        return True\n    return h * 10 + (half(h) is not None)\n\ndef run():\n    return fun(3) + fun(8) * 1000\n";
+        let mut parser = Parser::new(parse_module(code), vec![], code);
+        assert_eq!(parser.parse("run", &[]).unwrap().to_string(), "41001");
+    }
+
+    #[test]
+    fn num_types() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "def diff(a: int, b: int) -> int:\n    return a - b\n\ndef fun():\n    total = 0\n    for i in range(diff(1, 3), 2):\n        total += i * 10\n    x: float = 3\n    return total, x / 2\n";
+        let mut parser = Parser::new(parse_module(code), vec![], code);
+        assert_eq!(
+            parser.parse("fun", &[]).unwrap().to_string(),
+            "(-20, 1.500)"
+        );
+
+        let code = "def fun(a: u24, b: i24):\n    return a < b\n";
+        let mut parser = Parser::new(parse_module(code), vec![], code);
+        let err = parser.parse("fun", &[]).unwrap_err();
+        assert!(err.to_string().contains("between u24 and i24 numbers"));
+
+        // Variables bound to integer literals, and the integer arguments, take
+        // the type of the numbers they are operated with, and the integers
+        // operated with floats are converted
+        let cases = [
+            ("def fun():\n    x = 1\n    x = x + 0.5\n    return x\n", "1.500"),
+            ("def fun():\n    i = 1\n    return i + 0.5\n", "1.500"),
+            ("def fun():\n    return sum([i + 0.5 for i in [1, 2]])\n", "4.000"),
+            ("def fun():\n    total = 0\n    for x in [1, -2]:\n        total = total + x\n    return total\n", "-1"),
+            ("def fun(a, b):\n    return a + b\n", "3.500"),
+            ("def half(x: f24):\n    return x / 2\n\ndef fun(a, b):\n    return half(b) + a\n", "2.750"),
+            ("def fun(a: u24, b: f24):\n    return a < b\n", "0"),
+            ("def triple(x: u24):\n    return x * 3\n\ndef fun(a, b):\n    return triple(a) + b\n", "7.500"),
+            ("def triple(x):\n    return x * 3\n\ndef fun(a, b):\n    return triple(a) + 1.5\n", "7.500"),
+            ("def tr(x):\n    return x * 3\n\ndef fun(a, b):\n    return tr(b) + a\n", "6.500"),
+            ("def mix(x, y):\n    return x * 2 + y\n\ndef fun(a, b):\n    return mix(a, b) + 1\n", "6.500"),
+            ("def h2(x, y):\n    return x + y\n\ndef fun(a, b):\n    return h2(2, 1.5)\n", "3.500"),
+            ("def fun(a, b):\n    x = 1\n    y = x + 2\n    return y + b\n", "4.500"),
+        ];
+
+        Python::with_gil(|py| {
+            let args = || {
+                vec![
+                    ("a".to_string(), 2_u32.into_py(py).into_bound(py)),
+                    ("b".to_string(), 1.5_f64.into_py(py).into_bound(py)),
+                ]
+            };
+
+            for (code, expected) in cases {
+                let mut parser = Parser::new(parse_module(code), args(), code);
+                let result = parser.parse("fun", &[]).unwrap();
+                assert_eq!(result.to_string(), expected, "{}", code);
+            }
+
+            // Mixed operations with values whose type isn't known, or between
+            // signed and unsigned integers, can't be translated
+            let errors = [
+                ("def first(xs):\n    return xs[0]\n\ndef fun(a, b):\n    return first([a]) + 1.5\n", "Results of first, whose type isn't known, where f24 values are expected"),
+                ("def fun(a, b):\n    x = -1\n    return x + len([a])\n", "i24 values where u24 values are expected"),
+            ];
+            for (code, expected) in errors {
+                let mut parser = Parser::new(parse_module(code), args(), code);
+                let err = parser.parse("fun", &[]).unwrap_err();
+                assert!(err.is_instance_of::<UnsupportedPythonConstruct>(py));
+                assert!(err.to_string().contains(expected), "{}", err);
+            }
+        });
+    }
+
+    #[test]
+    fn constants() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "SCALE = 1.5\nPAIR = (4, 2)\n\ndef fun(x: float):\n    a, b = PAIR\n    return x * SCALE + 1, a + b, NAME\n";
+
+        Python::with_gil(|py| {
+            let globals = PyDict::new_bound(py);
+            py.run_bound(
+                "SCALE = 1.5\nPAIR = (4, 2)\nNAME = 'bend'",
+                Some(&globals),
+                None,
+            )
+            .unwrap();
+            let x = 2.0_f64.into_py(py).into_bound(py);
+
+            let mut parser = Parser::new(
+                parse_module(code),
+                vec![("x".to_string(), x)],
+                code,
+            );
+            parser.add_constant_type("SCALE", NumType::F24);
+            parser.compile().unwrap();
+
+            for name in ["SCALE", "PAIR", "NAME"] {
+                let value = globals.get_item(name).unwrap().unwrap();
+                parser
+                    .add_constant(name, &value, NumType::of_value(&value))
+                    .unwrap();
+            }
+
+            assert_eq!(
+                parser.run("fun", &["x".to_string()]).unwrap().to_string(),
+                "(4.000, (6, \"bend\"))"
+            );
+        });
     }
 }
@@ -0,0 +1,184 @@
+//! Numeric types of the translated code
+//!
+//! Python has a single integer type, while Bend numbers are either `u24`, `i24`
+//! or `f24`, and the HVM doesn't convert between them. The type of a number is
+//! taken from the annotations of the Python code and from the arguments of the
+//! call, and given to the literals operated with it. The variables bound to
+//! integer literals, like `x = 1`, and the integer arguments take the type of
+//! the numbers they are operated with too.
+
+use std::fmt::Display;
+
+use bend::fun::{Num, Op};
+use bend::imp::Expr;
+use pyo3::types::{PyAnyMethods, PyBool, PyFloat, PyInt};
+use pyo3::{Bound, PyAny};
+use rustpython_parser::ast::Expr as rExpr;
+
+use crate::types::BuiltinType;
+
+/// The type of a Bend number
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumType {
+    U24,
+    I24,
+    F24,
+}
+
+impl NumType {
+    /// Reads the numeric type named by a type annotation, like `i24`,
+    /// `benda.f24` or `int`
+    ///
+    /// # Returns
+    ///
+    /// The type, or `None` if the annotation isn't a numeric type. Python
+    /// `int`s can be negative, so they are `i24`, and `float`s are `f24`.
+    pub fn from_annotation(annotation: &rExpr) -> Option<Self> {
+        let name = match annotation {
+            rExpr::Name(nam) => nam.id.as_str(),
+            rExpr::Attribute(att) => att.attr.as_str(),
+            _ => return None,
+        };
+
+        match name {
+            "u24" | "U24" => Some(NumType::U24),
+            "i24" | "I24" | "int" => Some(NumType::I24),
+            "f24" | "F24" | "float" => Some(NumType::F24),
+            _ => None,
+        }
+    }
+
+    /// The type a Python number has without an annotation, like the ones of
+    /// the arguments: `float`s are `f24`, negative `int`s are `i24` and the
+    /// other `int`s are `u24`
+    ///
+    /// # Returns
+    ///
+    /// The type, or `None` if the value isn't a number. Booleans aren't
+    /// numbers here, as they are converted on their own.
+    pub fn of_value(value: &Bound<PyAny>) -> Option<Self> {
+        if value.is_instance_of::<PyFloat>() {
+            return Some(NumType::F24);
+        }
+        if !value.is_instance_of::<PyInt>() || value.is_instance_of::<PyBool>()
+        {
+            return None;
+        }
+
+        match value.lt(0) {
+            Ok(true) => Some(NumType::I24),
+            _ => Some(NumType::U24),
+        }
+    }
+
+    /// The type Python numbers are converted into to have this type
+    pub fn builtin_type(self) -> BuiltinType {
+        match self {
+            NumType::U24 => BuiltinType::U24,
+            NumType::I24 => BuiltinType::I32,
+            NumType::F24 => BuiltinType::F32,
+        }
+    }
+
+    /// The type of a number
+    pub fn of(num: &Num) -> Self {
+        match num {
+            Num::U24(_) => NumType::U24,
+            Num::I24(_) => NumType::I24,
+            Num::F24(_) => NumType::F24,
+        }
+    }
+}
+
+impl Display for NumType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NumType::U24 => f.write_str("u24"),
+            NumType::I24 => f.write_str("i24"),
+            NumType::F24 => f.write_str("f24"),
+        }
+    }
+}
+
+/// Checks if an operator computes a number of the type of its operands,
+/// unlike comparisons, which result in a `u24`
+pub fn is_arithmetic(op: &Op) -> bool {
+    !matches!(op, Op::EQ | Op::NEQ | Op::LT | Op::GT | Op::LE | Op::GE)
+}
+
+/// Checks if an expression has negative integer literals, which make it an
+/// `i24` expression when its type isn't known otherwise
+pub fn is_signed(expr: &Expr) -> bool {
+    match expr {
+        Expr::Num { val: Num::I24(_) } => true,
+        Expr::Opr { op, lhs, rhs } if is_arithmetic(op) => {
+            is_signed(lhs) || is_signed(rhs)
+        }
+        _ => false,
+    }
+}
+
+/// The type an expression made only of integer literals, like `1` or `2 * -3`,
+/// has until the numbers it's operated with give it another one
+///
+/// # Returns
+///
+/// `u24`, `i24` if some of the literals are negative, or `None` if the
+/// expression isn't made only of integer literals.
+pub fn int_literal_type(expr: &Expr) -> Option<NumType> {
+    fn is_int_literal(expr: &Expr) -> bool {
+        match expr {
+            Expr::Num {
+                val: Num::U24(_) | Num::I24(_),
+            } => true,
+            Expr::Opr { op, lhs, rhs } if is_arithmetic(op) => {
+                is_int_literal(lhs) && is_int_literal(rhs)
+            }
+            _ => false,
+        }
+    }
+
+    match is_int_literal(expr) {
+        true if is_signed(expr) => Some(NumType::I24),
+        true => Some(NumType::U24),
+        false => None,
+    }
+}
+
+/// Gives a numeric type to the number literals of an expression, including
+/// the ones of the arithmetic it's made of
+///
+/// # Returns
+///
+/// `false` if a literal can't have the type, like a negative or a fractional
+/// literal in a `u24` expression.
+pub fn coerce(expr: &mut Expr, ty: NumType) -> bool {
+    match expr {
+        Expr::Num { val } => {
+            let coerced = match (*val, ty) {
+                (Num::U24(val), NumType::U24) => Some(Num::U24(val)),
+                (Num::U24(val), NumType::I24) => i32::try_from(val)
+                    .ok()
+                    .filter(|val| *val <= 0x7FFFFF)
+                    .map(Num::I24),
+                (Num::U24(val), NumType::F24) => Some(Num::F24(val as f32)),
+                (Num::I24(val), NumType::I24) => Some(Num::I24(val)),
+                (Num::I24(val), NumType::F24) => Some(Num::F24(val as f32)),
+                (Num::F24(val), NumType::F24) => Some(Num::F24(val)),
+                _ => None,
+            };
+
+            match coerced {
+                Some(coerced) => {
+                    *val = coerced;
+                    true
+                }
+                None => false,
+            }
+        }
+        Expr::Opr { op, lhs, rhs } if is_arithmetic(op) => {
+            coerce(lhs, ty) && coerce(rhs, ty)
+        }
+        _ => true,
+    }
+}
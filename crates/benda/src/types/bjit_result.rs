@@ -0,0 +1,501 @@
+//! Results of `bjit` functions
+//!
+//! The terms read back from the HVM don't carry the Python types they were
+//! translated from: lists, `Optional` values and dataclasses all come back as
+//! num-scott encoded constructors, which only have a tag and their fields. The
+//! annotations of the Python code tell which Python values they stand for, so
+//! the return annotation of a function, and the annotations of the fields of
+//! the dataclasses it names, guide the conversion of its result.
+
+use std::cell::OnceCell;
+
+use bend::fun::{builtins, Book, FanKind, Name, Num, Term as BTerm};
+use pyo3::types::{PyAnyMethods, PyDict, PyDictMethods, PyList, PyTuple};
+use pyo3::{Bound, IntoPy, PyObject, PyResult, Python};
+use rustpython_parser::ast::{Expr as rExpr, Stmt as rStmt};
+
+use super::book::Adts;
+use super::user_adt::{from_term_into_py, split_ctr, split_maybe, CtrId};
+use crate::parser::num_type::NumType;
+use crate::parser::optional_inner;
+
+/// The nesting of the tuples a function returns, as written in its `return`
+/// statements
+///
+/// The HVM reads `(a, b, c)` back as `(a, (b, c))`, so the arity of the
+/// returned tuples tells how much of that nesting to undo when no annotation
+/// tells it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum TupleShape {
+    /// A tuple, with the shapes of its elements
+    Tuple(Vec<TupleShape>),
+    /// A value that isn't a tuple, or whose shape isn't known
+    #[default]
+    Value,
+}
+
+impl TupleShape {
+    /// The shape of a returned expression, like `(a, (b, c))`
+    pub fn of(expr: &rExpr) -> Self {
+        match expr {
+            rExpr::Tuple(tup) => {
+                TupleShape::Tuple(tup.elts.iter().map(TupleShape::of).collect())
+            }
+            _ => TupleShape::Value,
+        }
+    }
+
+    /// Joins the shapes of two `return` statements of a function, keeping
+    /// only the tuples that have the same arity in both
+    pub fn join(&self, other: &Self) -> Self {
+        match (self, other) {
+            (TupleShape::Tuple(a), TupleShape::Tuple(b))
+                if a.len() == b.len() =>
+            {
+                TupleShape::Tuple(
+                    a.iter().zip(b).map(|(a, b)| a.join(b)).collect(),
+                )
+            }
+            _ => TupleShape::Value,
+        }
+    }
+
+    /// The shapes of the elements of a tuple
+    fn elements(&self) -> &[TupleShape] {
+        match self {
+            TupleShape::Tuple(shapes) => shapes,
+            TupleShape::Value => &[],
+        }
+    }
+}
+
+/// The types a `bjit` function was translated with, used to convert its
+/// results into Python objects
+///
+/// # Fields
+///
+/// * `py` - The Python GIL token
+/// * `statements` - The statements of the module of the function, where its
+///   dataclasses are defined
+/// * `globals` - The globals of the function, where the classes of its
+///   dataclasses are found
+/// * `book` - The Book the function was translated into
+/// * `adts` - The ADTs of the Book, created the first time a term without an
+///   annotation needs them
+pub struct ResultTypes<'a, 'py> {
+    py: Python<'py>,
+    statements: &'a [rStmt],
+    globals: &'a Bound<'py, PyDict>,
+    book: &'a Book,
+    adts: OnceCell<Adts>,
+}
+
+impl<'a, 'py> ResultTypes<'a, 'py> {
+    pub fn new(
+        py: Python<'py>,
+        statements: &'a [rStmt],
+        globals: &'a Bound<'py, PyDict>,
+        book: &'a Book,
+    ) -> Self {
+        Self {
+            py,
+            statements,
+            globals,
+            book,
+            adts: OnceCell::new(),
+        }
+    }
+
+    /// Converts a term into the Python value of the given annotation
+    ///
+    /// Numbers become `int`s, or `float`s for `float` and `f24` annotations,
+    /// and `bool` annotations turn them into booleans. `tuple[...]`,
+    /// `list[...]`, `Optional[...]` and dataclass annotations, including
+    /// unions of dataclasses, are converted along with their elements and
+    /// fields.
+    ///
+    /// # Arguments
+    ///
+    /// * `term` - The term read back from the HVM
+    /// * `annotation` - The annotation of the term, if it has one
+    /// * `shape` - The shape of the tuples the term was translated from
+    ///
+    /// # Returns
+    ///
+    /// A `PyResult` with the converted object. Terms that don't match their
+    /// annotation, or have none, are converted like the results of a Book,
+    /// and the ones with no Python equivalent are returned as a `Term`.
+    pub fn to_py(
+        &self,
+        term: &BTerm,
+        annotation: Option<&rExpr>,
+        shape: &TupleShape,
+    ) -> PyResult<PyObject> {
+        let py = self.py;
+
+        // Only the values returned by functions with an `Optional` return
+        // annotation are wrapped in a `Maybe`, the ones of `Optional` elements
+        // and fields may be the value itself.
+        if let Some(inner) = annotation.and_then(optional_inner) {
+            return match split_maybe(term) {
+                Some(Some(value)) => self.to_py(value, Some(inner), shape),
+                Some(None) => Ok(py.None()),
+                None => self.to_py(term, Some(inner), shape),
+            };
+        }
+
+        if let BTerm::Fan {
+            fan: FanKind::Tup,
+            els,
+            ..
+        } = term
+        {
+            return self.tuple_to_py(els, annotation, shape);
+        }
+
+        let Some(annotation) = annotation else {
+            return self.unannotated(term);
+        };
+
+        let converted = match (term, annotation) {
+            (BTerm::Num { val }, rExpr::Name(nam))
+                if nam.id.as_str() == "bool" =>
+            {
+                let truthy = match val {
+                    Num::U24(val) => *val != 0,
+                    Num::I24(val) => *val != 0,
+                    Num::F24(val) => *val != 0.0,
+                };
+                Some(truthy.into_py(py))
+            }
+            (BTerm::Num { val }, _)
+                if NumType::from_annotation(annotation)
+                    == Some(NumType::F24) =>
+            {
+                let val = match val {
+                    Num::U24(val) => *val as f64,
+                    Num::I24(val) => *val as f64,
+                    Num::F24(val) => *val as f64,
+                };
+                Some(val.into_py(py))
+            }
+            _ => match subscript(annotation) {
+                Some(("list" | "List", element)) => {
+                    self.list_to_py(term, element)?
+                }
+                _ => match type_name(annotation) {
+                    Some(name) => self.dataclass_to_py(term, name)?,
+                    None => None,
+                },
+            },
+        };
+
+        match converted {
+            Some(converted) => Ok(converted),
+            None => self.unannotated(term),
+        }
+    }
+
+    /// Converts the elements of a tuple into a Python `tuple`, with the
+    /// annotations of `tuple[...]` or the arity of the returned tuple
+    ///
+    /// The elements of `tuple[T, ...]`, which doesn't tell the arity, all
+    /// take the annotation `T`.
+    fn tuple_to_py(
+        &self,
+        els: &[BTerm],
+        annotation: Option<&rExpr>,
+        shape: &TupleShape,
+    ) -> PyResult<PyObject> {
+        let (elements, repeated) = match annotation.and_then(subscript) {
+            Some(("tuple" | "Tuple", rExpr::Tuple(tup))) => {
+                match tup.elts.as_slice() {
+                    [element, rExpr::Constant(ellipsis)]
+                        if ellipsis.value.is_ellipsis() =>
+                    {
+                        (vec![], Some(element))
+                    }
+                    elements => (elements.iter().collect(), None),
+                }
+            }
+            Some(("tuple" | "Tuple", element)) => (vec![element], None),
+            _ => (vec![], None),
+        };
+        let shapes = shape.elements();
+
+        // The HVM reads `(a, b, c)` back as `(a, (b, c))`
+        let mut els: Vec<&BTerm> = els.iter().collect();
+        while els.len() < elements.len().max(shapes.len()) {
+            match els.last() {
+                Some(BTerm::Fan {
+                    fan: FanKind::Tup,
+                    els: rest,
+                    ..
+                }) => {
+                    els.pop();
+                    els.extend(rest.iter());
+                }
+                _ => break,
+            }
+        }
+
+        let mut values: Vec<PyObject> = vec![];
+        for (index, el) in els.into_iter().enumerate() {
+            values.push(self.to_py(
+                el,
+                elements.get(index).copied().or(repeated),
+                shapes.get(index).unwrap_or(&TupleShape::Value),
+            )?);
+        }
+        Ok(PyTuple::new_bound(self.py, values).into_py(self.py))
+    }
+
+    /// Converts a term that has no annotation, like the results of a Book
+    fn unannotated(&self, term: &BTerm) -> PyResult<PyObject> {
+        let adts = match self.adts.get() {
+            Some(adts) => adts,
+            None => {
+                let adts = Adts::from_book(self.book)?;
+                self.adts.get_or_init(|| adts)
+            }
+        };
+        from_term_into_py(self.py, term, adts)
+    }
+
+    /// Converts a Bend `List` into a Python `list` of values of the element
+    /// annotation
+    ///
+    /// # Returns
+    ///
+    /// `Ok(None)` if the term isn't a `List`.
+    fn list_to_py(
+        &self,
+        term: &BTerm,
+        element: &rExpr,
+    ) -> PyResult<Option<PyObject>> {
+        let mut elements: Vec<PyObject> = vec![];
+        let mut cur = term;
+
+        loop {
+            if let BTerm::List { els } = cur {
+                for el in els {
+                    elements.push(self.to_py(
+                        el,
+                        Some(element),
+                        &TupleShape::Value,
+                    )?);
+                }
+                break;
+            }
+
+            let Some((id, fields)) = split_ctr(cur) else {
+                return Ok(None);
+            };
+
+            let is_cons = match id {
+                CtrId::Tag(tag) => tag == builtins::LCONS_TAG as usize,
+                CtrId::Name(nam) => nam.as_ref() == builtins::LCONS,
+            };
+
+            match fields.as_slice() {
+                [] if !is_cons => break,
+                [head, tail] if is_cons => {
+                    elements.push(self.to_py(
+                        head,
+                        Some(element),
+                        &TupleShape::Value,
+                    )?);
+                    cur = tail;
+                }
+                _ => return Ok(None),
+            }
+        }
+
+        Ok(Some(PyList::new_bound(self.py, elements).into_py(self.py)))
+    }
+
+    /// Converts a term into an instance of the dataclass with the given name,
+    /// or of the dataclass of its constructor if the name is a union of
+    /// dataclasses
+    ///
+    /// # Returns
+    ///
+    /// `Ok(None)` if the name isn't a dataclass of the module, or the term
+    /// isn't one of its constructors.
+    fn dataclass_to_py(
+        &self,
+        term: &BTerm,
+        name: &str,
+    ) -> PyResult<Option<PyObject>> {
+        // The dataclasses of a union are only constructors of its ADT
+        let adt = self.book.adts.get(&Name::new(name)).or_else(|| {
+            self.book.adts.iter().find_map(|(adt_name, adt)| {
+                let ctr = Name::new(format!("{}/{}", adt_name, name));
+                adt.ctrs.contains_key(&ctr).then_some(adt)
+            })
+        });
+
+        let Some(adt) = adt else {
+            return Ok(None);
+        };
+
+        let Some((id, fields)) = split_ctr(term) else {
+            return Ok(None);
+        };
+
+        let ctr_name = match id {
+            CtrId::Tag(tag) => adt.ctrs.keys().nth(tag),
+            CtrId::Name(nam) => adt.ctrs.keys().find(|ctr| *ctr == nam),
+        };
+
+        // The constructors of the union `Type = A | B` are `Type/A` and
+        // `Type/B`, and the one of a dataclass has its name.
+        let Some(class_name) = ctr_name.and_then(|ctr| ctr.split('/').last())
+        else {
+            return Ok(None);
+        };
+
+        let Some(annotations) = self.field_annotations(class_name) else {
+            return Ok(None);
+        };
+
+        if annotations.len() != fields.len() {
+            return Ok(None);
+        }
+
+        let Some(class) = self.globals.get_item(class_name)? else {
+            return Ok(None);
+        };
+
+        let mut values: Vec<PyObject> = vec![];
+        for (field, annotation) in fields.iter().zip(annotations) {
+            values.push(self.to_py(
+                field,
+                Some(annotation),
+                &TupleShape::Value,
+            )?);
+        }
+
+        Ok(Some(
+            class.call1(PyTuple::new_bound(self.py, values))?.unbind(),
+        ))
+    }
+
+    /// The annotations of the fields of a dataclass defined in the module
+    fn field_annotations(&self, class_name: &str) -> Option<Vec<&'a rExpr>> {
+        self.statements.iter().find_map(|stmt| match stmt {
+            rStmt::ClassDef(class) if class.name.as_str() == class_name => {
+                Some(
+                    class
+                        .body
+                        .iter()
+                        .filter_map(|stmt| match stmt {
+                            rStmt::AnnAssign(assign) => {
+                                Some(assign.annotation.as_ref())
+                            }
+                            _ => None,
+                        })
+                        .collect(),
+                )
+            }
+            _ => None,
+        })
+    }
+}
+
+/// The name of a type annotation, like `Point` or `module.Point`
+fn type_name(annotation: &rExpr) -> Option<&str> {
+    match annotation {
+        rExpr::Name(nam) => Some(nam.id.as_str()),
+        rExpr::Attribute(att) => Some(att.attr.as_str()),
+        _ => None,
+    }
+}
+
+/// Splits a generic type annotation, like `list[int]` or `typing.Tuple[int,
+/// float]`, into the name of the type and its parameters
+fn subscript(annotation: &rExpr) -> Option<(&str, &rExpr)> {
+    match annotation {
+        rExpr::Subscript(sub) => {
+            Some((type_name(&sub.value)?, sub.slice.as_ref()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod bjit_result_tests {
+    use pyo3::types::PyDict;
+    use pyo3::Python;
+    use rustpython_parser::{parse, Mode};
+
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn annotated_results() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "from dataclasses import dataclass\nfrom typing import Optional\n\n@dataclass\nclass Point:\n    x: int\n    y: float\n\n@dataclass\nclass Size:\n    side: float\n\nShape = Point | Size\n\ndef fun() -> tuple[list[Optional[Point]], Shape, bool]:\n    n: int = -2\n    return [Point(n, 1.5), None], Size(0.5), n > 1\n";
+
+        let rustpython_parser::ast::Mod::Module(module) =
+            parse(code, Mode::Module, "test.py").unwrap()
+        else {
+            unreachable!()
+        };
+        let returns = module.body.iter().find_map(|stmt| match stmt {
+            rStmt::FunctionDef(fun_def) => fun_def.returns.as_deref(),
+            _ => None,
+        });
+
+        let mut parser = Parser::new(module.body.clone(), vec![], code);
+        let term = parser.parse("fun", &[]).unwrap();
+
+        Python::with_gil(|py| {
+            let globals = PyDict::new_bound(py);
+            py.run_bound(code, Some(&globals), None).unwrap();
+
+            let types =
+                ResultTypes::new(py, &module.body, &globals, parser.book());
+            let result = types
+                .to_py(&term, returns, &parser.return_shape("fun"))
+                .unwrap();
+
+            assert_eq!(
+                result.bind(py).repr().unwrap().to_string(),
+                "([Point(x=-2, y=1.5), None], Size(side=0.5), False)"
+            );
+        });
+    }
+
+    #[test]
+    fn unannotated_tuples() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "def fun(a):\n    if a > 5:\n        return a, (a, a, a), a\n    return a, (a + 1, a + 2, a + 3), a + 4\n\ndef run():\n    return fun(1)\n";
+
+        let rustpython_parser::ast::Mod::Module(module) =
+            parse(code, Mode::Module, "test.py").unwrap()
+        else {
+            unreachable!()
+        };
+
+        let mut parser = Parser::new(module.body.clone(), vec![], code);
+        let term = parser.parse("run", &[]).unwrap();
+
+        assert_eq!(parser.return_shape("run"), TupleShape::Value);
+        let shape = parser.return_shape("fun");
+
+        Python::with_gil(|py| {
+            let globals = PyDict::new_bound(py);
+            let types =
+                ResultTypes::new(py, &module.body, &globals, parser.book());
+            let result = types.to_py(&term, None, &shape).unwrap();
+
+            assert_eq!(
+                result.bind(py).repr().unwrap().to_string(),
+                "(1, (2, 3, 4), 5)"
+            );
+        });
+    }
+}
@@ -15,10 +15,11 @@
 //! ## Core Structures
 //!
 //! - `Term`: Represents HVM output in lambda encoding.
-//! - `Ctrs`: Represents Bend ADTs with up to 8 constructors.
+//! - `Ctr` and `Ctrs`: Represent Bend ADT constructors and ADTs with any number of constructors.
 //! - `Definition` and `Definitions`: Handle individual and collections of Bend function definitions.
 //! - `Adts`: Manages collections of Bend ADTs.
 //! - `Book`: The primary structure holding all Bend-related components.
+//! - `RunResult`: The result of a call together with the statistics of the run.
 //!
 //! ## Functionality
 //!
@@ -29,29 +30,28 @@
 //!
 //! This module forms the backbone of the Bend-Python integration, allowing developers to leverage Bend's capabilities within Python projects efficiently.
 
-use core::panic;
 use std::cell::RefCell;
 use std::fmt::Display;
 use std::vec;
 
-use bend::fun::{self, Book as BendBook, Name, Rule};
-use bend::imp::{self, Expr, Stmt};
+use bend::fun::{self, Book as BendBook, CtrField, Name, Rule};
 use indexmap::IndexMap;
-use pyo3::exceptions::PyException;
+use pyo3::exceptions::{PyAttributeError, PyTypeError};
 use pyo3::prelude::*;
-use pyo3::types::{PyString, PyTuple};
+use pyo3::types::{PyDict, PyString, PyTuple, PyType};
 use pyo3::PyTypeInfo;
 
+use super::config::RunConfig;
+use super::extract_expr;
 use super::fan::Fan;
-use super::user_adt::{from_term_into_adt, UserAdt};
-use super::{extract_type_raw, BendType};
+use super::run_result::RunResult;
+use super::user_adt::{
+    from_cons_into_py, from_map_into_py, from_term_into_adt, from_term_into_py,
+};
 use crate::benda_ffi;
+use crate::errors::{ArgumentConversionError, BendError, BendRuntimeError};
 use crate::types::user_adt::BendCtr;
 
-fn new_err<T>(str: String) -> PyResult<T> {
-    Err(PyException::new_err(str))
-}
-
 thread_local!(static GLOBAL_BOOK: RefCell<Option<BendBook>> = const { RefCell::new(None) });
 thread_local!(static GLOBAL_BENDA_BOOK: RefCell<Option<Book>> = const { RefCell::new(None) });
 
@@ -68,6 +68,13 @@ pub struct Term {
     term: fun::Term,
 }
 
+impl Term {
+    /// Creates a new Term from a Bend term
+    pub fn new(term: fun::Term) -> Self {
+        Self { term }
+    }
+}
+
 #[pymethods]
 impl Term {
     /// Returns a string representation of the Term
@@ -115,161 +122,400 @@ impl Term {
             Ok(ctrs) => {
                 let adt = from_term_into_adt(
                     &self.term.clone(),
-                    &ctrs.extract::<Ctrs>().unwrap(),
+                    &ctrs.extract::<Ctrs>()?,
                 );
                 if let Some(adt) = adt {
                     match adt {
                         super::user_adt::TermParse::I32(val) => {
                             return Ok(val.into_py(py))
                         }
-                        super::user_adt::TermParse::Any(any) => {
-                            let list = any.extract::<Ctr2>(py);
-                            return Ok(list.unwrap().into_py(py));
-                        }
+                        super::user_adt::TermParse::Any(any) => return Ok(any),
                         _ => {}
                     }
                 };
-                new_err("Could not parse Term into the given ADT".to_string())
+                Err(ArgumentConversionError::new_err(
+                    "Could not parse Term into the given ADT",
+                ))
             }
-            Err(_) => new_err("Invalid Type given as argument".to_string()),
+            Err(_) => Err(ArgumentConversionError::new_err(
+                "Invalid Type given as argument",
+            )),
         }
     }
-}
 
-macro_rules! generate_structs {
-    ($name:literal, $iden: ident) => {
-        #[pyclass(name = $name)]
-        #[derive(Clone, Debug)]
-        pub(crate) struct $iden {
-            full_name: String,
-            fields: IndexMap<String, Option<Py<PyAny>>>,
+    /// Converts the Term into Python objects, without naming the target ADT
+    ///
+    /// The lambda encoded Term is walked using the ADTs of the loaded Book, so
+    /// any constructor of any ADT can be decoded.
+    ///
+    /// # Returns
+    ///
+    /// A PyResult containing:
+    /// - `int` or `float` for numbers;
+    /// - `str` for Bend `String`s;
+    /// - `list` for Bend `List`s;
+    /// - `tuple` for Bend tuples;
+    /// - `None` for erased values;
+    /// - An ADT instance for any other constructor;
+    /// - A `Term` for anything that can't be converted, like lambdas.
+    ///
+    /// # Examples
+    ///
+    /// ```python
+    /// book = benda.load_book_from_file("./examples/quicksort.bend")
+    /// sorted_list = book.defs.Sort(my_list).to_python()
+    /// ```
+    fn to_python(&self, py: Python) -> PyResult<PyObject> {
+        GLOBAL_BENDA_BOOK.with_borrow(|book| match book {
+            Some(book) => from_term_into_py(py, &self.term, &book.adts),
+            None => from_term_into_py(py, &self.term, &Adts::new()),
+        })
+    }
+
+    /// Converts the Term into a Python list, if it is a Bend `List`
+    ///
+    /// Unlike `to_python`, the Term is always read as a `List`, so an empty list
+    /// or a list of characters isn't mistaken for a `String`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Term isn't a Bend `List`
+    ///
+    /// # Examples
+    ///
+    /// ```python
+    /// sorted_list = book.defs.Sort([3, 2, 1]).to_list()  # [1, 2, 3]
+    /// ```
+    fn to_list(&self, py: Python) -> PyResult<PyObject> {
+        let list = GLOBAL_BENDA_BOOK.with_borrow(|book| match book {
+            Some(book) => from_cons_into_py(
+                py,
+                &self.term,
+                fun::builtins::LIST,
+                &book.adts,
+            ),
+            None => Ok(None),
+        })?;
+
+        match list {
+            Some(list) => Ok(list),
+            None => Err(ArgumentConversionError::new_err("Term is not a List")),
         }
+    }
 
-        impl BendCtr for $iden {
-            fn to_py(&self, py: &Python) -> Py<PyAny> {
-                Py::new(*py, self.clone()).unwrap().as_any().clone()
+    /// Converts the Term into a Python str, if it is a Bend `String`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Term isn't a Bend `String`
+    ///
+    /// # Examples
+    ///
+    /// ```python
+    /// text = book.defs.Greet("World").to_str()  # "Hello, World"
+    /// ```
+    fn to_str(&self, py: Python) -> PyResult<PyObject> {
+        let text = GLOBAL_BENDA_BOOK.with_borrow(|book| match book {
+            Some(book) => from_cons_into_py(
+                py,
+                &self.term,
+                fun::builtins::STRING,
+                &book.adts,
+            ),
+            None => Ok(None),
+        })?;
+
+        match text {
+            Some(text) => Ok(text),
+            None => {
+                Err(ArgumentConversionError::new_err("Term is not a String"))
             }
+        }
+    }
 
-            fn call_constructor(
-                &mut self,
-                args: Bound<PyTuple>,
-            ) -> PyResult<PyObject> {
-                self.__call__(args)
-            }
+    /// Converts the Term into a Python dict, if it is a Bend `Map`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Term isn't a Bend `Map`
+    ///
+    /// # Examples
+    ///
+    /// ```python
+    /// counts = book.defs.Count([1, 2, 1]).to_dict()  # {1: 2, 2: 1}
+    /// ```
+    fn to_dict(&self, py: Python) -> PyResult<PyObject> {
+        let dict = GLOBAL_BENDA_BOOK.with_borrow(|book| match book {
+            Some(book) => from_map_into_py(py, &self.term, &book.adts),
+            None => Ok(None),
+        })?;
+
+        match dict {
+            Some(dict) => Ok(dict),
+            None => Err(ArgumentConversionError::new_err("Term is not a Map")),
+        }
+    }
+}
 
-            fn arity(&self) -> usize {
-                self.fields.len()
-            }
+/// Represents a single Bend ADT constructor, like `List/Cons` or `List/Nil`
+///
+/// Every constructor of a loaded Book gets its own Python subclass of `Ctr`, created at
+/// runtime, so instances of different constructors can be told apart in a `match` statement
+/// through the `type` attribute.
+///
+/// # Fields
+///
+/// * `full_name` - The full name of the constructor, like `List/Cons`;
+/// * `tag` - The index of the constructor inside its ADT, as used by the num-scott encoding;
+/// * `fields` - An IndexMap of field names to their values;
+/// * `recursive` - Whether each field, in order, is recursive (`~field` in Bend);
+/// * `ctr_type` - The Python subclass created for this constructor;
+#[pyclass(name = "Ctr", subclass)]
+#[derive(Clone, Debug)]
+pub struct Ctr {
+    full_name: String,
+    tag: usize,
+    fields: IndexMap<String, Option<Py<PyAny>>>,
+    recursive: Vec<bool>,
+    ctr_type: Option<Py<PyType>>,
+}
+
+impl Ctr {
+    /// Creates a new constructor and its Python subclass
+    ///
+    /// # Arguments
+    ///
+    /// * `py` - The Python GIL token
+    /// * `full_name` - The full name of the constructor, like `List/Cons`
+    /// * `tag` - The index of the constructor inside its ADT
+    /// * `fields` - The fields of the constructor
+    pub fn new(
+        py: Python,
+        full_name: String,
+        tag: usize,
+        fields: &[CtrField],
+    ) -> PyResult<Self> {
+        let mut ctr = Self {
+            full_name,
+            tag,
+            fields: fields.iter().map(|f| (f.nam.to_string(), None)).collect(),
+            recursive: fields.iter().map(|f| f.rec).collect(),
+            ctr_type: None,
+        };
+
+        ctr.ctr_type = Some(ctr.new_type(py)?);
+
+        Ok(ctr)
+    }
+
+    /// Creates the Python subclass of `Ctr` that represents this constructor
+    fn new_type(&self, py: Python) -> PyResult<Py<PyType>> {
+        let name = self.full_name.split('/').last().unwrap();
+
+        let match_args: Vec<String> =
+            (1..=self.fields.len()).map(|i| i.to_string()).collect();
+
+        let dict = PyDict::new_bound(py);
+        dict.set_item("__module__", "benda")?;
+        dict.set_item("__ctr_type__", &self.full_name)?;
+        dict.set_item("__ctr_tag__", self.tag)?;
+        dict.set_item(
+            "__ctr_fields__",
+            PyTuple::new_bound(py, self.fields.keys()),
+        )?;
+        dict.set_item(
+            "__ctr_recursive__",
+            PyTuple::new_bound(py, &self.recursive),
+        )?;
+        dict.set_item("__match_args__", PyTuple::new_bound(py, match_args))?;
+
+        let bases = PyTuple::new_bound(py, [Ctr::type_object_bound(py)]);
+
+        let ctr_type =
+            PyType::type_object_bound(py).call1((name, bases, dict))?;
+
+        Ok(ctr_type.downcast_into::<PyType>()?.unbind())
+    }
+
+    /// Returns the tag of the constructor inside its ADT
+    pub fn tag(&self) -> usize {
+        self.tag
+    }
+
+    /// Returns the full name of the constructor, like `List/Cons`
+    pub fn full_name(&self) -> &str {
+        &self.full_name
+    }
+
+    /// Returns whether the field at `index` is recursive
+    pub fn is_recursive(&self, index: usize) -> bool {
+        self.recursive.get(index).copied().unwrap_or(false)
+    }
+
+    /// Creates a Python instance of this constructor with the given fields
+    pub fn instantiate(
+        &self,
+        py: Python,
+        args: Vec<PyObject>,
+    ) -> PyResult<PyObject> {
+        self.clone().__call__(PyTuple::new_bound(py, args))
+    }
+}
+
+impl BendCtr for Ctr {
+    fn to_py(&self, py: &Python) -> Py<PyAny> {
+        match &self.ctr_type {
+            Some(ctr_type) => ctr_type.bind(*py).call0().unwrap().unbind(),
+            None => Py::new(*py, self.clone()).unwrap().into_any(),
         }
+    }
 
-        #[pymethods]
-        impl $iden {
-            #[classattr]
-            fn __match_args__() -> PyResult<Py<PyAny>> {
-                Python::with_gil(|py| {
-                    Ok(PyTuple::new_bound(
-                        py,
-                        vec!["1", "2", "3", "4", "5", "6", "7", "8", "9", "10"],
-                    )
-                    .into_py(py))
-                })
-            }
+    fn call_constructor(&mut self, args: Bound<PyTuple>) -> PyResult<PyObject> {
+        let py = args.py();
+        let arity = self.arity().min(args.len());
+        let args = args.get_slice(0, arity);
 
-            fn __str__(&self) -> String {
-                let mut out = String::new();
-                out.push_str(format!("<Bend ADT {}>", self.full_name).as_str());
-                out
-            }
+        match &self.ctr_type {
+            Some(ctr_type) => Ok(ctr_type.bind(py).call1(args)?.unbind()),
+            None => self.__call__(args),
+        }
+    }
 
-            #[pyo3(signature = (*args))]
-            pub fn __call__(
-                &mut self,
-                args: Bound<'_, PyTuple>,
-            ) -> PyResult<PyObject> {
-                let py = args.py();
+    fn arity(&self) -> usize {
+        self.fields.len()
+    }
+}
 
-                for (i, field) in self.fields.iter_mut().enumerate() {
-                    field.1.replace(args.get_item(i).unwrap().to_object(py));
-                }
+#[pymethods]
+impl Ctr {
+    #[new]
+    #[classmethod]
+    #[pyo3(signature = (*args))]
+    fn __new__(cls: &Bound<PyType>, args: Bound<PyTuple>) -> PyResult<Self> {
+        let (Ok(full_name), Ok(tag), Ok(fields), Ok(recursive)) = (
+            cls.getattr("__ctr_type__"),
+            cls.getattr("__ctr_tag__"),
+            cls.getattr("__ctr_fields__"),
+            cls.getattr("__ctr_recursive__"),
+        ) else {
+            return Err(PyTypeError::new_err(
+                "Ctr can only be created from a Bend ADT",
+            ));
+        };
 
-                Ok(Py::new(py, self.clone()).unwrap().as_any().clone())
-            }
+        let mut ctr = Self {
+            full_name: full_name.extract()?,
+            tag: tag.extract()?,
+            fields: fields
+                .extract::<Vec<String>>()?
+                .into_iter()
+                .map(|f| (f, None))
+                .collect(),
+            recursive: recursive.extract()?,
+            ctr_type: Some(cls.clone().unbind()),
+        };
 
-            fn __setattr__(
-                &mut self,
-                field: Bound<PyAny>,
-                value: Bound<PyAny>,
-            ) {
-                if let Some(val) = self.fields.get_mut(&field.to_string()) {
-                    val.replace(value.to_object(field.py()));
-                }
-            }
+        ctr.fill_fields(args)?;
 
-            #[getter]
-            fn r#type(&self) -> PyResult<PyObject> {
-                Python::with_gil(|py| {
-                    let ctr_type = $iden::type_object_bound(py).to_object(py);
-                    Ok(ctr_type)
-                })
-            }
+        Ok(ctr)
+    }
 
-            fn __getattr__(&self, object: Bound<PyAny>) -> PyResult<PyObject> {
-                let field = object.to_string();
+    fn __str__(&self) -> String {
+        let mut out = String::new();
+        out.push_str(format!("<Bend ADT {}>", self.full_name).as_str());
+        out
+    }
 
-                let py = object.py();
+    #[pyo3(signature = (*args))]
+    pub fn __call__(&mut self, args: Bound<'_, PyTuple>) -> PyResult<PyObject> {
+        let py = args.py();
 
-                if field == "__variant" {
-                    return Ok(
-                        PyString::new_bound(py, &self.full_name).into_py(py)
-                    );
-                }
+        if let Some(ctr_type) = &self.ctr_type {
+            return Ok(ctr_type.bind(py).call1(args)?.unbind());
+        }
 
-                if field == "__ctr_type__" {
-                    return Ok(
-                        PyString::new_bound(py, &self.full_name).into_py(py)
-                    );
-                }
+        let mut ctr = self.clone();
+        ctr.fill_fields(args)?;
 
-                if let Ok(val) = object.to_string().parse::<usize>() {
-                    let return_val = self.fields.get_index(val - 1);
-                    if let Some(return_val) = return_val {
-                        return Ok(return_val.1.clone().into_py(py));
-                    }
-                }
+        Ok(Py::new(py, ctr)?.into_any())
+    }
 
-                if let Some(val) = self.fields.get(&object.to_string()) {
-                    Ok(val.clone().into_py(object.py()))
-                } else {
-                    new_err(format!("Could not find attr {}", object))
-                }
+    fn __setattr__(&mut self, field: Bound<PyAny>, value: Bound<PyAny>) {
+        if let Some(val) = self.fields.get_mut(&field.to_string()) {
+            val.replace(value.to_object(field.py()));
+        }
+    }
+
+    #[getter]
+    fn r#type(&self, py: Python) -> PyResult<PyObject> {
+        match &self.ctr_type {
+            Some(ctr_type) => Ok(ctr_type.to_object(py)),
+            None => Ok(Ctr::type_object_bound(py).to_object(py)),
+        }
+    }
+
+    fn __getattr__(&self, object: Bound<PyAny>) -> PyResult<PyObject> {
+        let field = object.to_string();
+
+        let py = object.py();
+
+        if field == "__variant" {
+            return Ok(PyString::new_bound(py, &self.full_name).into_py(py));
+        }
+
+        if field == "__ctr_type__" {
+            return Ok(PyString::new_bound(py, &self.full_name).into_py(py));
+        }
+
+        if let Ok(val) = object.to_string().parse::<usize>() {
+            let return_val = self.fields.get_index(val.wrapping_sub(1));
+            if let Some(return_val) = return_val {
+                return Ok(return_val.1.clone().into_py(py));
             }
         }
-    };
+
+        if let Some(val) = self.fields.get(&object.to_string()) {
+            Ok(val.clone().into_py(object.py()))
+        } else {
+            Err(PyAttributeError::new_err(format!(
+                "Could not find attr {}",
+                object
+            )))
+        }
+    }
 }
 
-generate_structs!("Ctr1", Ctr1);
-generate_structs!("Ctr2", Ctr2);
-generate_structs!("Ctr3", Ctr3);
-generate_structs!("Ctr4", Ctr4);
-generate_structs!("Ctr5", Ctr5);
-generate_structs!("Ctr6", Ctr6);
-generate_structs!("Ctr7", Ctr7);
-generate_structs!("Ctr8", Ctr8);
+impl Ctr {
+    /// Sets the constructor fields, in order, from the given arguments
+    fn fill_fields(&mut self, args: Bound<PyTuple>) -> PyResult<()> {
+        if args.len() != self.fields.len() {
+            return Err(PyTypeError::new_err(format!(
+                "Constructor {} has arity {} and received {} arguments",
+                self.full_name,
+                self.fields.len(),
+                args.len(),
+            )));
+        }
+
+        let py = args.py();
+
+        for (i, field) in self.fields.iter_mut().enumerate() {
+            field.1.replace(args.get_item(i)?.to_object(py));
+        }
+
+        Ok(())
+    }
+}
 
 /// Represents a Bend ADT (Algebraic Data Type)
 ///
 /// A Bend ADT is a collection of constructors, like: List, Tree, Map, etc.
-/// This struct holds up to 8 constructors and provides methods to access them.
+/// This struct holds any number of constructors and provides methods to access them.
 ///
 /// # Fields
 ///
 /// * `fields` - An IndexMap of constructor names to their PyAny instance;
-/// * `first` to `eighth` - Optional fields for up to 8 constructors (Ctr1 to Ctr8);
-///
-/// # Note
-///
-/// Due to pyo3 limitations, a Bend ADT used in Benda can have only up to 8 constructors.
-/// This may change in the future if runtime types are added to pyo3.
+/// * `ctrs` - The constructors of the ADT, indexed by their tag;
 ///
 /// # Examples
 ///
@@ -285,17 +531,26 @@ generate_structs!("Ctr8", Ctr8);
 #[derive(Clone, Debug, Default)]
 pub struct Ctrs {
     fields: IndexMap<String, Py<PyAny>>,
-    pub(crate) first: Option<Ctr1>,
-    pub(crate) second: Option<Ctr2>,
-    pub(crate) third: Option<Ctr3>,
-    pub(crate) fourth: Option<Ctr4>,
-    pub(crate) fifth: Option<Ctr5>,
-    pub(crate) sixth: Option<Ctr6>,
-    pub(crate) seventh: Option<Ctr7>,
-    pub(crate) eighth: Option<Ctr8>,
+    pub(crate) ctrs: Vec<Ctr>,
 }
 
 impl Ctrs {
+    /// Retrieves a constructor of the ADT by its tag
+    ///
+    /// In Bend's num-scott encoding, every constructor is identified by its index
+    /// inside the ADT definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The index of the constructor
+    ///
+    /// # Returns
+    ///
+    /// The constructor with the given tag, or `None` if the ADT has no such constructor
+    pub fn get_ctr(&self, tag: usize) -> Option<&Ctr> {
+        self.ctrs.get(tag)
+    }
+
     /// Retrieves the base case constructor of the ADT, if it exists
     ///
     /// In Bend's lambda encoding, when a constructor has no fields (arity of 0),
@@ -307,42 +562,11 @@ impl Ctrs {
     /// An `Option<Box<dyn BendCtr>>`:
     /// - `Some(Box<dyn BendCtr>)` if a base case constructor (arity 0) is found
     /// - `None` if no base case constructor is present in the ADT
-    ///
-    /// # Details
-    ///
-    /// The method checks each constructor (from `first` to `eighth`) in order.
-    /// It returns the first constructor with an arity of 0, wrapped in a `Box<dyn BendCtr>`.
-    ///
-    /// # Note
-    ///
-    /// This method assumes that at least one of the constructors (up to `eighth`) is `Some`.
-    /// It will panic if it encounters a `None` value before finding a base case or reaching the end.
     pub fn get_base_case(&self) -> Option<Box<dyn BendCtr>> {
-        if self.first.as_ref().unwrap().arity() == 0 {
-            return Some(Box::new(self.first.clone().unwrap()));
-        }
-        if self.second.as_ref().unwrap().arity() == 0 {
-            return Some(Box::new(self.second.clone().unwrap()));
-        }
-        if self.third.as_ref().unwrap().arity() == 0 {
-            return Some(Box::new(self.third.clone().unwrap()));
-        }
-        if self.fourth.as_ref().unwrap().arity() == 0 {
-            return Some(Box::new(self.fourth.clone().unwrap()));
-        }
-        if self.fifth.as_ref().unwrap().arity() == 0 {
-            return Some(Box::new(self.fifth.clone().unwrap()));
-        }
-        if self.sixth.as_ref().unwrap().arity() == 0 {
-            return Some(Box::new(self.sixth.clone().unwrap()));
-        }
-        if self.seventh.as_ref().unwrap().arity() == 0 {
-            return Some(Box::new(self.seventh.clone().unwrap()));
-        }
-        if self.eighth.as_ref().unwrap().arity() == 0 {
-            return Some(Box::new(self.eighth.clone().unwrap()));
-        }
-        None
+        self.ctrs
+            .iter()
+            .find(|ctr| ctr.arity() == 0)
+            .map(|ctr| Box::new(ctr.clone()) as Box<dyn BendCtr>)
     }
 }
 
@@ -352,7 +576,10 @@ impl Ctrs {
         if let Some(val) = self.fields.get(&name.to_string()) {
             Ok(val.clone())
         } else {
-            new_err(format!("Could not find attr {}", name))
+            Err(PyAttributeError::new_err(format!(
+                "Could not find attr {}",
+                name
+            )))
         }
     }
 }
@@ -417,12 +644,16 @@ impl Display for BendRuntime {
 /// * `arity` - The number of arguments the function expects
 /// * `name` - The name of the function
 /// * `cmd` - An optional `BendRuntime` specifying the runtime to use for execution
+/// * `stats` - Whether calls return a `RunResult` with the run statistics
+/// * `config` - An optional `RunConfig` with the compilation and runtime options
 #[pyclass(name = "Definition")]
 #[derive(Clone, Debug, Default)]
 pub struct Definition {
     arity: usize,
     name: String,
     cmd: Option<BendRuntime>,
+    stats: bool,
+    config: Option<RunConfig>,
 }
 
 #[pymethods]
@@ -436,6 +667,24 @@ impl Definition {
         format!("Bend function: {}({})", self.name, self.arity)
     }
 
+    /// Sets whether calls to the Definition return the run statistics
+    ///
+    /// # Arguments
+    ///
+    /// * `stats` - If true, calls return a `RunResult` instead of a `Term`
+    fn set_stats(&mut self, stats: bool) {
+        self.stats = stats;
+    }
+
+    /// Sets the compilation and runtime options used to call the Definition
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - A `RunConfig` with the options
+    fn set_config(&mut self, config: RunConfig) {
+        self.config = Some(config);
+    }
+
     /// Calls the Bend function with the given arguments
     ///
     /// This method executes the Bend function, handling argument processing,
@@ -444,11 +693,13 @@ impl Definition {
     /// # Arguments
     ///
     /// * `args` - A tuple of Python arguments passed to the function;
+    /// * `config` - An optional `RunConfig` used only for this call;
     ///
     /// # Returns
     ///
     /// A `Term` containing the result of the function execution, that can be parsed into a ADT
-    /// using `to_adt()` method.
+    /// using `to_adt()` method. If the statistics are enabled with `set_stats`, a `RunResult`
+    /// with the `Term` and the statistics of the run is returned instead.
     ///
     /// # Errors
     ///
@@ -457,26 +708,28 @@ impl Definition {
     /// - The global Bend book is not available
     /// - The HVM output cannot be parsed
     /// - The function execution fails for any reason
-    #[pyo3(signature = (*args))]
-    fn __call__(&mut self, args: Bound<'_, PyTuple>) -> PyResult<Py<PyAny>> {
+    #[pyo3(signature = (*args, config = None))]
+    fn __call__(
+        &mut self,
+        args: Bound<'_, PyTuple>,
+        config: Option<RunConfig>,
+    ) -> PyResult<Py<PyAny>> {
         let py = args.py();
 
-        let bend_book = GLOBAL_BOOK.take();
+        let bend_book = GLOBAL_BOOK.with_borrow(|book| book.clone());
 
         if self.arity != args.len() && self.arity != 0 {
-            return new_err(format!(
+            return Err(PyTypeError::new_err(format!(
                 "Function has arity {} and received {} arguments",
                 self.arity,
                 args.len(),
-            ));
+            )));
         }
 
-        let mut new_args: Vec<Expr> = vec![];
-
-        if let Some(mut b) = bend_book.clone() {
-            for (arg_num, arg) in args.iter().enumerate() {
-                let arg_name = Name::new(format!("arg{}", arg_num));
+        let mut new_args: Vec<fun::Term> = vec![];
 
+        if let Some(mut b) = bend_book {
+            for arg in args.iter() {
                 let mut u_type: Option<fun::Term> = None;
 
                 if let Ok(term) = arg.downcast::<Term>() {
@@ -488,84 +741,68 @@ impl Definition {
                         u_type = Some(new_term.term);
                     }
                 } else {
-                    let adt = UserAdt::new(arg.clone(), &b);
-
-                    let new_arg: Expr;
-
-                    if let Some(adt) = adt {
-                        new_arg = adt.to_bend().unwrap();
-                    } else {
-                        new_arg = extract_type_raw(arg.clone())
-                            .unwrap()
-                            .to_bend()
-                            .unwrap();
-                    }
+                    let new_arg = extract_expr(arg.clone(), &b)?;
 
-                    u_type = Some(new_arg.clone().to_fun());
+                    u_type = Some(new_arg.to_fun());
                 }
 
+                // Arguments are passed inline to the function call instead of
+                // being separate definitions, otherwise the HVM reads back a
+                // reference to the argument when it is returned untouched.
                 if let Some(n_type) = u_type {
-                    let def = fun::Definition {
-                        name: arg_name.clone(),
-                        rules: vec![Rule {
-                            pats: vec![],
-                            body: n_type,
-                        }],
-                        builtin: false,
-                    };
-
-                    b.defs.insert(arg_name.clone(), def);
-
-                    new_args.push(Expr::Var {
-                        nam: arg_name.clone(),
-                    });
+                    new_args.push(n_type);
                 }
             }
 
-            let first = Stmt::Return {
-                term: Box::new(Expr::Call {
-                    fun: Box::new(imp::Expr::Var {
-                        nam: Name::new(self.name.to_string()),
-                    }),
-                    args: new_args,
-                    kwargs: vec![],
-                }),
-            };
-
-            let main_def = imp::Definition {
+            let main_def = fun::Definition {
                 name: Name::new("main"),
-                params: vec![],
-                body: first,
+                rules: vec![Rule {
+                    pats: vec![],
+                    body: fun::Term::call(
+                        fun::Term::Var {
+                            nam: Name::new(self.name.to_string()),
+                        },
+                        new_args,
+                    ),
+                }],
+                builtin: false,
             };
 
-            b.defs
-                .insert(Name::new("main"), main_def.to_fun(true).unwrap());
+            b.defs.insert(Name::new("main"), main_def);
+
+            let config = config.or(self.config.clone()).unwrap_or_default();
 
-            let res = benda_ffi::run(
+            let res = benda_ffi::run_with_opts(
                 &b,
                 &self.cmd.clone().unwrap_or_default().to_string(),
+                config.run_opts(),
+                config.compile_opts(),
+                config.stack_size(),
             );
 
-            GLOBAL_BOOK.set(bend_book);
-
-            let ret_term: Term;
-
-            match res {
+            let (ret_term, stats) = match res {
                 Ok(res) => match res {
-                    Some(res) => ret_term = Term { term: res.0 },
+                    Some(res) => (Term { term: res.0 }, res.1),
                     None => {
-                        return new_err(
-                            "Could not parse HVM output".to_string(),
-                        )
+                        return Err(BendRuntimeError::new_err(
+                            "Could not parse HVM output",
+                        ))
                     }
                 },
-                Err(e) => return new_err(e.to_string()),
+                Err(e) => return Err(e.into()),
+            };
+
+            if self.stats {
+                return Ok(RunResult::new(ret_term, stats).into_py(py));
             }
 
             return Ok(ret_term.into_py(py));
         }
 
-        new_err(format!("Could not execute function {}", self.name))
+        Err(BendError::new_err(format!(
+            "Could not execute function {}",
+            self.name
+        )))
     }
 }
 
@@ -578,11 +815,15 @@ impl Definition {
 ///
 /// * `defs` - An IndexMap of function names to their corresponding `Definition`s;
 /// * `cmd` - An optional `BendRuntime` specifying the preferred runtime for all definitions;
+/// * `stats` - Whether calls to the definitions return the run statistics;
+/// * `config` - An optional `RunConfig` used by all definitions;
 #[pyclass(name = "Definitions")]
 #[derive(Clone, Debug, Default)]
 pub struct Definitions {
     defs: IndexMap<String, Definition>,
     cmd: Option<BendRuntime>,
+    stats: bool,
+    config: Option<RunConfig>,
 }
 
 #[pymethods]
@@ -611,9 +852,14 @@ impl Definitions {
         if let Some(def) = self.defs.get(field) {
             let mut def = def.clone();
             def.cmd = self.cmd.clone();
+            def.stats = self.stats;
+            def.config = self.config.clone();
             Ok(Py::new(py, def)?)
         } else {
-            new_err(format!("Could not find attr {}", object))
+            Err(PyAttributeError::new_err(format!(
+                "Could not find attr {}",
+                object
+            )))
         }
     }
 }
@@ -624,7 +870,7 @@ impl Definitions {
 #[pyclass(name = "Adt")]
 #[derive(Clone, Debug)]
 pub struct Adts {
-    adts: IndexMap<String, Ctrs>,
+    pub(crate) adts: IndexMap<String, Ctrs>,
 }
 
 impl Adts {
@@ -633,11 +879,53 @@ impl Adts {
     /// # Returns
     ///
     /// A new `Adts` instance with an empty `IndexMap`
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             adts: IndexMap::new(),
         }
     }
+
+    /// Creates the ADTs of a Bend Book, with a Python class for each constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `bend_book` - A reference to a Bend Book
+    ///
+    /// # Returns
+    ///
+    /// A new `Adts` instance with the ADTs of the Book, in the order they were defined
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of Python if the class of a constructor can't be
+    /// created.
+    pub(crate) fn from_book(bend_book: &BendBook) -> PyResult<Self> {
+        let mut adts = Adts::new();
+
+        for (adt_name, bend_adt) in bend_book.adts.iter() {
+            let mut all_ctrs = Ctrs::default();
+
+            Python::with_gil(|py| -> PyResult<()> {
+                for (tag, (ctr_name, ctr_fields)) in
+                    bend_adt.ctrs.iter().enumerate()
+                {
+                    let new_name =
+                        ctr_name.split('/').last().unwrap().to_string();
+
+                    let ct =
+                        Ctr::new(py, ctr_name.to_string(), tag, ctr_fields)?;
+
+                    all_ctrs.fields.insert(new_name, ct.clone().into_py(py));
+                    all_ctrs.ctrs.push(ct);
+                }
+                Ok(())
+            })?;
+
+            adts.adts.insert(adt_name.to_string(), all_ctrs);
+        }
+
+        Ok(adts)
+    }
 }
 
 #[pymethods]
@@ -663,7 +951,10 @@ impl Adts {
         if let Some(ctrs) = self.adts.get(field) {
             Ok(ctrs.clone().into_py(py))
         } else {
-            new_err(format!("Could not find attr {}", object))
+            Err(PyAttributeError::new_err(format!(
+                "Could not find attr {}",
+                object
+            )))
         }
     }
 }
@@ -675,9 +966,11 @@ impl Adts {
 #[pyclass(name = "Book")]
 #[derive(Clone, Debug)]
 pub struct Book {
-    adts: Adts,
+    pub(crate) adts: Adts,
     defs: Definitions,
     cmd: Option<BendRuntime>,
+    stats: bool,
+    config: Option<RunConfig>,
 }
 
 impl Book {
@@ -693,149 +986,12 @@ impl Book {
     /// # Returns
     ///
     /// A new `Book` instance
-    pub fn new(bend_book: &mut BendBook) -> Self {
-        let mut adts = Adts::new();
-
-        for (adt_name, bend_adt) in bend_book.adts.iter() {
-            let mut all_ctrs = Ctrs::default();
-
-            let mut first: Option<Ctr1> = None;
-            let mut second: Option<Ctr2> = None;
-            let mut third: Option<Ctr3> = None;
-            let mut fourth: Option<Ctr4> = None;
-            let mut fifth: Option<Ctr5> = None;
-            let mut sixth: Option<Ctr6> = None;
-            let mut seventh: Option<Ctr7> = None;
-            let mut eighth: Option<Ctr8> = None;
-
-            for (index, (ctr_name, ctr_fields)) in
-                bend_adt.ctrs.iter().enumerate()
-            {
-                let new_name = ctr_name.split('/').last().unwrap().to_string();
-
-                Python::with_gil(|py| match index {
-                    0 => {
-                        let mut ct = Ctr1 {
-                            full_name: ctr_name.to_string(),
-                            fields: IndexMap::new(),
-                        };
-                        for c in ctr_fields {
-                            ct.fields.insert(c.nam.to_string(), None);
-                        }
-                        first = Some(ct.clone());
-                        all_ctrs
-                            .fields
-                            .insert(new_name, ct.into_py(py).as_any().clone());
-                    }
-                    1 => {
-                        let mut ct = Ctr2 {
-                            full_name: ctr_name.to_string(),
-                            fields: IndexMap::new(),
-                        };
-                        for c in ctr_fields {
-                            ct.fields.insert(c.nam.to_string(), None);
-                        }
-                        second = Some(ct.clone());
-                        all_ctrs
-                            .fields
-                            .insert(new_name, ct.into_py(py).as_any().clone());
-                    }
-                    2 => {
-                        let mut ct = Ctr3 {
-                            full_name: ctr_name.to_string(),
-                            fields: IndexMap::new(),
-                        };
-                        for c in ctr_fields {
-                            ct.fields.insert(c.nam.to_string(), None);
-                        }
-                        third = Some(ct.clone());
-                        all_ctrs
-                            .fields
-                            .insert(new_name, ct.into_py(py).as_any().clone());
-                    }
-                    3 => {
-                        let mut ct = Ctr4 {
-                            full_name: ctr_name.to_string(),
-                            fields: IndexMap::new(),
-                        };
-                        for c in ctr_fields {
-                            ct.fields.insert(c.nam.to_string(), None);
-                        }
-                        fourth = Some(ct.clone());
-                        all_ctrs
-                            .fields
-                            .insert(new_name, ct.into_py(py).as_any().clone());
-                    }
-
-                    4 => {
-                        let mut ct = Ctr5 {
-                            full_name: ctr_name.to_string(),
-                            fields: IndexMap::new(),
-                        };
-                        for c in ctr_fields {
-                            ct.fields.insert(c.nam.to_string(), None);
-                        }
-                        fifth = Some(ct.clone());
-                        all_ctrs
-                            .fields
-                            .insert(new_name, ct.into_py(py).as_any().clone());
-                    }
-
-                    5 => {
-                        let mut ct = Ctr6 {
-                            full_name: ctr_name.to_string(),
-                            fields: IndexMap::new(),
-                        };
-                        for c in ctr_fields {
-                            ct.fields.insert(c.nam.to_string(), None);
-                        }
-                        sixth = Some(ct.clone());
-                        all_ctrs
-                            .fields
-                            .insert(new_name, ct.into_py(py).as_any().clone());
-                    }
-                    6 => {
-                        let mut ct = Ctr7 {
-                            full_name: ctr_name.to_string(),
-                            fields: IndexMap::new(),
-                        };
-                        for c in ctr_fields {
-                            ct.fields.insert(c.nam.to_string(), None);
-                        }
-                        seventh = Some(ct.clone());
-                        all_ctrs
-                            .fields
-                            .insert(new_name, ct.into_py(py).as_any().clone());
-                    }
-
-                    7 => {
-                        let mut ct = Ctr8 {
-                            full_name: ctr_name.to_string(),
-                            fields: IndexMap::new(),
-                        };
-                        for c in ctr_fields {
-                            ct.fields.insert(c.nam.to_string(), None);
-                        }
-                        eighth = Some(ct.clone());
-                        all_ctrs
-                            .fields
-                            .insert(new_name, ct.into_py(py).as_any().clone());
-                    }
-                    _ => panic!("Type must have up to 5 Ctrs"),
-                });
-            }
-
-            all_ctrs.first = first;
-            all_ctrs.second = second;
-            all_ctrs.third = third;
-            all_ctrs.fourth = fourth;
-            all_ctrs.fifth = fifth;
-            all_ctrs.sixth = sixth;
-            all_ctrs.seventh = seventh;
-            all_ctrs.eighth = eighth;
-
-            adts.adts.insert(adt_name.to_string(), all_ctrs);
-        }
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of Python if the classes of the ADTs can't be created.
+    pub fn new(bend_book: &mut BendBook) -> PyResult<Self> {
+        let adts = Adts::from_book(bend_book)?;
 
         let mut definitions = Definitions::default();
 
@@ -844,6 +1000,8 @@ impl Book {
                 arity: def.arity(),
                 name: def.name.to_string(),
                 cmd: None,
+                stats: false,
+                config: None,
             };
             definitions.defs.insert(nam.to_string(), new_def);
         }
@@ -857,10 +1015,12 @@ impl Book {
             adts,
             defs: definitions,
             cmd: None,
+            stats: false,
+            config: None,
         };
         GLOBAL_BENDA_BOOK.set(Some(benda_book.clone()));
 
-        benda_book
+        Ok(benda_book)
     }
 }
 
@@ -875,6 +1035,24 @@ impl Book {
         self.cmd = Some(cmd);
     }
 
+    /// Sets whether calls to the Book's definitions return the run statistics
+    ///
+    /// # Arguments
+    ///
+    /// * `stats` - If true, calls return a `RunResult` instead of a `Term`
+    fn set_stats(&mut self, stats: bool) {
+        self.stats = stats;
+    }
+
+    /// Sets the compilation and runtime options used by the Book's definitions
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - A `RunConfig` with the options
+    fn set_config(&mut self, config: RunConfig) {
+        self.config = Some(config);
+    }
+
     /// Retrieves the ADTs contained in the Book
     ///
     /// # Returns
@@ -898,6 +1076,8 @@ impl Book {
         Python::with_gil(|py| {
             let mut defs = self.defs.clone();
             defs.cmd = self.cmd.clone();
+            defs.stats = self.stats;
+            defs.config = self.config.clone();
             Ok(defs.into_py(py))
         })
     }
@@ -918,6 +1098,9 @@ impl Book {
     fn __getattr__(&self, attr_name: Bound<PyAny>) -> PyResult<PyObject> {
         let attr_name = attr_name.to_string();
 
-        new_err(format!("Could not find attribute {}", attr_name))
+        Err(PyAttributeError::new_err(format!(
+            "Could not find attribute {}",
+            attr_name
+        )))
     }
 }
@@ -0,0 +1,310 @@
+//! Compilation and runtime options
+//!
+//! This module exposes Bend's `CompileOpts` and `RunOpts` to Python through the
+//! `RunConfig` class, so optimizations can be disabled when debugging a program
+//! and tuned when running it in production.
+
+use bend::{AdtEncoding, CompileOpts, OptLevel, RunOpts};
+use pyo3::exceptions::PyValueError;
+use pyo3::{pyclass, pymethods, PyResult};
+
+use crate::benda_ffi::DEFAULT_STACK_SIZE;
+
+/// Options used to compile and run a Bend book
+///
+/// The defaults enable every optimization, which is how Benda runs books when
+/// no `RunConfig` is given.
+///
+/// # Fields
+///
+/// * `eta` - Enables eta-reduction of the compiled nets
+/// * `prune` - Removes definitions that are never used
+/// * `linearize_matches` - How matches are linearized: `"disabled"`, `"enabled"` or `"alt"`
+/// * `float_combinators` - Moves closed terms to their own definitions
+/// * `merge` - Merges definitions with the same body
+/// * `inline` - Inlines small definitions
+/// * `check_net_size` - Checks that the compiled definitions fit in the HVM
+/// * `adt_encoding` - How constructors are encoded: `"num-scott"` or `"scott"`
+/// * `linear_readback` - Reads back the result without expanding duplications
+/// * `pretty` - Pretty prints the result of the HVM
+/// * `hvm_path` - The path of the `hvm` binary
+/// * `stack_size` - The size in bytes of the stack of the thread that runs the
+///   book, which big terms need to be compiled and read back
+#[pyclass(module = "benda", name = "RunConfig")]
+#[derive(Clone, Debug)]
+pub struct RunConfig {
+    #[pyo3(get, set)]
+    eta: bool,
+    #[pyo3(get, set)]
+    prune: bool,
+    linearize_matches: OptLevel,
+    #[pyo3(get, set)]
+    float_combinators: bool,
+    #[pyo3(get, set)]
+    merge: bool,
+    #[pyo3(get, set)]
+    inline: bool,
+    #[pyo3(get, set)]
+    check_net_size: bool,
+    adt_encoding: AdtEncoding,
+    #[pyo3(get, set)]
+    linear_readback: bool,
+    #[pyo3(get, set)]
+    pretty: bool,
+    #[pyo3(get, set)]
+    hvm_path: String,
+    #[pyo3(get, set)]
+    stack_size: usize,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        let compile_opts = CompileOpts::default().set_all();
+        let run_opts = RunOpts::default();
+
+        Self {
+            eta: compile_opts.eta,
+            prune: compile_opts.prune,
+            linearize_matches: compile_opts.linearize_matches,
+            float_combinators: compile_opts.float_combinators,
+            merge: compile_opts.merge,
+            inline: compile_opts.inline,
+            check_net_size: compile_opts.check_net_size,
+            adt_encoding: compile_opts.adt_encoding,
+            linear_readback: run_opts.linear_readback,
+            pretty: run_opts.pretty,
+            hvm_path: run_opts.hvm_path,
+            stack_size: DEFAULT_STACK_SIZE,
+        }
+    }
+}
+
+impl RunConfig {
+    /// Returns the Bend compilation options of this config
+    pub fn compile_opts(&self) -> CompileOpts {
+        CompileOpts {
+            eta: self.eta,
+            prune: self.prune,
+            linearize_matches: self.linearize_matches,
+            float_combinators: self.float_combinators,
+            merge: self.merge,
+            inline: self.inline,
+            check_net_size: self.check_net_size,
+            adt_encoding: self.adt_encoding,
+        }
+    }
+
+    /// Returns the Bend runtime options of this config
+    pub fn run_opts(&self) -> RunOpts {
+        RunOpts {
+            linear_readback: self.linear_readback,
+            pretty: self.pretty,
+            hvm_path: self.hvm_path.clone(),
+        }
+    }
+
+    /// Returns the size of the stack of the thread that runs the book
+    pub fn stack_size(&self) -> usize {
+        self.stack_size
+    }
+}
+
+#[pymethods]
+impl RunConfig {
+    /// Creates a RunConfig
+    ///
+    /// Every option is a keyword argument. When `optimize` is false, all the
+    /// optimizations start disabled and can be enabled one by one.
+    ///
+    /// # Examples
+    ///
+    /// ```python
+    /// config = benda.RunConfig(optimize=False, float_combinators=True)
+    /// config.inline = True
+    /// ```
+    #[new]
+    #[pyo3(signature = (
+        optimize = true,
+        *,
+        eta = None,
+        prune = None,
+        linearize_matches = None,
+        float_combinators = None,
+        merge = None,
+        inline = None,
+        check_net_size = None,
+        adt_encoding = None,
+        linear_readback = None,
+        pretty = None,
+        hvm_path = None,
+        stack_size = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        optimize: bool,
+        eta: Option<bool>,
+        prune: Option<bool>,
+        linearize_matches: Option<&str>,
+        float_combinators: Option<bool>,
+        merge: Option<bool>,
+        inline: Option<bool>,
+        check_net_size: Option<bool>,
+        adt_encoding: Option<&str>,
+        linear_readback: Option<bool>,
+        pretty: Option<bool>,
+        hvm_path: Option<String>,
+        stack_size: Option<usize>,
+    ) -> PyResult<Self> {
+        let mut config = RunConfig::default();
+
+        if !optimize {
+            let compile_opts = config.compile_opts().set_no_all();
+            config.eta = compile_opts.eta;
+            config.prune = compile_opts.prune;
+            config.linearize_matches = compile_opts.linearize_matches;
+            config.float_combinators = compile_opts.float_combinators;
+            config.merge = compile_opts.merge;
+            config.inline = compile_opts.inline;
+        }
+
+        config.eta = eta.unwrap_or(config.eta);
+        config.prune = prune.unwrap_or(config.prune);
+        config.float_combinators =
+            float_combinators.unwrap_or(config.float_combinators);
+        config.merge = merge.unwrap_or(config.merge);
+        config.inline = inline.unwrap_or(config.inline);
+        config.check_net_size = check_net_size.unwrap_or(config.check_net_size);
+        config.linear_readback =
+            linear_readback.unwrap_or(config.linear_readback);
+        config.pretty = pretty.unwrap_or(config.pretty);
+        config.hvm_path = hvm_path.unwrap_or(config.hvm_path);
+        config.stack_size = stack_size.unwrap_or(config.stack_size);
+
+        if let Some(level) = linearize_matches {
+            config.set_linearize_matches(level)?;
+        }
+
+        if let Some(encoding) = adt_encoding {
+            config.set_adt_encoding(encoding)?;
+        }
+
+        Ok(config)
+    }
+
+    #[getter]
+    fn get_linearize_matches(&self) -> &'static str {
+        match self.linearize_matches {
+            OptLevel::Disabled => "disabled",
+            OptLevel::Enabled => "enabled",
+            OptLevel::Alt => "alt",
+        }
+    }
+
+    #[setter]
+    fn set_linearize_matches(&mut self, level: &str) -> PyResult<()> {
+        self.linearize_matches = match level {
+            "disabled" => OptLevel::Disabled,
+            "enabled" => OptLevel::Enabled,
+            "alt" => OptLevel::Alt,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Invalid linearize_matches level {}, expected \"disabled\", \"enabled\" or \"alt\"",
+                    level
+                )))
+            }
+        };
+        Ok(())
+    }
+
+    #[getter]
+    fn get_adt_encoding(&self) -> &'static str {
+        match self.adt_encoding {
+            AdtEncoding::NumScott => "num-scott",
+            AdtEncoding::Scott => "scott",
+        }
+    }
+
+    #[setter]
+    fn set_adt_encoding(&mut self, encoding: &str) -> PyResult<()> {
+        self.adt_encoding = match encoding {
+            "num-scott" => AdtEncoding::NumScott,
+            "scott" => AdtEncoding::Scott,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                "Invalid adt_encoding {}, expected \"num-scott\" or \"scott\"",
+                encoding
+            )))
+            }
+        };
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "RunConfig(eta={}, prune={}, linearize_matches=\"{}\", float_combinators={}, merge={}, inline={}, check_net_size={}, adt_encoding=\"{}\", linear_readback={}, pretty={}, hvm_path=\"{}\", stack_size={})",
+            py_bool(self.eta),
+            py_bool(self.prune),
+            self.get_linearize_matches(),
+            py_bool(self.float_combinators),
+            py_bool(self.merge),
+            py_bool(self.inline),
+            py_bool(self.check_net_size),
+            self.get_adt_encoding(),
+            py_bool(self.linear_readback),
+            py_bool(self.pretty),
+            self.hvm_path,
+            self.stack_size,
+        )
+    }
+}
+
+/// Writes a boolean like Python does
+fn py_bool(value: bool) -> &'static str {
+    if value {
+        "True"
+    } else {
+        "False"
+    }
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+
+    #[test]
+    fn optimize() {
+        let default = RunConfig::default().compile_opts();
+        assert!(
+            default.eta && default.prune && default.merge && default.inline
+        );
+
+        let config = RunConfig::new(
+            false,
+            None,
+            None,
+            None,
+            Some(true),
+            None,
+            None,
+            None,
+            Some("scott"),
+            None,
+            None,
+            None,
+            Some(1 << 20),
+        )
+        .unwrap();
+        let opts = config.compile_opts();
+
+        assert!(!opts.eta && !opts.prune && !opts.merge && !opts.inline);
+        assert!(!opts.linearize_matches.enabled());
+        assert!(opts.float_combinators);
+        assert!(matches!(opts.adt_encoding, AdtEncoding::Scott));
+        assert_eq!(config.stack_size(), 1 << 20);
+
+        assert_eq!(
+            config.__repr__(),
+            "RunConfig(eta=False, prune=False, linearize_matches=\"disabled\", float_combinators=True, merge=False, inline=False, check_net_size=False, adt_encoding=\"scott\", linear_readback=False, pretty=False, hvm_path=\"hvm\", stack_size=1048576)"
+        );
+    }
+}
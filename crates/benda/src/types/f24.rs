@@ -1,11 +1,16 @@
+use std::cmp::Ordering;
 use std::ops::{Add, Sub};
 
 use bend::imp;
-use pyo3::{pyclass, pymethods};
+use pyo3::basic::CompareOp;
+use pyo3::types::{PyAnyMethods, PyFloat};
+use pyo3::{pyclass, pymethods, PyObject, PyResult, Python};
 
 use super::{BendResult, BendType};
 
-#[pyclass(module = "benda")]
+/// A 24-bit float, an `f32` with the 8 lowest bits of the mantissa rounded off
+/// like the HVM does
+#[pyclass(module = "benda", name = "f24")]
 #[derive(Clone, Copy, PartialEq, PartialOrd)]
 pub struct F24(f32);
 
@@ -18,14 +23,28 @@ impl BendType for F24 {
 }
 
 impl F24 {
-    // TODO: Implement a masking for float numbers.
+    /// Rounds the value to the precision of an f24
     pub fn new(value: f32) -> Self {
-        Self(value)
+        Self(Self::round(value))
     }
 
     pub fn get(self) -> f32 {
         self.0
     }
+
+    /// Drops the 8 lowest bits of the value, rounding ties to even and keeping
+    /// NaNs as NaNs, the same way the HVM stores its f24 numbers
+    fn round(value: f32) -> f32 {
+        let bits = value.to_bits();
+        let mut shifted_bits = bits >> 8;
+        let lost_bits = bits & 0xFF;
+
+        shifted_bits += u32::from(!value.is_nan())
+            & ((lost_bits - ((lost_bits >> 7) & !shifted_bits)) >> 7);
+        shifted_bits |= u32::from(value.is_nan());
+
+        f32::from_bits(shifted_bits << 8)
+    }
 }
 
 impl std::fmt::Debug for F24 {
@@ -68,11 +87,9 @@ impl std::ops::Sub for F24 {
     }
 }
 
-// TODO: Check for overflow on the operations
-// TODO: Implement tests for each operation comparing to Bend
-
 #[pymethods]
 impl F24 {
+    /// Creates an f24, rounding the value to its precision
     #[new]
     fn new_py(value: f32) -> Self {
         F24::new(value)
@@ -86,7 +103,175 @@ impl F24 {
         F24::sub(*self, *other)
     }
 
+    fn __mul__(&self, other: &Self) -> Self {
+        F24::new(self.0 * other.0)
+    }
+
+    /// Float division, returning infinity or NaN when dividing by zero like the HVM
+    fn __truediv__(&self, other: &Self) -> Self {
+        F24::new(self.0 / other.0)
+    }
+
+    /// Remainder of the division, with the sign of the dividend like the HVM
+    fn __mod__(&self, other: &Self) -> Self {
+        F24::new(self.0 % other.0)
+    }
+
+    fn __pow__(&self, other: &Self, _modulo: Option<Self>) -> Self {
+        F24::new(self.0.powf(other.0))
+    }
+
+    fn __neg__(&self) -> Self {
+        F24::new(-self.0)
+    }
+
+    fn __pos__(&self) -> Self {
+        *self
+    }
+
+    fn __abs__(&self) -> Self {
+        F24::new(self.0.abs())
+    }
+
     fn __str__(&self) -> String {
         self.0.to_string()
     }
+
+    fn __repr__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __float__(&self) -> f64 {
+        self.0 as f64
+    }
+
+    /// Truncates the value, raising the same errors as `int(float)`
+    fn __int__(&self, py: Python) -> PyResult<PyObject> {
+        Ok(PyFloat::new_bound(py, self.0 as f64)
+            .call_method0("__int__")?
+            .unbind())
+    }
+
+    fn __bool__(&self) -> bool {
+        self.0 != 0.0
+    }
+
+    /// Hashes like the equivalent Python float
+    fn __hash__(&self, py: Python) -> PyResult<isize> {
+        PyFloat::new_bound(py, self.0 as f64).hash()
+    }
+
+    /// Compares two f24s like the HVM, where `<=` and `>=` are the negation of
+    /// `>` and `<`, so they are true when one of the values is NaN
+    fn __richcmp__(&self, other: &Self, op: CompareOp) -> PyResult<bool> {
+        match op {
+            pyclass::CompareOp::Lt => Ok(self < other),
+            pyclass::CompareOp::Le => {
+                Ok(self.partial_cmp(other) != Some(Ordering::Greater))
+            }
+            pyclass::CompareOp::Eq => Ok(self == other),
+            pyclass::CompareOp::Ne => Ok(self != other),
+            pyclass::CompareOp::Gt => Ok(self > other),
+            pyclass::CompareOp::Ge => {
+                Ok(self.partial_cmp(other) != Some(Ordering::Less))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod f24_tests {
+    use bend::fun::{Num, Op};
+
+    use super::*;
+    use crate::types::num_tests::{assert_parity, Rng};
+
+    const OPS: [Op; 12] = [
+        Op::ADD,
+        Op::SUB,
+        Op::MUL,
+        Op::DIV,
+        Op::REM,
+        Op::EQ,
+        Op::NEQ,
+        Op::LT,
+        Op::GT,
+        Op::LE,
+        Op::GE,
+        Op::POW,
+    ];
+
+    fn benda_oper(opr: Op, fst: Num, snd: Num) -> Num {
+        let (Num::F24(a), Num::F24(b)) = (fst, snd) else {
+            unreachable!()
+        };
+        let (a, b) = (F24::new(a), F24::new(b));
+
+        let cmp = |op| Num::U24(a.__richcmp__(&b, op).unwrap() as u32);
+
+        match opr {
+            Op::ADD => Num::F24(a.__add__(&b).get()),
+            Op::SUB => Num::F24(a.__sub__(&b).get()),
+            Op::MUL => Num::F24(a.__mul__(&b).get()),
+            Op::DIV => Num::F24(a.__truediv__(&b).get()),
+            Op::REM => Num::F24(a.__mod__(&b).get()),
+            Op::EQ => cmp(CompareOp::Eq),
+            Op::NEQ => cmp(CompareOp::Ne),
+            Op::LT => cmp(CompareOp::Lt),
+            Op::GT => cmp(CompareOp::Gt),
+            Op::LE => cmp(CompareOp::Le),
+            Op::GE => cmp(CompareOp::Ge),
+            Op::POW => Num::F24(a.__pow__(&b, None).get()),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn rounding() {
+        // 1 + 2^-16 is lost, 1 + 2^-15 is the next f24 after 1
+        assert_eq!(F24::new(1.0 + f32::powi(2.0, -16)).get(), 1.0);
+        assert_eq!(
+            F24::new(1.0 + f32::powi(2.0, -15)).get(),
+            1.0 + f32::powi(2.0, -15)
+        );
+        assert!(F24::new(f32::NAN).get().is_nan());
+        assert_eq!(F24::new(f32::INFINITY).get(), f32::INFINITY);
+    }
+
+    #[test]
+    fn parity() {
+        let mut rng = Rng::new(0x5eed_f024);
+
+        // Division by zero, overflow to infinity and NaNs are compared too
+        let mut values: Vec<f32> = vec![
+            0.0,
+            -0.0,
+            1.0,
+            -1.0,
+            0.5,
+            3.0,
+            -2.5,
+            1e-30,
+            1e30,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::NAN,
+        ];
+        values.extend((0..4).map(|_| f32::from_bits(rng.next_u32())));
+        values.extend((0..4).map(|_| rng.next_u32() as f32 / 1e6));
+        let values: Vec<f32> =
+            values.into_iter().map(|v| F24::new(v).get()).collect();
+
+        let mut opers: Vec<(Op, Num, Num)> = vec![];
+
+        for opr in OPS {
+            for a in &values {
+                for b in &values {
+                    opers.push((opr, Num::F24(*a), Num::F24(*b)));
+                }
+            }
+        }
+
+        assert_parity(&opers, benda_oper);
+    }
 }
@@ -1,7 +1,8 @@
 use bend::fun::Term;
-use pyo3::types::{PyTuple, PyTupleMethods};
-use pyo3::{pyclass, pymethods, Bound};
+use pyo3::types::{PyAnyMethods, PyTuple, PyTupleMethods, PyTypeMethods};
+use pyo3::{pyclass, pymethods, Bound, PyResult};
 
+use crate::errors::ArgumentConversionError;
 use crate::types::extract_type_raw;
 
 #[pyclass(name = "Fan")]
@@ -14,12 +15,16 @@ pub struct Fan {
 impl Fan {
     #[new]
     #[pyo3(signature = (*args))]
-    fn new(args: Bound<'_, PyTuple>) -> Self {
+    fn new(args: Bound<'_, PyTuple>) -> PyResult<Self> {
         let mut elements: Vec<Term> = vec![];
         for arg in args.iter() {
-            let new_arg =
-                extract_type_raw(arg.clone()).unwrap().to_bend().unwrap();
-            let u_type = Some(new_arg.clone().to_fun());
+            let Some(new_arg) = extract_type_raw(arg.clone())? else {
+                return Err(ArgumentConversionError::new_err(format!(
+                    "Could not convert Fan element of type {}",
+                    arg.get_type().name()?
+                )));
+            };
+            let u_type = Some(new_arg.to_bend()?.to_fun());
 
             if let Some(u_type) = u_type {
                 elements.push(u_type);
@@ -32,6 +37,6 @@ impl Fan {
             els: elements,
         };
 
-        Self { term: fan }
+        Ok(Self { term: fan })
     }
 }
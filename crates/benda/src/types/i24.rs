@@ -1,11 +1,15 @@
 use std::ops::{Add, Sub};
 
 use bend::imp;
-use pyo3::{pyclass, pymethods};
+use pyo3::basic::CompareOp;
+use pyo3::exceptions::PyZeroDivisionError;
+use pyo3::{pyclass, pymethods, PyResult};
 
+use super::f24::F24;
 use super::{BendResult, BendType};
 
-#[pyclass(module = "benda")]
+/// A signed 24-bit integer with the same wrapping behaviour as the HVM
+#[pyclass(module = "benda", name = "i24")]
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct I24(i32);
 
@@ -18,11 +22,12 @@ impl BendType for I24 {
 }
 
 impl I24 {
-    const MAX: i32 = 0xffffff;
+    pub const MAX: i32 = 0x7fffff;
+    pub const MIN: i32 = -0x800000;
 
-    // TODO: Check if the masking is working properly
+    /// Wraps the value into 24 bits, keeping its two's complement sign
     pub fn new(value: i32) -> Self {
-        Self(value & Self::MAX)
+        Self((value << 8) >> 8)
     }
 
     pub fn get(self) -> i32 {
@@ -30,6 +35,13 @@ impl I24 {
     }
 }
 
+/// Whether the remainder `rem` of a division by `div` has a different sign
+/// than `div`, in which case the quotient that the HVM rounds towards zero
+/// must be rounded down
+fn rounds(rem: i32, div: i32) -> bool {
+    rem != 0 && (rem < 0) != (div < 0)
+}
+
 impl std::fmt::Debug for I24 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -58,7 +70,7 @@ impl std::ops::Add for I24 {
     type Output = Self;
 
     fn add(self, other: Self) -> Self {
-        I24::new(self.0 + other.0)
+        I24::new(self.0.wrapping_add(other.0))
     }
 }
 
@@ -66,18 +78,16 @@ impl std::ops::Sub for I24 {
     type Output = Self;
 
     fn sub(self, other: Self) -> Self {
-        I24::new(self.0 - other.0)
+        I24::new(self.0.wrapping_sub(other.0))
     }
 }
 
-// TODO: Check for overflow on the operations
-// TODO: Implement tests for each operation comparing to Bend
-
 #[pymethods]
 impl I24 {
+    /// Creates an i24, wrapping values outside of its range like the HVM does
     #[new]
-    fn new_py(value: i32) -> Self {
-        I24::new(value)
+    fn new_py(value: i64) -> Self {
+        I24::new(value as i32)
     }
 
     fn __add__(&self, other: &Self) -> Self {
@@ -88,7 +98,392 @@ impl I24 {
         I24::sub(*self, *other)
     }
 
+    fn __mul__(&self, other: &Self) -> Self {
+        I24::new(self.0.wrapping_mul(other.0))
+    }
+
+    /// True division, which results in an f24 like `/` results in a `float`
+    fn __truediv__(&self, other: &Self) -> PyResult<F24> {
+        match other.0 {
+            0 => Err(PyZeroDivisionError::new_err("division by zero")),
+            _ => Ok(F24::new(self.0 as f32 / other.0 as f32)),
+        }
+    }
+
+    /// Integer division, rounding down like Python's `//` on `int`s
+    fn __floordiv__(&self, other: &Self) -> PyResult<Self> {
+        let (Some(div), Some(rem)) =
+            (self.0.checked_div(other.0), self.0.checked_rem(other.0))
+        else {
+            return Err(PyZeroDivisionError::new_err("division by zero"));
+        };
+
+        match rounds(rem, other.0) {
+            true => Ok(I24::new(div - 1)),
+            false => Ok(I24::new(div)),
+        }
+    }
+
+    /// Remainder of the division, with the sign of the divisor like Python's
+    /// `%` on `int`s
+    fn __mod__(&self, other: &Self) -> PyResult<Self> {
+        let Some(rem) = self.0.checked_rem(other.0) else {
+            return Err(PyZeroDivisionError::new_err("division by zero"));
+        };
+
+        match rounds(rem, other.0) {
+            true => Ok(I24::new(rem + other.0)),
+            false => Ok(I24::new(rem)),
+        }
+    }
+
+    fn __and__(&self, other: &Self) -> Self {
+        I24::new(self.0 & other.0)
+    }
+
+    fn __or__(&self, other: &Self) -> Self {
+        I24::new(self.0 | other.0)
+    }
+
+    fn __xor__(&self, other: &Self) -> Self {
+        I24::new(self.0 ^ other.0)
+    }
+
+    /// Shifts left by the 5 lowest bits of `other`, like the HVM shifts u24s,
+    /// wrapping into 24 bits
+    fn __lshift__(&self, other: &Self) -> Self {
+        I24::new(self.0 << (other.0 & 31))
+    }
+
+    /// Shifts right by the 5 lowest bits of `other`, like the HVM shifts
+    /// u24s, keeping the sign
+    fn __rshift__(&self, other: &Self) -> Self {
+        Self(self.0 >> (other.0 & 31))
+    }
+
+    fn __invert__(&self) -> Self {
+        I24::new(!self.0)
+    }
+
+    fn __neg__(&self) -> Self {
+        I24::new(self.0.wrapping_neg())
+    }
+
+    fn __pos__(&self) -> Self {
+        *self
+    }
+
+    fn __abs__(&self) -> Self {
+        I24::new(self.0.wrapping_abs())
+    }
+
     fn __str__(&self) -> String {
         self.0.to_string()
     }
+
+    fn __repr__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __int__(&self) -> i32 {
+        self.0
+    }
+
+    fn __index__(&self) -> i32 {
+        self.0
+    }
+
+    fn __float__(&self) -> f64 {
+        self.0 as f64
+    }
+
+    fn __bool__(&self) -> bool {
+        self.0 != 0
+    }
+
+    fn __hash__(&self) -> i64 {
+        self.0 as i64
+    }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp) -> PyResult<bool> {
+        match op {
+            pyclass::CompareOp::Lt => Ok(self < other),
+            pyclass::CompareOp::Le => Ok(self <= other),
+            pyclass::CompareOp::Eq => Ok(self == other),
+            pyclass::CompareOp::Ne => Ok(self != other),
+            pyclass::CompareOp::Gt => Ok(self > other),
+            pyclass::CompareOp::Ge => Ok(self >= other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod i24_tests {
+    use bend::fun::{Definition, FanKind, Name, Num, Op, Rule, Tag, Term};
+    use pyo3::types::PyAnyMethods;
+    use pyo3::{IntoPy, Python};
+
+    use super::*;
+    use crate::benda_ffi;
+    use crate::errors::ArgumentConversionError;
+    use crate::parser::prelude;
+    use crate::types::book::BendRuntime;
+    use crate::types::num_tests::{assert_parity, run_opers, Rng};
+    use crate::types::{extract_num_raw, BuiltinType};
+
+    /// The operations that behave the same in the HVM and in the i24 class.
+    /// The HVM rounds the division towards zero, while the class rounds it
+    /// down like Python, which `floor_division` checks.
+    const OPS: [Op; 12] = [
+        Op::ADD,
+        Op::SUB,
+        Op::MUL,
+        Op::EQ,
+        Op::NEQ,
+        Op::LT,
+        Op::GT,
+        Op::LE,
+        Op::GE,
+        Op::AND,
+        Op::OR,
+        Op::XOR,
+    ];
+
+    fn benda_oper(opr: Op, fst: Num, snd: Num) -> Num {
+        let (Num::I24(a), Num::I24(b)) = (fst, snd) else {
+            unreachable!()
+        };
+        let (a, b) = (I24::new(a), I24::new(b));
+
+        let cmp = |op| Num::U24(a.__richcmp__(&b, op).unwrap() as u32);
+
+        match opr {
+            Op::ADD => Num::I24(a.__add__(&b).get()),
+            Op::SUB => Num::I24(a.__sub__(&b).get()),
+            Op::MUL => Num::I24(a.__mul__(&b).get()),
+            Op::EQ => cmp(CompareOp::Eq),
+            Op::NEQ => cmp(CompareOp::Ne),
+            Op::LT => cmp(CompareOp::Lt),
+            Op::GT => cmp(CompareOp::Gt),
+            Op::LE => cmp(CompareOp::Le),
+            Op::GE => cmp(CompareOp::Ge),
+            Op::AND => Num::I24(a.__and__(&b).get()),
+            Op::OR => Num::I24(a.__or__(&b).get()),
+            Op::XOR => Num::I24(a.__xor__(&b).get()),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn wrapping() {
+        assert_eq!(I24::new(I24::MAX + 1).get(), I24::MIN);
+        assert_eq!(I24::new(I24::MIN - 1).get(), I24::MAX);
+        assert_eq!(I24::new_py(1 << 24).get(), 0);
+        assert_eq!(I24::new(I24::MIN).__neg__().get(), I24::MIN);
+    }
+
+    #[test]
+    fn conversion_range() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            for (val, fits) in
+                [(I24::MAX, true), (I24::MIN, true), (1 << 23, false)]
+            {
+                let arg = val.into_py(py).into_bound(py);
+                let num = extract_num_raw(arg, BuiltinType::I32);
+                assert_eq!(num.is_ok(), fits, "{}", val);
+            }
+
+            let arg = (-0x800001_i32).into_py(py).into_bound(py);
+            let err = extract_num_raw(arg, BuiltinType::U24).err().unwrap();
+            assert!(err.is_instance_of::<ArgumentConversionError>(py));
+        });
+    }
+
+    #[test]
+    fn parity() {
+        let mut rng = Rng::new(0x5eed_1024);
+
+        let mut values: Vec<i32> =
+            vec![0, 1, -1, 2, -2, 100, -100, I24::MAX, I24::MIN];
+        values.extend((0..8).map(|_| I24::new(rng.next_u32() as i32).get()));
+
+        let mut opers: Vec<(Op, Num, Num)> = vec![];
+
+        for opr in OPS {
+            for a in &values {
+                for b in &values {
+                    opers.push((opr, Num::I24(*a), Num::I24(*b)));
+                }
+            }
+        }
+
+        assert_parity(&opers, benda_oper);
+    }
+
+    #[test]
+    fn floor_division() {
+        pyo3::prepare_freethreaded_python();
+
+        let values = [7, -7, 2, -2, 6, -6, 1, -1, 0, I24::MAX, I24::MIN];
+
+        Python::with_gil(|py| {
+            for a in values {
+                for b in values.into_iter().filter(|b| *b != 0) {
+                    let expr = format!("(({a}) // ({b}), ({a}) % ({b}))");
+                    let (div, rem): (i64, i64) = py
+                        .eval_bound(&expr, None, None)
+                        .unwrap()
+                        .extract()
+                        .unwrap();
+
+                    let (a, b) = (I24::new(a), I24::new(b));
+                    let case = format!("{} // {}", a, b);
+
+                    assert_eq!(
+                        a.__floordiv__(&b).unwrap(),
+                        I24::new(div as i32),
+                        "{}",
+                        case
+                    );
+                    assert_eq!(
+                        a.__mod__(&b).unwrap(),
+                        I24::new(rem as i32),
+                        "{}",
+                        case
+                    );
+                }
+            }
+        });
+    }
+
+    /// `bjit` translates `//` and `%` of i24s to definitions of its prelude,
+    /// which must round like the i24 class
+    #[test]
+    fn bjit_floor_division() {
+        let values = [7, -7, 2, -2, 6, -6, 1, -1, 0, I24::MAX, I24::MIN];
+
+        let mut cases: Vec<(&str, I24, I24)> = vec![];
+        for fun in ["floordiv", "mod"] {
+            for a in values {
+                for b in values.into_iter().filter(|b| *b != 0) {
+                    cases.push((fun, I24::new(a), I24::new(b)));
+                }
+            }
+        }
+
+        let mut body = Term::Era;
+        for (fun, a, b) in cases.iter().rev() {
+            let call = Term::call(
+                Term::r#ref(&format!("benda/{}/i24", fun)),
+                [a, b].map(|n| Term::Num {
+                    val: Num::I24(n.get()),
+                }),
+            );
+
+            body = Term::Fan {
+                fan: FanKind::Tup,
+                tag: Tag::Static,
+                els: vec![call, body],
+            };
+        }
+
+        let mut book = prelude();
+        book.defs.insert(
+            Name::new("main"),
+            Definition {
+                name: Name::new("main"),
+                rules: vec![Rule { pats: vec![], body }],
+                builtin: false,
+            },
+        );
+
+        let res = benda_ffi::run(&book, BendRuntime::Rust.to_string().as_str());
+        let (mut term, _, _) =
+            res.unwrap().expect("Could not get result from HVM");
+
+        let mut results: Vec<Term> = vec![];
+        while let Term::Fan { els, .. } = &mut term {
+            let rest = els.pop().unwrap();
+            results.append(els);
+            term = rest;
+        }
+        assert_eq!(results.len(), cases.len());
+
+        for ((fun, a, b), res) in cases.into_iter().zip(results) {
+            let Term::Num { val: Num::I24(hvm) } = res else {
+                panic!("The HVM returned {} for benda/{}/i24", res, fun)
+            };
+
+            let benda = match fun {
+                "floordiv" => a.__floordiv__(&b),
+                _ => a.__mod__(&b),
+            };
+
+            assert_eq!(
+                benda.unwrap(),
+                I24::new(hvm),
+                "(benda/{}/i24 {} {})",
+                fun,
+                a,
+                b
+            );
+        }
+    }
+
+    /// The HVM only shifts u24s, so the i24 shifts are checked against the
+    /// u24 shifts of their bits. The right shift of a negative number is the
+    /// inverse of the right shift of its inverse.
+    #[test]
+    fn shifts() {
+        assert!(run_opers(&[(Op::SHL, Num::I24(1), Num::I24(3))]).is_none());
+
+        let values = [0, 1, -1, 5, -16, 23, 24, 31, -24, I24::MAX, I24::MIN];
+        let bits = |val: i32| Num::U24(val as u32 & 0xffffff);
+
+        let mut cases: Vec<(Op, I24, I24, bool)> = vec![];
+        let mut opers: Vec<(Op, Num, Num)> = vec![];
+
+        for opr in [Op::SHL, Op::SHR] {
+            for a in values {
+                for b in values {
+                    let inverted = opr == Op::SHR && a < 0;
+                    let shifted = if inverted { !a } else { a };
+
+                    cases.push((opr, I24::new(a), I24::new(b), inverted));
+                    opers.push((opr, bits(shifted), bits(b)));
+                }
+            }
+        }
+
+        let results = run_opers(&opers).expect("Could not get result from HVM");
+
+        for ((opr, a, b, inverted), res) in cases.into_iter().zip(results) {
+            let Num::U24(res) = res else {
+                panic!("The HVM returned {:?} for a u24 shift", res)
+            };
+            let hvm = I24::new(if inverted { !res } else { res } as i32);
+
+            let benda = match opr {
+                Op::SHL => a.__lshift__(&b),
+                _ => a.__rshift__(&b),
+            };
+
+            assert_eq!(benda, hvm, "({:?} {:?} {:?})", opr, a, b);
+        }
+    }
+
+    #[test]
+    fn division_by_zero() {
+        let (a, zero) = (I24::new(-7), I24::new(0));
+
+        assert!(a.__truediv__(&zero).is_err());
+        assert!(a.__floordiv__(&zero).is_err());
+        assert!(a.__mod__(&zero).is_err());
+
+        for opr in [Op::DIV, Op::REM] {
+            assert!(run_opers(&[(opr, Num::I24(-7), Num::I24(0))]).is_none());
+        }
+    }
 }
@@ -0,0 +1,67 @@
+//! Python sequences as Bend Lists
+//!
+//! This module converts Python `list`s, `tuple`s and any other iterable into Bend's
+//! builtin `List` ADT, so they can be passed directly to Bend functions without
+//! building a cons-list with `List.Cons` and `List.Nil` from Python.
+
+use bend::fun::Book;
+use bend::imp;
+use pyo3::types::{PyAnyMethods, PyTypeMethods};
+use pyo3::{Bound, PyAny};
+
+use super::{extract_expr, extract_type_raw, BendResult, BendType};
+use crate::errors::ArgumentConversionError;
+
+/// Represents a Python iterable that will be converted into a Bend `List`
+///
+/// # Fields
+///
+/// * `data` - The Python iterable
+/// * `book` - The Bend book used to convert elements that are ADTs, if any
+pub struct BendList<'py, 'book> {
+    data: Bound<'py, PyAny>,
+    book: Option<&'book Book>,
+}
+
+impl<'py, 'book> BendList<'py, 'book> {
+    /// Creates a new BendList
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The Python iterable to convert
+    /// * `book` - The Bend book containing the ADT definitions, if any
+    pub fn new(data: Bound<'py, PyAny>, book: Option<&'book Book>) -> Self {
+        Self { data, book }
+    }
+}
+
+impl<'py, 'book> BendType for BendList<'py, 'book> {
+    /// Converts the iterable into a Bend list expression
+    ///
+    /// Every element is converted on its own, so lists can hold numbers, ADTs
+    /// and other lists.
+    fn to_bend(&self) -> BendResult {
+        let mut els: Vec<imp::Expr> = vec![];
+
+        for item in self.data.iter()? {
+            let item = item?;
+
+            let el = match self.book {
+                Some(book) => extract_expr(item, book)?,
+                None => match extract_type_raw(item.clone())? {
+                    Some(el) => el.to_bend()?,
+                    None => {
+                        return Err(ArgumentConversionError::new_err(format!(
+                            "Could not convert list element of type {}",
+                            item.get_type().name()?
+                        )))
+                    }
+                },
+            };
+
+            els.push(el);
+        }
+
+        Ok(imp::Expr::Lst { els })
+    }
+}
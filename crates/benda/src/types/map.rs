@@ -0,0 +1,76 @@
+//! Python dicts as Bend Maps
+//!
+//! This module converts Python `dict`s with integer keys into Bend's builtin `Map`
+//! ADT, a binary tree indexed by the bits of the key.
+
+use bend::fun::{Book, Num};
+use bend::imp;
+use pyo3::types::{PyAnyMethods, PyDict, PyDictMethods, PyTypeMethods};
+use pyo3::{Bound, PyAny, PyErr};
+
+use super::{extract_expr, extract_type_raw, BendResult, BendType};
+use crate::errors::ArgumentConversionError;
+
+/// Represents a Python dict that will be converted into a Bend `Map`
+///
+/// # Fields
+///
+/// * `data` - The Python dict
+/// * `book` - The Bend book used to convert values that are ADTs, if any
+pub struct BendMap<'py, 'book> {
+    data: Bound<'py, PyAny>,
+    book: Option<&'book Book>,
+}
+
+impl<'py, 'book> BendMap<'py, 'book> {
+    /// Creates a new BendMap
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The Python dict to convert
+    /// * `book` - The Bend book containing the ADT definitions, if any
+    pub fn new(data: Bound<'py, PyAny>, book: Option<&'book Book>) -> Self {
+        Self { data, book }
+    }
+}
+
+impl<'py, 'book> BendType for BendMap<'py, 'book> {
+    /// Converts the dict into a Bend map expression
+    ///
+    /// Keys must be integers that fit in a u24, values are converted like any
+    /// other argument.
+    fn to_bend(&self) -> BendResult {
+        let dict = self.data.downcast::<PyDict>().map_err(PyErr::from)?;
+
+        let mut entries: Vec<(imp::Expr, imp::Expr)> = vec![];
+
+        for (key, value) in dict.iter() {
+            let key = match key.extract::<u32>() {
+                Ok(key) if key <= 0xFFFFFF => key,
+                _ => {
+                    return Err(ArgumentConversionError::new_err(format!(
+                        "Map keys must be integers between 0 and {}, got {}",
+                        0xFFFFFF, key
+                    )))
+                }
+            };
+
+            let value = match self.book {
+                Some(book) => extract_expr(value, book)?,
+                None => match extract_type_raw(value.clone())? {
+                    Some(value) => value.to_bend()?,
+                    None => {
+                        return Err(ArgumentConversionError::new_err(format!(
+                            "Could not convert map value of type {}",
+                            value.get_type().name()?
+                        )))
+                    }
+                },
+            };
+
+            entries.push((imp::Expr::Num { val: Num::U24(key) }, value));
+        }
+
+        Ok(imp::Expr::Map { entries })
+    }
+}
@@ -1,21 +1,52 @@
 use std::fmt::Display;
 
-use bend::fun::{Book, Num};
+use bend::fun::{Book, Name, Num};
 use bend::imp::{self};
+use f24::F24;
+use i24::I24;
+use list::BendList;
+use map::BendMap;
 use num_traits::cast::ToPrimitive;
-use pyo3::types::{PyAnyMethods, PyFloat, PyTypeMethods};
-use pyo3::{Bound, FromPyObject, PyAny, PyErr, PyTypeCheck};
-use rustpython_parser::ast::ExprCall;
+use pyo3::types::{PyAnyMethods, PyDict, PyFloat, PyTypeMethods};
+use pyo3::{Bound, FromPyObject, PyAny, PyErr, PyResult, PyTypeCheck};
+use rustpython_parser::ast::{Constant, ExprCall};
+use string::BendString;
 use u24::U24;
 use user_adt::UserAdt;
 
+use crate::errors::{ArgumentConversionError, UnsupportedPythonConstruct};
+
+pub mod bjit_result;
 pub mod book;
+pub mod config;
 pub mod f24;
 pub mod fan;
 pub mod i24;
+pub mod list;
+pub mod map;
+pub mod run_result;
+pub mod string;
 pub mod u24;
 pub mod user_adt;
 
+/// The `None` of the `Maybe` type that `None` and `Optional` values become
+pub fn maybe_none() -> imp::Expr {
+    imp::Expr::Ctr {
+        name: Name::new("Maybe/None"),
+        args: vec![],
+        kwargs: vec![],
+    }
+}
+
+/// Wraps a value that isn't `None` into the `Maybe` of an `Optional` value
+pub fn maybe_some(val: imp::Expr) -> imp::Expr {
+    imp::Expr::Ctr {
+        name: Name::new("Maybe/Some"),
+        args: vec![val],
+        kwargs: vec![],
+    }
+}
+
 /// Trait for converting types to Bend expressions
 ///
 /// This trait defines the interface for converting various types into Bend expressions.
@@ -30,125 +61,273 @@ pub fn extract_inner<'py, T: BendType + PyTypeCheck + FromPyObject<'py>>(
 ) -> Option<T> {
     let inner = arg.downcast::<T>();
     if let Ok(inner) = inner {
-        let inner = <T as FromPyObject>::extract_bound(inner.as_any());
-        return Some(inner.unwrap());
+        return <T as FromPyObject>::extract_bound(inner.as_any()).ok();
     }
     None
 }
 
+/// Returns an `ArgumentConversionError` for a value that can't be converted
+/// into the given type
+fn conversion_err<T>(arg: &Bound<PyAny>, t_type: BuiltinType) -> PyResult<T> {
+    Err(ArgumentConversionError::new_err(format!(
+        "Could not convert {} of type {} into a Bend {}",
+        arg,
+        arg.get_type().name()?,
+        t_type
+    )))
+}
+
+/// Converts a Python number for `extract_type_raw`, where integers are always
+/// i24s
+///
+/// # Errors
+///
+/// Returns an `ArgumentConversionError` if the number is an integer that
+/// doesn't fit in an i24.
 pub fn extract_num_raw(
     arg: Bound<PyAny>,
     t_type: BuiltinType,
-) -> Box<dyn BendType> {
+) -> PyResult<Box<dyn BendType>> {
+    let text = arg.to_string();
+
     match t_type {
-        BuiltinType::U24 => Box::new(arg.to_string().parse::<i32>().unwrap()),
-        BuiltinType::I32 => Box::new(arg.to_string().parse::<i32>().unwrap()),
-        BuiltinType::F32 => Box::new(arg.to_string().parse::<f32>().unwrap()),
+        BuiltinType::U24 | BuiltinType::I32 => match text.parse::<i32>() {
+            Ok(num) if (I24::MIN..=I24::MAX).contains(&num) => {
+                Ok(Box::new(num))
+            }
+            _ => conversion_err(&arg, BuiltinType::I24),
+        },
+        BuiltinType::F32 => match text.parse::<f32>() {
+            Ok(num) => Ok(Box::new(num)),
+            Err(_) => conversion_err(&arg, t_type),
+        },
         _ => unreachable!(),
     }
 }
 
 pub fn extract_num(arg: Bound<PyAny>, t_type: BuiltinType) -> BendResult {
+    let text = arg.to_string();
+
     match t_type {
-        BuiltinType::I32 => arg.to_string().parse::<i32>().unwrap().to_bend(),
-        BuiltinType::F32 => arg.to_string().parse::<f32>().unwrap().to_bend(),
+        BuiltinType::U24 => match text.parse::<u32>() {
+            Ok(num) if num <= 0xFFFFFF => num.to_bend(),
+            _ => conversion_err(&arg, t_type),
+        },
+        BuiltinType::I32 => match text.parse::<i32>() {
+            Ok(num) if (I24::MIN..=I24::MAX).contains(&num) => num.to_bend(),
+            _ => conversion_err(&arg, t_type),
+        },
+        BuiltinType::F32 => match text.parse::<f32>() {
+            Ok(num) => num.to_bend(),
+            Err(_) => conversion_err(&arg, t_type),
+        },
         _ => unreachable!(),
     }
 }
 
-pub fn extract_type_raw(arg: Bound<PyAny>) -> Option<Box<dyn BendType>> {
+/// Converts a Python object of a builtin type without looking up any ADT
+///
+/// # Returns
+///
+/// The convertible object, or `None` if the object is not of a builtin type.
+///
+/// # Errors
+///
+/// Returns an `ArgumentConversionError` if the object is a number that doesn't fit
+/// in a Bend number.
+pub fn extract_type_raw<'py>(
+    arg: Bound<'py, PyAny>,
+) -> PyResult<Option<Box<dyn BendType + 'py>>> {
     let t_type = arg.get_type();
-    let name = t_type.name().unwrap();
+    let name = t_type.name()?;
 
     let arg_type = BuiltinType::from(name.to_string());
 
-    match arg_type {
+    Ok(match arg_type {
         //BuiltinType::U24 => Some(Box::new(extract_inner::<U24>(arg).unwrap())),
-        BuiltinType::U24 => Some(extract_num_raw(arg, BuiltinType::U24)),
-        BuiltinType::I32 => Some(extract_num_raw(arg, BuiltinType::I32)),
-        BuiltinType::F32 => Some(extract_num_raw(arg, BuiltinType::F32)),
+        BuiltinType::Bool => Some(Box::new(arg.is_truthy()? as u32)),
+        BuiltinType::U24 => Some(extract_num_raw(arg, BuiltinType::U24)?),
+        BuiltinType::I32 => Some(extract_num_raw(arg, BuiltinType::I32)?),
+        BuiltinType::F32 => Some(extract_num_raw(arg, BuiltinType::F32)?),
+        BuiltinType::I24 => {
+            extract_inner::<I24>(arg).map(|v| Box::new(v) as Box<dyn BendType>)
+        }
+        BuiltinType::F24 => {
+            extract_inner::<F24>(arg).map(|v| Box::new(v) as Box<dyn BendType>)
+        }
+        BuiltinType::List => Some(Box::new(BendList::new(arg, None))),
+        BuiltinType::String => Some(Box::new(BendString::new(arg))),
+        BuiltinType::Map => Some(Box::new(BendMap::new(arg, None))),
         _ => None,
-    }
+    })
 }
 
 pub fn extract_type(arg: Bound<PyAny>, book: &Book) -> BendResult {
     let t_type = arg.get_type();
-    let name = t_type.name().unwrap();
+    let name = t_type.name()?;
 
     let arg_type = BuiltinType::from(name.to_string());
 
     match arg_type {
-        BuiltinType::U24 => extract_inner::<U24>(arg).unwrap().to_bend(),
+        BuiltinType::Bool => (arg.is_truthy()? as u32).to_bend(),
+        BuiltinType::U24 => match extract_inner::<U24>(arg.clone()) {
+            Some(num) => num.to_bend(),
+            None => extract_num(arg, BuiltinType::U24),
+        },
         BuiltinType::I32 => extract_num(arg, BuiltinType::I32),
         BuiltinType::F32 => extract_num(arg, BuiltinType::F32),
-        BuiltinType::UserAdt => UserAdt::new(arg, book).unwrap().to_bend(),
+        BuiltinType::I24 => match extract_inner::<I24>(arg.clone()) {
+            Some(num) => num.to_bend(),
+            None => conversion_err(&arg, arg_type),
+        },
+        BuiltinType::F24 => match extract_inner::<F24>(arg.clone()) {
+            Some(num) => num.to_bend(),
+            None => conversion_err(&arg, arg_type),
+        },
+        BuiltinType::List => BendList::new(arg, Some(book)).to_bend(),
+        BuiltinType::String => BendString::new(arg).to_bend(),
+        BuiltinType::Map => BendMap::new(arg, Some(book)).to_bend(),
+        BuiltinType::UserAdt => match UserAdt::new(arg.clone(), book) {
+            Some(adt) => adt.to_bend(),
+            None => conversion_err(&arg, arg_type),
+        },
     }
 }
 
-pub fn extract_type_expr(call: ExprCall) -> Option<imp::Expr> {
-    let name = call.func.as_name_expr().unwrap().id.to_string();
+/// Converts any supported Python object into a Bend expression
+///
+/// ADT instances are looked up in the given Book, builtin types (numbers, lists,
+/// strings and dicts) are converted with `extract_type_raw`, other dicts are converted
+/// into a Bend `Map` and any other iterable is converted into a Bend `List`.
+///
+/// # Errors
+///
+/// Returns an `ArgumentConversionError` if the object can't be converted.
+pub fn extract_expr(arg: Bound<PyAny>, book: &Book) -> BendResult {
+    if let Some(adt) = UserAdt::new(arg.clone(), book) {
+        return adt.to_bend();
+    }
 
-    let arg = call.args.first().unwrap();
+    if arg.is_none() {
+        return Err(ArgumentConversionError::new_err(
+            "`None` can only be passed to Books that define `type Maybe = (Some value) | (None)`",
+        ));
+    }
 
-    let arg_type = BuiltinType::from(name.to_string());
+    let arg_type = BuiltinType::from(arg.get_type().name()?.to_string());
 
     match arg_type {
-        BuiltinType::U24 => Some(imp::Expr::Num {
-            val: Num::U24(
-                arg.clone()
-                    .constant_expr()
-                    .unwrap()
-                    .value
-                    .int()
-                    .unwrap()
-                    .to_u32()
-                    .unwrap(),
-            ),
-        }),
-        BuiltinType::I32 => Some(imp::Expr::Num {
-            val: Num::I24(
-                arg.clone()
-                    .constant_expr()
-                    .unwrap()
-                    .value
-                    .int()
-                    .unwrap()
-                    .to_i32()
-                    .unwrap(),
-            ),
-        }),
-        BuiltinType::F32 => Some(imp::Expr::Num {
-            val: Num::F24(
-                arg.clone()
-                    .constant_expr()
-                    .unwrap()
-                    .value
-                    .int()
-                    .unwrap()
-                    .to_f32()
-                    .unwrap(),
-            ),
-        }),
+        BuiltinType::List => return BendList::new(arg, Some(book)).to_bend(),
+        BuiltinType::Map => return BendMap::new(arg, Some(book)).to_bend(),
+        _ => {}
+    }
+
+    if let Some(val) = extract_type_raw(arg.clone())? {
+        return val.to_bend();
+    }
+
+    if arg.is_instance_of::<PyDict>() {
+        return BendMap::new(arg, Some(book)).to_bend();
+    }
+
+    if arg.iter().is_ok() {
+        return BendList::new(arg, Some(book)).to_bend();
+    }
+
+    Err(ArgumentConversionError::new_err(format!(
+        "Could not convert argument of type {}",
+        arg.get_type().name()?
+    )))
+}
+
+/// Converts a call to a number type, like `u24(3)`, into a Bend number
+///
+/// # Returns
+///
+/// The number, or `None` if the called function is not a number type.
+///
+/// # Errors
+///
+/// Returns an `UnsupportedPythonConstruct` if the type is not called with a
+/// single number literal.
+pub fn extract_type_expr(call: ExprCall) -> PyResult<Option<imp::Expr>> {
+    let Some(name) = call.func.as_name_expr().map(|n| n.id.to_string()) else {
+        return Ok(None);
+    };
+
+    let arg_type = BuiltinType::from(name.to_string());
+
+    if !matches!(
+        arg_type,
+        BuiltinType::U24
+            | BuiltinType::I32
+            | BuiltinType::I24
+            | BuiltinType::F32
+            | BuiltinType::F24
+    ) {
+        return Ok(None);
+    }
+
+    let value = match call.args.as_slice() {
+        [rustpython_parser::ast::Expr::Constant(c)] => c.value.clone(),
+        _ => {
+            return Err(UnsupportedPythonConstruct::new_err(format!(
+                "{}() only accepts a single number literal",
+                name
+            )))
+        }
+    };
+
+    let val = match (arg_type, value) {
+        (BuiltinType::U24, Constant::Int(i)) => i.to_u32().map(Num::U24),
+        (BuiltinType::I32 | BuiltinType::I24, Constant::Int(i)) => {
+            i.to_i32().map(Num::I24)
+        }
+        (BuiltinType::F32 | BuiltinType::F24, Constant::Int(i)) => {
+            i.to_f32().map(Num::F24)
+        }
+        (BuiltinType::F32 | BuiltinType::F24, Constant::Float(f)) => {
+            Some(Num::F24(f as f32))
+        }
         _ => None,
+    };
+
+    match val {
+        Some(val) => Ok(Some(imp::Expr::Num { val })),
+        None => Err(UnsupportedPythonConstruct::new_err(format!(
+            "{}() only accepts a single number literal",
+            name
+        ))),
     }
 }
 
 #[derive(Debug, Clone)]
 pub enum BuiltinType {
+    Bool,
     U24,
     F32,
-    #[allow(dead_code)]
     I32,
+    I24,
+    F24,
+    List,
+    String,
+    Map,
     UserAdt,
 }
 
 impl Display for BuiltinType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            BuiltinType::Bool => f.write_str("u24"),
             BuiltinType::U24 => f.write_str("u24"),
             BuiltinType::F32 => f.write_str("f24"),
             BuiltinType::I32 => f.write_str("i24"),
-            _ => panic!(),
+            BuiltinType::I24 => f.write_str("i24"),
+            BuiltinType::F24 => f.write_str("f24"),
+            BuiltinType::List => f.write_str("List"),
+            BuiltinType::String => f.write_str("String"),
+            BuiltinType::Map => f.write_str("Map"),
+            BuiltinType::UserAdt => f.write_str("ADT"),
         }
     }
 }
@@ -156,10 +335,23 @@ impl Display for BuiltinType {
 impl From<String> for BuiltinType {
     fn from(value: String) -> Self {
         match value.as_str() {
+            "bool" => BuiltinType::Bool,
             "float" => BuiltinType::F32,
             "int" => BuiltinType::U24,
             "benda.U24" => BuiltinType::U24,
             "U24" => BuiltinType::U24,
+            "u24" => BuiltinType::U24,
+            "benda.u24" => BuiltinType::U24,
+            "i24" => BuiltinType::I24,
+            "benda.i24" => BuiltinType::I24,
+            "f24" => BuiltinType::F24,
+            "benda.f24" => BuiltinType::F24,
+            "list" => BuiltinType::List,
+            "tuple" => BuiltinType::List,
+            "str" => BuiltinType::String,
+            "bytes" => BuiltinType::String,
+            "bytearray" => BuiltinType::String,
+            "dict" => BuiltinType::Map,
             _ => BuiltinType::UserAdt,
         }
     }
@@ -199,3 +391,137 @@ impl BendType for PyFloat {
         }
     }
 }
+
+/// Helpers to compare the numeric types with the results of the HVM
+#[cfg(test)]
+pub(crate) mod num_tests {
+    use bend::fun::{
+        Book, Definition, FanKind, Name, Num, Op, Rule, Tag, Term,
+    };
+
+    use crate::benda_ffi;
+    use crate::types::book::BendRuntime;
+
+    /// A xorshift generator, so the tests don't need a random dependency
+    pub struct Rng(u64);
+
+    impl Rng {
+        pub fn new(seed: u64) -> Self {
+            Self(seed)
+        }
+
+        pub fn next_u32(&mut self) -> u32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 >> 32) as u32
+        }
+    }
+
+    /// How many operations `assert_parity` evaluates in each run of the HVM
+    const OPERS_PER_RUN: usize = 256;
+
+    /// Evaluates all the operations in a single run of the HVM
+    ///
+    /// # Returns
+    ///
+    /// The results in the same order as the operations, or `None` if the HVM
+    /// didn't return a number for every operation.
+    pub fn run_opers(opers: &[(Op, Num, Num)]) -> Option<Vec<Num>> {
+        let mut body = Term::Num { val: Num::U24(0) };
+
+        for (opr, fst, snd) in opers.iter().rev() {
+            let oper = Term::Oper {
+                opr: *opr,
+                fst: Box::new(Term::Num { val: *fst }),
+                snd: Box::new(Term::Num { val: *snd }),
+            };
+
+            body = Term::Fan {
+                fan: FanKind::Tup,
+                tag: Tag::Static,
+                els: vec![oper, body],
+            };
+        }
+
+        let mut book = Book::builtins();
+        book.defs.insert(
+            Name::new("main"),
+            Definition {
+                name: Name::new("main"),
+                rules: vec![Rule { pats: vec![], body }],
+                builtin: false,
+            },
+        );
+
+        let res = benda_ffi::run(&book, BendRuntime::Rust.to_string().as_str());
+        let (mut term, _, _) = res.ok()??;
+
+        let mut results: Vec<Num> = vec![];
+
+        while let Term::Fan { els, .. } = &mut term {
+            let rest = els.pop()?;
+
+            for el in els.iter() {
+                match el {
+                    Term::Num { val } => results.push(*val),
+                    _ => return None,
+                }
+            }
+
+            term = rest;
+        }
+
+        if results.len() == opers.len() {
+            Some(results)
+        } else {
+            None
+        }
+    }
+
+    /// Checks if two numbers have the same type and bits, treating every NaN as equal
+    pub fn same_num(a: &Num, b: &Num) -> bool {
+        match (a, b) {
+            (Num::U24(a), Num::U24(b)) => a == b,
+            (Num::I24(a), Num::I24(b)) => a == b,
+            (Num::F24(a), Num::F24(b)) => {
+                a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan())
+            }
+            _ => false,
+        }
+    }
+
+    /// Runs the operations in the HVM and asserts that `benda` gives the same results
+    ///
+    /// # Arguments
+    ///
+    /// * `opers` - The operations to run
+    /// * `benda` - Evaluates an operation with the Python numeric type
+    pub fn assert_parity(
+        opers: &[(Op, Num, Num)],
+        benda: impl Fn(Op, Num, Num) -> Num,
+    ) {
+        // The HVM limits how many nodes a definition can allocate at once,
+        // so the operations are split between many runs.
+        let results: Vec<Num> = opers
+            .chunks(OPERS_PER_RUN)
+            .flat_map(|opers| {
+                run_opers(opers).expect("Could not get result from HVM")
+            })
+            .collect();
+
+        for ((opr, fst, snd), bend_res) in opers.iter().zip(results) {
+            let benda_res = benda(*opr, *fst, *snd);
+
+            assert!(
+                same_num(&bend_res, &benda_res),
+                "({:?} {:?} {:?}): HVM returned {:?}, benda returned {:?}",
+                opr,
+                fst,
+                snd,
+                bend_res,
+                benda_res
+            );
+        }
+    }
+}
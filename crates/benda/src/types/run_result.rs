@@ -0,0 +1,123 @@
+//! Statistics of a Bend run
+//!
+//! The HVM reports how many interactions a run took, how long it took and its speed
+//! in millions of interactions per second. This module parses that report so it can
+//! be read from Python together with the result of the run.
+
+use pyo3::{pyclass, pymethods, IntoPy, PyObject, Python};
+
+use super::book::Term;
+
+/// The result of a Bend function call, with the statistics reported by the HVM
+///
+/// # Fields
+///
+/// * `term` - The resulting term
+/// * `itrs` - The number of interactions, if reported
+/// * `time` - The elapsed time in seconds, if reported
+/// * `mips` - Millions of interactions per second, if reported
+/// * `output` - The raw statistics output of the runtime
+#[pyclass(module = "benda", name = "RunResult")]
+#[derive(Clone, Debug)]
+pub struct RunResult {
+    term: Term,
+    itrs: Option<u64>,
+    time: Option<f64>,
+    mips: Option<f64>,
+    output: String,
+}
+
+impl RunResult {
+    /// Creates a RunResult from a term and the statistics output of the HVM
+    ///
+    /// # Arguments
+    ///
+    /// * `term` - The resulting term
+    /// * `output` - The statistics output, like `"- ITRS: 10\n- TIME: 0.00s\n- MIPS: 2.51\n"`
+    pub fn new(term: Term, output: String) -> Self {
+        let mut itrs = None;
+        let mut time = None;
+        let mut mips = None;
+
+        for line in output.lines() {
+            let Some((key, value)) =
+                line.trim_start_matches('-').split_once(':')
+            else {
+                continue;
+            };
+
+            let value = value.trim().trim_end_matches('s');
+
+            match key.trim() {
+                "ITRS" => itrs = value.parse().ok(),
+                "TIME" => time = value.parse().ok(),
+                "MIPS" => mips = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        Self {
+            term,
+            itrs,
+            time,
+            mips,
+            output,
+        }
+    }
+}
+
+#[pymethods]
+impl RunResult {
+    /// The resulting term
+    #[getter]
+    fn term(&self, py: Python) -> PyObject {
+        self.term.clone().into_py(py)
+    }
+
+    /// The number of interactions of the run
+    #[getter]
+    fn itrs(&self) -> Option<u64> {
+        self.itrs
+    }
+
+    /// The elapsed time of the run, in seconds
+    #[getter]
+    fn time(&self) -> Option<f64> {
+        self.time
+    }
+
+    /// Millions of interactions per second
+    #[getter]
+    fn mips(&self) -> Option<f64> {
+        self.mips
+    }
+
+    /// The raw statistics output of the runtime
+    #[getter]
+    fn output(&self) -> String {
+        self.output.clone()
+    }
+
+    fn __str__(&self) -> String {
+        self.output.clone()
+    }
+}
+
+#[cfg(test)]
+mod run_result_tests {
+    use bend::fun::Term as BTerm;
+
+    use super::*;
+
+    #[test]
+    fn parse_stats() {
+        let res = RunResult::new(
+            Term::new(BTerm::Era),
+            "- ITRS: 10\n- TIME: 0.25s\n- MIPS: 2.51\n".to_string(),
+        );
+
+        assert_eq!(res.itrs, Some(10));
+        assert_eq!(res.time, Some(0.25));
+        assert_eq!(res.mips, Some(2.51));
+    }
+}
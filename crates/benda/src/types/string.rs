@@ -0,0 +1,166 @@
+//! Python text and binary data as Bend Strings and Lists
+//!
+//! This module converts Python `str`s into Bend's builtin `String` ADT and
+//! `bytes`/`bytearray`s into a Bend `List` of u24 numbers, one for each byte.
+
+use bend::fun::{Num, STRINGS};
+use bend::imp;
+use pyo3::types::{
+    PyAnyMethods, PyByteArray, PyByteArrayMethods, PyBytes, PyString,
+};
+use pyo3::{Bound, PyAny, PyErr};
+
+use super::{BendResult, BendType};
+
+/// Represents a Python `str`, `bytes` or `bytearray` that will be converted
+/// into a Bend expression
+///
+/// # Fields
+///
+/// * `data` - The Python object
+pub struct BendString<'py> {
+    data: Bound<'py, PyAny>,
+}
+
+impl<'py> BendString<'py> {
+    /// Creates a new BendString
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The Python `str`, `bytes` or `bytearray` to convert
+    pub fn new(data: Bound<'py, PyAny>) -> Self {
+        Self { data }
+    }
+}
+
+impl<'py> BendType for BendString<'py> {
+    /// Converts the object into a Bend expression
+    ///
+    /// A `str` becomes a Bend `String` and binary data becomes a `List` of
+    /// u24 numbers.
+    fn to_bend(&self) -> BendResult {
+        if let Ok(text) = self.data.downcast::<PyString>() {
+            let text: String = text.extract()?;
+            return Ok(imp::Expr::Str {
+                val: STRINGS.get(text),
+            });
+        }
+
+        let bytes: Vec<u8> = if let Ok(bytes) = self.data.downcast::<PyBytes>()
+        {
+            bytes.extract()?
+        } else {
+            self.data
+                .downcast::<PyByteArray>()
+                .map_err(PyErr::from)?
+                .to_vec()
+        };
+
+        let els = bytes
+            .into_iter()
+            .map(|byte| imp::Expr::Num {
+                val: Num::U24(byte as u32),
+            })
+            .collect();
+
+        Ok(imp::Expr::Lst { els })
+    }
+}
+
+#[cfg(test)]
+mod string_tests {
+    use pyo3::types::{PyAnyMethods, PyByteArray, PyBytes, PyString};
+    use pyo3::{IntoPy, Py, PyObject, PyResult, Python};
+
+    use crate::errors::ArgumentConversionError;
+    use crate::load_book;
+    use crate::types::book::Book;
+
+    const CODE: &str = "
+def id(x):
+  return x
+
+def main():
+  return 0
+";
+
+    /// Passes the object through a Bend identity function and reads the
+    /// returned Term back with the given method, like `to_str` or `to_list`
+    fn round_trip(
+        py: Python,
+        book: &Py<Book>,
+        obj: PyObject,
+        method: &str,
+    ) -> PyResult<PyObject> {
+        let term = book
+            .bind(py)
+            .getattr("defs")?
+            .getattr("id")?
+            .call1((obj,))?;
+
+        Ok(term.call_method0(method)?.unbind())
+    }
+
+    #[test]
+    fn strings() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let book =
+                load_book(py, PyString::new_bound(py, CODE).unbind()).unwrap();
+
+            for text in ["benda", "héllo, 世界 🦀", ""] {
+                let obj = PyString::new_bound(py, text).into_any().unbind();
+                let res = round_trip(py, &book, obj, "to_str").unwrap();
+
+                assert_eq!(res.extract::<String>(py).unwrap(), text);
+            }
+        });
+    }
+
+    #[test]
+    fn bytes() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let book =
+                load_book(py, PyString::new_bound(py, CODE).unbind()).unwrap();
+
+            let data = [0, 98, 255];
+            let objs = [
+                PyBytes::new_bound(py, &data).into_any().unbind(),
+                PyByteArray::new_bound(py, &data).into_any().unbind(),
+                PyBytes::new_bound(py, &[]).into_any().unbind(),
+            ];
+
+            for (i, obj) in objs.into_iter().enumerate() {
+                let res = round_trip(py, &book, obj, "to_list").unwrap();
+                let expected = if i < 2 { data.to_vec() } else { vec![] };
+
+                assert_eq!(res.extract::<Vec<u8>>(py).unwrap(), expected);
+            }
+        });
+    }
+
+    #[test]
+    fn not_a_string() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let book =
+                load_book(py, PyString::new_bound(py, CODE).unbind()).unwrap();
+
+            let objs = [
+                5_u32.into_py(py),
+                vec![1_u32, 2].into_py(py),
+                PyBytes::new_bound(py, b"ab").into_any().unbind(),
+            ];
+
+            for obj in objs {
+                let err = round_trip(py, &book, obj, "to_str").unwrap_err();
+
+                assert!(err.is_instance_of::<ArgumentConversionError>(py));
+            }
+        });
+    }
+}
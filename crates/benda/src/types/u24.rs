@@ -23,7 +23,6 @@ impl BendType for U24 {
 impl U24 {
     const MAX: u32 = 0xffffff;
 
-    // TODO: Check if the masking is working properly
     pub fn new(value: u32) -> Self {
         Self(value & Self::MAX)
     }
@@ -61,7 +60,7 @@ impl std::ops::Add for U24 {
     type Output = Self;
 
     fn add(self, other: Self) -> Self {
-        U24::new(self.0 + other.0)
+        U24::new(self.0.wrapping_add(other.0))
     }
 }
 
@@ -69,13 +68,10 @@ impl std::ops::Sub for U24 {
     type Output = Self;
 
     fn sub(self, other: Self) -> Self {
-        U24::new(self.0 - other.0)
+        U24::new(self.0.wrapping_sub(other.0))
     }
 }
 
-// TODO: Check for overflow on the operations
-// TODO: Implement tests for each operation comparing to Bend
-
 #[pymethods]
 impl U24 {
     #[new]
@@ -92,7 +88,7 @@ impl U24 {
     }
 
     fn __mul__(&self, other: &Self) -> Self {
-        Self(self.0.wrapping_mul(other.0))
+        U24::new(self.0.wrapping_mul(other.0))
     }
 
     fn __truediv__(&self, other: &Self) -> PyResult<Self> {
@@ -109,6 +105,35 @@ impl U24 {
         }
     }
 
+    fn __mod__(&self, other: &Self) -> PyResult<Self> {
+        match self.0.checked_rem(other.0) {
+            Some(i) => Ok(Self(i)),
+            None => Err(PyZeroDivisionError::new_err("division by zero")),
+        }
+    }
+
+    fn __and__(&self, other: &Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    fn __or__(&self, other: &Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    fn __xor__(&self, other: &Self) -> Self {
+        Self(self.0 ^ other.0)
+    }
+
+    /// Shifts left by the 5 lowest bits of `other`, like the HVM
+    fn __lshift__(&self, other: &Self) -> Self {
+        U24::new(self.0 << (other.0 & 31))
+    }
+
+    /// Shifts right by the 5 lowest bits of `other`, like the HVM
+    fn __rshift__(&self, other: &Self) -> Self {
+        Self(self.0 >> (other.0 & 31))
+    }
+
     fn __str__(&self) -> String {
         self.0.to_string()
     }
@@ -136,17 +161,15 @@ impl U24 {
 #[cfg(test)]
 mod u24_tests {
     use core::panic;
-    use std::fmt::format;
     use std::fs::File;
     use std::path::Path;
 
-    use bend::fun::Term;
-    use bend::run_book;
-    use indexmap::IndexMap;
+    use bend::fun::{Num, Op, Term};
 
     use super::*;
     use crate::benda_ffi;
     use crate::types::book::{BendRuntime, Ctrs};
+    use crate::types::num_tests::{assert_parity, run_opers, Rng};
     use crate::types::user_adt::{from_term_into_adt, TermParse};
 
     fn run_bend_code(code: &str) -> Term {
@@ -205,4 +228,93 @@ mod u24_tests {
 
         assert_eq!(bend_res.to_u32().unwrap(), benda_res.0);
     }
+
+    const OPS: [Op; 16] = [
+        Op::ADD,
+        Op::SUB,
+        Op::MUL,
+        Op::DIV,
+        Op::REM,
+        Op::EQ,
+        Op::NEQ,
+        Op::LT,
+        Op::GT,
+        Op::LE,
+        Op::GE,
+        Op::AND,
+        Op::OR,
+        Op::XOR,
+        Op::SHL,
+        Op::SHR,
+    ];
+
+    fn benda_oper(opr: Op, fst: Num, snd: Num) -> Num {
+        let (Num::U24(a), Num::U24(b)) = (fst, snd) else {
+            unreachable!()
+        };
+        let (a, b) = (U24::new(a), U24::new(b));
+
+        let cmp = |op| a.__richcmp__(&b, op).unwrap() as u32;
+
+        let res = match opr {
+            Op::ADD => a.__add__(&b).get(),
+            Op::SUB => a.__sub__(&b).get(),
+            Op::MUL => a.__mul__(&b).get(),
+            Op::DIV => a.__floordiv__(&b).unwrap().get(),
+            Op::REM => a.__mod__(&b).unwrap().get(),
+            Op::EQ => cmp(CompareOp::Eq),
+            Op::NEQ => cmp(CompareOp::Ne),
+            Op::LT => cmp(CompareOp::Lt),
+            Op::GT => cmp(CompareOp::Gt),
+            Op::LE => cmp(CompareOp::Le),
+            Op::GE => cmp(CompareOp::Ge),
+            Op::AND => a.__and__(&b).get(),
+            Op::OR => a.__or__(&b).get(),
+            Op::XOR => a.__xor__(&b).get(),
+            Op::SHL => a.__lshift__(&b).get(),
+            Op::SHR => a.__rshift__(&b).get(),
+            _ => unreachable!(),
+        };
+
+        Num::U24(res)
+    }
+
+    #[test]
+    fn parity() {
+        let mut rng = Rng::new(0x5eed_0024);
+
+        // Shifts by 24 or more, and by "negative" amounts, wrap like in the HVM
+        let mut values: Vec<u32> = vec![
+            0, 1, 2, 23, 24, 31, 32, 0x7fffff, 0x800000, 0xffffe1, 0xffffff,
+        ];
+        values.extend((0..8).map(|_| rng.next_u32() & U24::MAX));
+
+        let mut opers: Vec<(Op, Num, Num)> = vec![];
+
+        for opr in OPS {
+            for a in &values {
+                for b in &values {
+                    if *b == 0 && matches!(opr, Op::DIV | Op::REM) {
+                        continue;
+                    }
+                    opers.push((opr, Num::U24(*a), Num::U24(*b)));
+                }
+            }
+        }
+
+        assert_parity(&opers, benda_oper);
+    }
+
+    #[test]
+    fn division_by_zero() {
+        let (a, zero) = (U24::new(7), U24::new(0));
+
+        assert!(a.__truediv__(&zero).is_err());
+        assert!(a.__floordiv__(&zero).is_err());
+        assert!(a.__mod__(&zero).is_err());
+
+        for opr in [Op::DIV, Op::REM] {
+            assert!(run_opers(&[(opr, Num::U24(7), Num::U24(0))]).is_none());
+        }
+    }
 }
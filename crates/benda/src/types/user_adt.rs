@@ -19,14 +19,18 @@
 
 use std::vec;
 
-use bend::fun::{Book, Name, Num, Term as BTerm};
+use bend::fun::{builtins, Book, FanKind, Name, Num, Pattern, Term as BTerm};
 use bend::imp::{self};
 use num_traits::ToPrimitive;
-use pyo3::types::{PyAnyMethods, PyString, PyTuple};
-use pyo3::{Bound, IntoPy, Py, PyAny, PyErr, PyObject, PyResult, Python};
+use pyo3::types::{
+    PyAnyMethods, PyDict, PyDictMethods, PyList, PyString, PyTuple,
+    PyTypeMethods,
+};
+use pyo3::{Bound, IntoPy, Py, PyAny, PyObject, PyResult, Python};
 
-use super::book::Ctrs;
+use super::book::{Adts, Ctr, Ctrs, Term};
 use super::{extract_type_raw, BendType};
+use crate::errors::ArgumentConversionError;
 
 /// Converts a Bend `Num` to an `i32`
 ///
@@ -104,12 +108,9 @@ pub fn from_term_into_adt(term: &BTerm, def_adts: &Ctrs) -> Option<TermParse> {
                     TermParse::Ctr(mut ct) => {
                         if ct.arity() == 0 {
                             return Python::with_gil(|py| {
-                                return Some(TermParse::Any(
-                                    ct.call_constructor(PyTuple::empty_bound(
-                                        py,
-                                    ))
-                                    .unwrap(),
-                                ));
+                                ct.call_constructor(PyTuple::empty_bound(py))
+                                    .ok()
+                                    .map(TermParse::Any)
                             });
                         }
                     }
@@ -122,12 +123,9 @@ pub fn from_term_into_adt(term: &BTerm, def_adts: &Ctrs) -> Option<TermParse> {
                                 args.push(case.to_py(&py));
                             }
 
-                            return Some(TermParse::Any(
-                                ct.call_constructor(PyTuple::new_bound(
-                                    py, args,
-                                ))
-                                .unwrap(),
-                            ));
+                            ct.call_constructor(PyTuple::new_bound(py, args))
+                                .ok()
+                                .map(TermParse::Any)
                         });
                     }
                     TermParse::Args(a) => {
@@ -135,42 +133,21 @@ pub fn from_term_into_adt(term: &BTerm, def_adts: &Ctrs) -> Option<TermParse> {
                     }
                 };
             }
-            todo!()
+            None
         }
         BTerm::App { tag: _, fun, arg } => {
             if let (BTerm::Var { nam: _ }, BTerm::Num { val }) =
                 (fun.as_ref(), arg.as_ref())
             {
-                let constructor: Option<Box<dyn BendCtr>> =
-                    match num_to_i32(val) {
-                        Some(0) => {
-                            Some(Box::new(def_adts.first.clone().unwrap()))
-                        }
-                        Some(1) => {
-                            Some(Box::new(def_adts.second.clone().unwrap()))
-                        }
-                        Some(2) => {
-                            Some(Box::new(def_adts.third.clone().unwrap()))
-                        }
-                        Some(3) => {
-                            Some(Box::new(def_adts.fourth.clone().unwrap()))
-                        }
-                        Some(4) => {
-                            Some(Box::new(def_adts.fifth.clone().unwrap()))
-                        }
-                        Some(5) => {
-                            Some(Box::new(def_adts.sixth.clone().unwrap()))
-                        }
-                        Some(6) => {
-                            Some(Box::new(def_adts.seventh.clone().unwrap()))
-                        }
-                        Some(7) => {
-                            Some(Box::new(def_adts.eighth.clone().unwrap()))
-                        }
-                        _ => panic!("ADT has more than 5 Ctrs"),
-                    };
+                let constructor: Box<dyn BendCtr> = match num_to_i32(val)
+                    .and_then(|tag| tag.to_usize())
+                    .and_then(|tag| def_adts.get_ctr(tag))
+                {
+                    Some(ctr) => Box::new(ctr.clone()),
+                    None => return None,
+                };
 
-                return Some(TermParse::Ctr(constructor.unwrap()));
+                return Some(TermParse::Ctr(constructor));
             }
 
             let app_arg = from_term_into_adt(arg, def_adts);
@@ -183,9 +160,8 @@ pub fn from_term_into_adt(term: &BTerm, def_adts: &Ctrs) -> Option<TermParse> {
                     TermParse::I32(val) => {
                         Python::with_gil(|py| args.push(val.into_py(py)));
                     }
-                    TermParse::Ctr(_) => todo!(),
+                    TermParse::Ctr(_) | TermParse::Vec(_, _) => return None,
                     TermParse::Any(a) => args.push(a),
-                    TermParse::Vec(_, _) => todo!(),
                     TermParse::Args(mut inner_args) => {
                         args.append(&mut inner_args);
                     }
@@ -217,6 +193,428 @@ pub fn from_term_into_adt(term: &BTerm, def_adts: &Ctrs) -> Option<TermParse> {
     }
 }
 
+/// Identifies the constructor of a term read back from the HVM
+#[derive(Debug)]
+pub(crate) enum CtrId<'t> {
+    /// A num-scott encoded constructor, `λx (x tag fields...)`, whose ADT is unknown
+    Tag(usize),
+    /// A constructor referenced by its name, like `List/Nil` or `(List/Cons h t)`
+    Name(&'t Name),
+}
+
+/// Splits a term into its constructor and fields, if it is a constructor
+///
+/// # Arguments
+///
+/// * `term` - A reference to a Bend `Term`
+///
+/// # Returns
+///
+/// An `Option` with the constructor identifier and its fields, or `None` if the term
+/// isn't a constructor
+pub(crate) fn split_ctr(term: &BTerm) -> Option<(CtrId, Vec<&BTerm>)> {
+    let (var, body) = match term {
+        BTerm::Lam { pat, bod, .. } => match pat.as_ref() {
+            Pattern::Var(Some(var)) => (Some(var), bod.as_ref()),
+            _ => return None,
+        },
+        _ => (None, term),
+    };
+
+    let mut fields: Vec<&BTerm> = vec![];
+    let mut head = body;
+
+    while let BTerm::App { fun, arg, .. } = head {
+        fields.push(arg.as_ref());
+        head = fun.as_ref();
+    }
+
+    fields.reverse();
+
+    match (var, head) {
+        (Some(var), BTerm::Var { nam }) if nam == var && !fields.is_empty() => {
+            match fields.remove(0) {
+                BTerm::Num { val: Num::U24(tag) } => {
+                    Some((CtrId::Tag(tag.to_usize()?), fields))
+                }
+                _ => None,
+            }
+        }
+        (None, BTerm::Ref { nam }) => Some((CtrId::Name(nam), fields)),
+        _ => None,
+    }
+}
+
+/// Finds the ADT and constructor with the given full name
+fn find_ctr<'a>(adts: &'a Adts, nam: &Name) -> Option<(&'a str, &'a Ctr)> {
+    adts.adts.iter().find_map(|(adt_name, ctrs)| {
+        ctrs.ctrs
+            .iter()
+            .find(|ctr| ctr.full_name() == nam.as_ref())
+            .map(|ctr| (adt_name.as_str(), ctr))
+    })
+}
+
+/// Converts a Bend term into Python objects, using the ADTs of a Book
+///
+/// Unlike `from_term_into_adt`, this function doesn't need to know the ADT of the term
+/// beforehand. The ADT of a constructor is inferred from its tag, its arity and its
+/// recursive fields, so only the ADTs that can represent the whole term are chosen.
+///
+/// # Arguments
+///
+/// * `py` - The Python GIL token
+/// * `term` - A reference to a Bend `Term`
+/// * `adts` - The ADTs of the Book the term came from
+///
+/// # Returns
+///
+/// A `PyResult` with the converted object. Terms that have no Python equivalent,
+/// like lambdas, are returned as a `Term`.
+pub fn from_term_into_py(
+    py: Python,
+    term: &BTerm,
+    adts: &Adts,
+) -> PyResult<PyObject> {
+    match term {
+        BTerm::Num { val } => Ok(match val {
+            Num::U24(val) => val.into_py(py),
+            Num::I24(val) => val.into_py(py),
+            Num::F24(val) => val.into_py(py),
+        }),
+        BTerm::Nat { val } => Ok(val.into_py(py)),
+        BTerm::Str { val } => Ok(val.to_string().into_py(py)),
+        BTerm::Era => Ok(py.None()),
+        BTerm::Fan {
+            fan: FanKind::Tup,
+            els,
+            ..
+        } => {
+            let mut elements: Vec<PyObject> = vec![];
+            for el in els {
+                elements.push(from_term_into_py(py, el, adts)?);
+            }
+            Ok(PyTuple::new_bound(py, elements).into_py(py))
+        }
+        BTerm::List { els } => {
+            let mut elements: Vec<PyObject> = vec![];
+            for el in els {
+                elements.push(from_term_into_py(py, el, adts)?);
+            }
+            Ok(PyList::new_bound(py, elements).into_py(py))
+        }
+        _ => {
+            let candidates: Vec<&str> = match split_ctr(term) {
+                Some((CtrId::Name(nam), _)) => find_ctr(adts, nam)
+                    .map(|(adt_name, _)| vec![adt_name])
+                    .unwrap_or_default(),
+                // User ADTs are defined after the builtin ones, so they are tried first
+                Some((CtrId::Tag(_), _)) => {
+                    adts.adts.keys().rev().map(|k| k.as_str()).collect()
+                }
+                None => vec![],
+            };
+
+            // Tags are only unique inside an ADT, so the ADTs whose recursive
+            // fields match the term deeper are preferred.
+            let mut candidates: Vec<(&str, usize)> = candidates
+                .into_iter()
+                .filter_map(|adt_name| {
+                    adt_score(term, adt_name, adts, MAX_SCORE_DEPTH)
+                        .map(|score| (adt_name, score))
+                })
+                .collect();
+            candidates.sort_by(|a, b| b.1.cmp(&a.1));
+
+            // A constructor without fields and with tag 0 is most likely an
+            // empty `List` or `String`, rather than the first constructor of
+            // a user ADT
+            if matches!(split_ctr(term), Some((CtrId::Tag(0), fields)) if fields.is_empty())
+            {
+                candidates.sort_by_key(|(adt_name, _)| {
+                    NULLARY_BUILTINS
+                        .iter()
+                        .position(|builtin| builtin == adt_name)
+                        .unwrap_or(NULLARY_BUILTINS.len())
+                });
+            }
+
+            for (adt_name, _) in candidates {
+                if let Some(obj) =
+                    from_term_into_adt_py(py, term, adt_name, adts)?
+                {
+                    return Ok(obj);
+                }
+            }
+
+            Ok(Term::new(term.clone()).into_py(py))
+        }
+    }
+}
+
+/// The builtin ADTs preferred for constructors without fields and with tag 0,
+/// in order
+const NULLARY_BUILTINS: [&str; 3] =
+    [builtins::LIST, builtins::STRING, builtins::MAP];
+
+/// How many levels of recursive fields are inspected when choosing the ADT of a term
+const MAX_SCORE_DEPTH: usize = 8;
+
+/// Scores how likely a term is to be a value of the given ADT
+///
+/// Every recursive field that is itself a constructor of the same ADT, up to `depth`
+/// levels, adds to the score.
+///
+/// # Returns
+///
+/// The score, or `None` if the term can't be a value of the ADT.
+fn adt_score(
+    term: &BTerm,
+    adt_name: &str,
+    adts: &Adts,
+    depth: usize,
+) -> Option<usize> {
+    let ctrs = adts.adts.get(adt_name)?;
+
+    let is_list = adt_name == builtins::LIST;
+    let is_string = adt_name == builtins::STRING;
+
+    match term {
+        BTerm::List { .. } if is_list => return Some(1),
+        BTerm::Str { .. } if is_string => return Some(1),
+        _ => {}
+    }
+
+    let (ctr, fields) = split_ctr_of(term, ctrs)?;
+
+    let mut score = match split_ctr(term) {
+        Some((CtrId::Name(_), _)) => 1,
+        _ => 0,
+    };
+
+    for (index, field) in fields.into_iter().enumerate() {
+        if is_string && index == 0 {
+            if let BTerm::Num { val: Num::U24(_) } = field {
+            } else {
+                return None;
+            }
+        }
+
+        if ctr.is_recursive(index) && depth > 0 {
+            score += 1 + adt_score(field, adt_name, adts, depth - 1)?;
+        }
+    }
+
+    Some(score)
+}
+
+/// Converts a Bend term into a Python object of the given ADT
+///
+/// # Returns
+///
+/// `Ok(None)` if the term can't be a value of the ADT.
+fn from_term_into_adt_py(
+    py: Python,
+    term: &BTerm,
+    adt_name: &str,
+    adts: &Adts,
+) -> PyResult<Option<PyObject>> {
+    if adt_name == builtins::LIST || adt_name == builtins::STRING {
+        return from_cons_into_py(py, term, adt_name, adts);
+    }
+
+    if adt_name == builtins::MAP {
+        return from_map_into_py(py, term, adts);
+    }
+
+    let Some(ctrs) = adts.adts.get(adt_name) else {
+        return Ok(None);
+    };
+
+    let Some((ctr, fields)) = split_ctr_of(term, ctrs) else {
+        return Ok(None);
+    };
+
+    // `Maybe/None` is the value that `None` is passed as
+    if ctr.full_name() == "Maybe/None" {
+        return Ok(Some(py.None()));
+    }
+
+    let mut args: Vec<PyObject> = vec![];
+
+    for (index, field) in fields.into_iter().enumerate() {
+        if ctr.is_recursive(index) {
+            match from_term_into_adt_py(py, field, adt_name, adts)? {
+                Some(obj) => args.push(obj),
+                None => return Ok(None),
+            }
+        } else {
+            args.push(from_term_into_py(py, field, adts)?);
+        }
+    }
+
+    Ok(Some(ctr.instantiate(py, args)?))
+}
+
+/// Splits a value of the `Maybe` type that `Optional` values become
+///
+/// # Returns
+///
+/// `Some(None)` for `Maybe/None`, `Some(Some(value))` for `Maybe/Some`, and `None` if
+/// the term isn't a `Maybe`.
+pub fn split_maybe(term: &BTerm) -> Option<Option<&BTerm>> {
+    match split_ctr(term)? {
+        (CtrId::Tag(1), fields) if fields.is_empty() => Some(None),
+        (CtrId::Tag(0), fields) if fields.len() == 1 => Some(Some(fields[0])),
+        (CtrId::Name(nam), fields) => match (nam.as_ref(), fields.as_slice()) {
+            ("Maybe/None", []) => Some(None),
+            ("Maybe/Some", [value]) => Some(Some(value)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Splits a term into a constructor of the given ADT and its fields
+fn split_ctr_of<'a, 't>(
+    term: &'t BTerm,
+    ctrs: &'a Ctrs,
+) -> Option<(&'a Ctr, Vec<&'t BTerm>)> {
+    let (id, fields) = split_ctr(term)?;
+
+    let ctr = match id {
+        CtrId::Tag(tag) => ctrs.get_ctr(tag)?,
+        CtrId::Name(nam) => ctrs
+            .ctrs
+            .iter()
+            .find(|ctr| ctr.full_name() == nam.as_ref())?,
+    };
+
+    if ctr.arity() != fields.len() {
+        return None;
+    }
+
+    Some((ctr, fields))
+}
+
+/// Converts a Bend `Map` into a Python `dict`
+///
+/// A value stored at the path `b0, b1, ..., bn` of the tree, where `bi` is 0 for the
+/// left branch and 1 for the right one, has the key `b0 + 2 * b1 + ... + 2^n * bn`.
+/// Erased values are the placeholders of nodes that were only created to reach
+/// deeper keys, so they are skipped. The keys of the resulting dict are sorted.
+///
+/// # Returns
+///
+/// `Ok(None)` if the term isn't a Bend `Map`.
+pub fn from_map_into_py(
+    py: Python,
+    term: &BTerm,
+    adts: &Adts,
+) -> PyResult<Option<PyObject>> {
+    let Some(ctrs) = adts.adts.get(builtins::MAP) else {
+        return Ok(None);
+    };
+
+    let mut entries: Vec<(u32, &BTerm)> = vec![];
+    let mut nodes: Vec<(&BTerm, u32, u32)> = vec![(term, 0, 0)];
+
+    while let Some((node, key, depth)) = nodes.pop() {
+        let Some((_, fields)) = split_ctr_of(node, ctrs) else {
+            return Ok(None);
+        };
+
+        let [value, left, right] = fields.as_slice() else {
+            continue;
+        };
+
+        if depth > 24 {
+            return Ok(None);
+        }
+
+        if !matches!(value, BTerm::Era) {
+            entries.push((key, value));
+        }
+
+        nodes.push((right, key | (1 << depth), depth + 1));
+        nodes.push((left, key, depth + 1));
+    }
+
+    entries.sort_by_key(|(key, _)| *key);
+
+    let dict = PyDict::new_bound(py);
+    for (key, value) in entries {
+        dict.set_item(key, from_term_into_py(py, value, adts)?)?;
+    }
+
+    Ok(Some(dict.into_py(py)))
+}
+
+/// Converts a Bend `List` or `String` into a Python `list` or `str`
+///
+/// Cons lists are walked iteratively, so long lists don't overflow the stack.
+pub fn from_cons_into_py(
+    py: Python,
+    term: &BTerm,
+    adt_name: &str,
+    adts: &Adts,
+) -> PyResult<Option<PyObject>> {
+    let Some(ctrs) = adts.adts.get(adt_name) else {
+        return Ok(None);
+    };
+
+    let is_list = adt_name == builtins::LIST;
+
+    let mut elements: Vec<PyObject> = vec![];
+    let mut text = String::new();
+    let mut cur = term;
+
+    loop {
+        match cur {
+            BTerm::List { els } if is_list => {
+                for el in els {
+                    elements.push(from_term_into_py(py, el, adts)?);
+                }
+                break;
+            }
+            BTerm::Str { val } if !is_list => {
+                text.push_str(val.as_ref());
+                break;
+            }
+            _ => {}
+        }
+
+        let Some((ctr, fields)) = split_ctr_of(cur, ctrs) else {
+            return Ok(None);
+        };
+
+        match fields.as_slice() {
+            [] => break,
+            [head, tail] if ctr.is_recursive(1) => {
+                if is_list {
+                    elements.push(from_term_into_py(py, head, adts)?);
+                } else {
+                    let BTerm::Num { val: Num::U24(chr) } = head else {
+                        return Ok(None);
+                    };
+                    text.push(
+                        char::from_u32(*chr)
+                            .unwrap_or(char::REPLACEMENT_CHARACTER),
+                    );
+                }
+                cur = tail;
+            }
+            _ => return Ok(None),
+        }
+    }
+
+    if is_list {
+        Ok(Some(PyList::new_bound(py, elements).into_py(py)))
+    } else {
+        Ok(Some(text.into_py(py)))
+    }
+}
+
 /// Represents a user-defined Algebraic Data Type (ADT)
 ///
 /// This struct encapsulates a user-defined ADT, providing methods to create and manipulate it.
@@ -249,10 +647,17 @@ impl<'py, 'book> UserAdt<'py, 'book> {
     /// # Notes
     ///
     /// This function attempts to create a UserAdt by matching the Python data's `__ctr_type__`
-    /// attribute with ADT definitions in the provided Bend book.
+    /// attribute with ADT definitions in the provided Bend book. Dataclass instances are
+    /// matched by the name of their class instead.
     pub fn new(data: Bound<'py, PyAny>, book: &'book Book) -> Option<Self> {
+        // `None` is a value of `Maybe`, in the Books that define it
         if data.is_none() {
-            return None;
+            let full_name = Name::new("Maybe/None");
+            return book.ctrs.contains_key(&full_name).then_some(Self {
+                book,
+                data,
+                full_name,
+            });
         }
 
         if let Ok(binding) = data.getattr("__ctr_type__") {
@@ -269,6 +674,22 @@ impl<'py, 'book> UserAdt<'py, 'book> {
             }
         }
 
+        // The dataclasses translated by `bjit` are the constructor with their
+        // name, or `Type/Name` in the union `Type = Name | ...`
+        if data.hasattr("__dataclass_fields__").unwrap_or(false) {
+            let class = data.get_type().name().ok()?.to_string();
+            let full_name = book.ctrs.keys().find(|nam| {
+                nam.as_ref() == class
+                    || nam.rsplit_once('/').is_some_and(|(_, nam)| nam == class)
+            })?;
+
+            return Some(Self {
+                book,
+                data,
+                full_name: full_name.clone(),
+            });
+        }
+
         None
     }
 }
@@ -286,8 +707,14 @@ impl<'py, 'book> UserAdt<'py, 'book> {
 impl<'py, 'book> BendType for UserAdt<'py, 'book> {
     fn to_bend(&self) -> super::BendResult {
         let binding = self.full_name.to_string();
-        let name = binding.split("/").next().unwrap();
-        let adt = self.book.adts.get(&Name::new(name)).unwrap();
+        let name = binding.split('/').next().unwrap_or_default();
+
+        let Some(adt) = self.book.adts.get(&Name::new(name)) else {
+            return Err(ArgumentConversionError::new_err(format!(
+                "Could not find the ADT of constructor {}",
+                self.full_name
+            )));
+        };
 
         for (nam, fields) in adt.ctrs.iter() {
             if nam.to_string() == self.full_name.to_string() {
@@ -300,23 +727,21 @@ impl<'py, 'book> BendType for UserAdt<'py, 'book> {
 
                     let attr = self
                         .data
-                        .getattr(PyString::new_bound(py, attr_nam.as_ref()))
-                        .unwrap();
-
-                    if let Some(t) = extract_type_raw(attr.clone()) {
-                        adt_fields.push(t.to_bend().unwrap());
-                    } else if let Some(adt) = UserAdt::new(attr, self.book) {
-                        let new_adt = adt.to_bend();
-                        adt_fields.push(new_adt.unwrap());
-                    } else {
-                        let field_name = nam.split('/').nth(0).unwrap();
+                        .getattr(PyString::new_bound(py, attr_nam.as_ref()))?;
 
-                        let new_ctr = self
-                            .book
-                            .adts
-                            .get(&Name::new(field_name.to_string()));
+                    // `None` fields are the constructors without fields of
+                    // the same type, like the leaves of a tree
+                    let leaf = attr.is_none()
+                        && adt.ctrs.values().any(|fields| fields.is_empty());
 
-                        for c in new_ctr.unwrap().ctrs.iter() {
+                    if let Some(t) = extract_type_raw(attr.clone())? {
+                        adt_fields.push(t.to_bend()?);
+                    } else if let Some(adt) =
+                        UserAdt::new(attr, self.book).filter(|_| !leaf)
+                    {
+                        adt_fields.push(adt.to_bend()?);
+                    } else {
+                        for c in adt.ctrs.iter() {
                             if c.1.is_empty() {
                                 adt_fields.push(imp::Expr::Ctr {
                                     name: c.0.clone(),
@@ -335,6 +760,195 @@ impl<'py, 'book> BendType for UserAdt<'py, 'book> {
                 });
             }
         }
-        Err(PyErr::fetch(self.data.py()))
+
+        Err(ArgumentConversionError::new_err(format!(
+            "Could not find constructor {}",
+            self.full_name
+        )))
+    }
+}
+
+#[cfg(test)]
+mod user_adt_tests {
+    use std::fs::File;
+    use std::path::Path;
+
+    use pyo3::types::PyListMethods;
+
+    use super::*;
+    use crate::errors::ArgumentConversionError;
+    use crate::types::book::{BendRuntime, Book as BendaBook};
+    use crate::{benda_ffi, load_book};
+
+    fn run_bend_book(code: &str) -> (BTerm, BendaBook) {
+        let path = Path::new("bend.tmp");
+        let _ = File::create_new(path);
+
+        let mut book = bend::fun::load_book::do_parse_book(
+            code,
+            path,
+            bend::fun::Book::builtins(),
+        )
+        .unwrap();
+
+        let res = benda_ffi::run(&book, BendRuntime::Rust.to_string().as_str());
+
+        match res {
+            Ok(Some((term, _, _))) => {
+                (term, BendaBook::new(&mut book).unwrap())
+            }
+            _ => panic!("Could not get result from HVM"),
+        }
+    }
+
+    #[test]
+    fn list_into_py() {
+        pyo3::prepare_freethreaded_python();
+
+        let (term, book) = run_bend_book("main = [1, 2, 3]");
+
+        Python::with_gil(|py| {
+            let obj = from_term_into_py(py, &term, &book.adts).unwrap();
+            let list = obj.downcast_bound::<PyList>(py).unwrap();
+            let items: Vec<u32> = list.extract().unwrap();
+
+            assert_eq!(items, vec![1, 2, 3]);
+            assert_eq!(list.len(), 3);
+        });
+    }
+
+    #[test]
+    fn string_into_py() {
+        pyo3::prepare_freethreaded_python();
+
+        let (term, book) = run_bend_book("main = (\"benda\", 1)");
+
+        Python::with_gil(|py| {
+            let obj = from_term_into_py(py, &term, &book.adts).unwrap();
+            let (text, num): (String, u32) = obj.extract(py).unwrap();
+
+            assert_eq!(text, "benda");
+            assert_eq!(num, 1);
+        });
+    }
+
+    #[test]
+    fn map_into_py() {
+        pyo3::prepare_freethreaded_python();
+
+        let (term, book) = run_bend_book(
+            "def main():\n  return {0: 5, 1: 10, 6: 60, 1000: 7}",
+        );
+
+        Python::with_gil(|py| {
+            let obj = from_term_into_py(py, &term, &book.adts).unwrap();
+            let map: Vec<(u32, u32)> = obj
+                .downcast_bound::<PyDict>(py)
+                .unwrap()
+                .items()
+                .extract()
+                .unwrap();
+
+            assert_eq!(map, vec![(0, 5), (1, 10), (6, 60), (1000, 7)]);
+        });
+    }
+
+    #[test]
+    fn adt_into_py() {
+        pyo3::prepare_freethreaded_python();
+
+        let (term, book) = run_bend_book(
+            "type T = (A x ~y) | (B) | (C z)
+             type U = (Node l r) | (Leaf v)
+             main = (T/A 1 (T/A 2 T/B))",
+        );
+
+        Python::with_gil(|py| {
+            let obj = from_term_into_py(py, &term, &book.adts).unwrap();
+            let obj = obj.bind(py);
+
+            let ctr_type: String =
+                obj.getattr("__ctr_type__").unwrap().extract().unwrap();
+            let inner: String = obj
+                .getattr("y")
+                .unwrap()
+                .getattr("__ctr_type__")
+                .unwrap()
+                .extract()
+                .unwrap();
+
+            assert_eq!(ctr_type, "T/A");
+            assert_eq!(inner, "T/A");
+        });
+    }
+
+    #[test]
+    fn empty_list_into_py() {
+        pyo3::prepare_freethreaded_python();
+
+        let (term, book) = run_bend_book(
+            "type Op = (A) | (B x)
+             main = ([], (Op/B []))",
+        );
+
+        Python::with_gil(|py| {
+            let obj = from_term_into_py(py, &term, &book.adts).unwrap();
+            let (empty, op): (Bound<PyList>, Bound<PyAny>) =
+                obj.extract(py).unwrap();
+
+            let ctr_type: String =
+                op.getattr("__ctr_type__").unwrap().extract().unwrap();
+            let field = op.getattr("x").unwrap();
+
+            assert!(empty.is_empty());
+            assert_eq!(ctr_type, "Op/B");
+            assert!(field.downcast::<PyList>().unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn none_into_py() {
+        pyo3::prepare_freethreaded_python();
+
+        let code = "
+type Maybe = (Some value) | (None)
+get m d = match m { Maybe/Some: m.value; Maybe/None: d }
+id x = x
+main = 0
+";
+
+        Python::with_gil(|py| {
+            let book =
+                load_book(py, PyString::new_bound(py, code).unbind()).unwrap();
+            let defs = book.bind(py).getattr("defs").unwrap();
+
+            let none = defs.getattr("id").unwrap().call1((py.None(),)).unwrap();
+            let got =
+                defs.getattr("get").unwrap().call1((py.None(), 3)).unwrap();
+
+            assert!(none.call_method0("to_python").unwrap().is_none());
+            assert_eq!(
+                got.call_method0("to_python")
+                    .unwrap()
+                    .extract::<u32>()
+                    .unwrap(),
+                3
+            );
+
+            let code = "id x = x\nmain = 0";
+            let book =
+                load_book(py, PyString::new_bound(py, code).unbind()).unwrap();
+            let err = book
+                .bind(py)
+                .getattr("defs")
+                .unwrap()
+                .getattr("id")
+                .unwrap()
+                .call1((py.None(),))
+                .unwrap_err();
+
+            assert!(err.is_instance_of::<ArgumentConversionError>(py));
+            assert!(err.to_string().contains("type Maybe"));
+        });
     }
 }
@@ -11,31 +11,6 @@ def gen_list(n: int, max_value: int = 0xffffff) -> list[int]:
     return result
 
 
-def to_cons_list(xs: list[int]):
-    """Converts a Python list to a Bend cons-list"""
-    result = List.Nil()
-
-    hi = len(xs)
-    if hi == 0:
-        return result
-
-    while hi > 0:
-        hi -= 1
-        result = List.Cons(int(xs[hi]), result)
-
-    return result
-
-def from_cons_list(xs) -> list[int]:
-    """Converts a Bend cons-list to a Python list"""
-    result: list[int] = []
-    while True:
-        match xs:
-            case List.Nil.type():
-                return result
-            case List.Cons.type(value, tail):
-                result.append(value)
-                xs = tail
-
 def print_list(list):
     print("[", end="")
     while True:
@@ -55,9 +30,7 @@ def main():
     expected = sorted(data)
     print("Expected:", expected)
 
-    cons_list = to_cons_list(data)
-
-    sorted_res = book.defs.Sort(cons_list)
+    sorted_res = book.defs.Sort(data)
     sorted_arr = sorted_res.to_adt(book.adts.List)
 
     sum = book.defs.Sum(sorted_res)
@@ -65,6 +38,7 @@ def main():
 
     print("Result:   ", end="")
     print_list(sorted_arr)
+    print("As list: ", sorted_res.to_list())
     print("Sum: ", sum)
 
     #mocked_sorted = mock_sort(cons_list)